@@ -1,30 +1,37 @@
 use choice::querier::{
-    query_balance, query_pair_info_from_pair, query_token_factory_denom_create_fee,
+    query_balance, query_pair_info_from_pair, query_pair_stats, query_pool_info_from_pool,
+    query_token_factory_denom_create_fee,
 };
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Reply, ReplyOn, Response, StdError, StdResult, SubMsg, SubMsgResult, Uint128, WasmMsg,
+    coin, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, SubMsgResult, Uint128,
+    WasmMsg,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
 
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{
-    add_allow_native_token, pair_key, read_pairs, Config, TmpPairInfo, ALLOW_NATIVE_TOKENS, CONFIG,
-    PAIRS, TMP_PAIR_INFO,
+    add_allow_native_token, migrate_pair_keys, pair_key, pool_key, read_fee_discount,
+    read_fee_discounts, read_fee_split, read_pair_status, read_pairs, read_pools, Config,
+    TmpPairInfo, TmpPoolInfo, ALLOW_NATIVE_TOKENS, CONFIG, FEE_DISCOUNTS, PAIRS, PAIR_FEE_SPLITS,
+    PAIR_STATUSES, POOLS, TMP_PAIR_INFO, TMP_POOL_INFO,
 };
 
-use choice::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo, PairInfoRaw};
+use choice::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo, PairInfoRaw, PoolInfo, PoolInfoRaw};
 use choice::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse,
-    PairsResponse, QueryMsg, UpdateConfigParams,
+    ConfigResponse, ExecuteMsg, FeeDiscountResponse, FeeDiscountsResponse, FeeSplit,
+    InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse, PairStats, PairsResponse,
+    PairsStatsResponse, PoolsResponse, QueryMsg, UpdateConfigParams, DEFAULT_COMMISSION_BPS,
+    MAX_COMMISSION_BPS,
 };
 use choice::pair::{
-    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
-    MigrateMsg as PairMigrateMsg,
+    Allowlist, ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
+    LaunchProtection, MigrateMsg as PairMigrateMsg, PairStatus, PairType,
 };
+use choice::pool::{InstantiateMsg as PoolInstantiateMsg, MAX_POOL_ASSETS, MIN_POOL_ASSETS};
 use choice::util::migrate_version;
 use injective_cosmwasm::query::InjectiveQueryWrapper;
 use protobuf::Message;
@@ -34,6 +41,7 @@ const CONTRACT_NAME: &str = "crates.io:choice-factory";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const CREATE_PAIR_REPLY_ID: u64 = 1;
+const CREATE_POOL_REPLY_ID: u64 = 2;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -47,10 +55,14 @@ pub fn instantiate(
     let config = Config {
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         pair_code_id: msg.pair_code_id,
+        pool_code_id: msg.pool_code_id,
 
         burn_address: deps.api.addr_canonicalize(&msg.burn_address)?, // Store burn address
         fee_wallet_address: deps.api.addr_canonicalize(&msg.fee_wallet_address)?, // Store fee wallet address
         proposed_owner: None,
+        fee_tiers: vec![DEFAULT_COMMISSION_BPS],
+        fee_split: FeeSplit::default(),
+        router: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -67,7 +79,31 @@ pub fn execute(
 ) -> StdResult<Response> {
     match msg {
         ExecuteMsg::UpdateConfig { params } => execute_update_config(deps, env, info, params),
-        ExecuteMsg::CreatePair { assets } => execute_create_pair(deps, env, info, assets),
+        ExecuteMsg::CreatePair {
+            assets,
+            commission_bps,
+            pair_type,
+            amp,
+            weights,
+            launch_protection,
+            allowlist,
+        } => execute_create_pair(
+            deps,
+            env,
+            info,
+            assets,
+            commission_bps,
+            pair_type,
+            amp,
+            weights,
+            launch_protection,
+            allowlist,
+        ),
+        ExecuteMsg::CreatePool {
+            asset_infos,
+            commission_bps,
+            amp,
+        } => execute_create_pool(deps, env, info, asset_infos, commission_bps, amp),
         ExecuteMsg::AddNativeTokenDecimals { denom, decimals } => {
             execute_add_native_token_decimals(deps, env, info, denom, decimals)
         }
@@ -82,6 +118,106 @@ pub fn execute(
         }
         ExecuteMsg::AcceptOwnership => execute_accept_ownership(deps, info),
         ExecuteMsg::CancelOwnershipProposal => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::UpdatePairFeeSplit { pair, fee_split } => {
+            execute_update_pair_fee_split(deps, info, pair, fee_split)
+        }
+        ExecuteMsg::UpdatePairStatus { pair, status } => {
+            execute_update_pair_status(deps, info, pair, status)
+        }
+        ExecuteMsg::UpdatePairDynamicFee { pair, dynamic_fee } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_dynamic_fee",
+            pair,
+            PairExecuteMsg::UpdateDynamicFee { dynamic_fee },
+        ),
+        ExecuteMsg::RampAmp {
+            pair,
+            future_amp,
+            future_time,
+        } => execute_forward_to_pair(
+            deps,
+            info,
+            "ramp_amp",
+            pair,
+            PairExecuteMsg::RampAmp {
+                future_amp,
+                future_time,
+            },
+        ),
+        ExecuteMsg::StopRampAmp { pair } => execute_forward_to_pair(
+            deps,
+            info,
+            "stop_ramp_amp",
+            pair,
+            PairExecuteMsg::StopRampAmp {},
+        ),
+        ExecuteMsg::SyncPair { pair } => {
+            execute_forward_to_pair(deps, info, "sync_pair", pair, PairExecuteMsg::Sync {})
+        }
+        ExecuteMsg::UpdatePairSwapHooks { pair, hooks } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_swap_hooks",
+            pair,
+            PairExecuteMsg::UpdateSwapHooks { hooks },
+        ),
+        ExecuteMsg::UpdatePairBatchAuction {
+            pair,
+            batch_auction,
+        } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_batch_auction",
+            pair,
+            PairExecuteMsg::UpdateBatchAuction { batch_auction },
+        ),
+        ExecuteMsg::UpdatePairCircuitBreaker {
+            pair,
+            circuit_breaker,
+        } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_circuit_breaker",
+            pair,
+            PairExecuteMsg::UpdateCircuitBreaker { circuit_breaker },
+        ),
+        ExecuteMsg::ResetPairCircuitBreaker { pair } => execute_forward_to_pair(
+            deps,
+            info,
+            "reset_pair_circuit_breaker",
+            pair,
+            PairExecuteMsg::ResetCircuitBreaker {},
+        ),
+        ExecuteMsg::UpdatePairHoldingPeriod {
+            pair,
+            holding_period,
+        } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_holding_period",
+            pair,
+            PairExecuteMsg::UpdateHoldingPeriod { holding_period },
+        ),
+        ExecuteMsg::UpdatePairAllowlist {
+            pair,
+            add,
+            remove,
+            restrict_swaps,
+        } => execute_forward_to_pair(
+            deps,
+            info,
+            "update_pair_allowlist",
+            pair,
+            PairExecuteMsg::UpdateAllowlist {
+                add,
+                remove,
+                restrict_swaps,
+            },
+        ),
+        ExecuteMsg::UpdateFeeDiscount { address, discount } => {
+            execute_update_fee_discount(deps, info, address, discount)
+        }
     }
 }
 
@@ -103,6 +239,10 @@ pub fn execute_update_config(
         config.pair_code_id = pair_code_id;
     }
 
+    if let Some(pool_code_id) = params.pool_code_id {
+        config.pool_code_id = Some(pool_code_id);
+    }
+
     if let Some(burn_address) = params.burn_address {
         config.burn_address = deps.api.addr_canonicalize(&burn_address)?;
     }
@@ -111,17 +251,49 @@ pub fn execute_update_config(
         config.fee_wallet_address = deps.api.addr_canonicalize(&fee_wallet_address)?;
     }
 
+    if let Some(mut fee_tiers) = params.fee_tiers {
+        if fee_tiers
+            .iter()
+            .any(|fee_tier| *fee_tier == 0 || *fee_tier > MAX_COMMISSION_BPS)
+        {
+            return Err(StdError::generic_err(format!(
+                "fee tiers must be between 1 and {} bps",
+                MAX_COMMISSION_BPS
+            )));
+        }
+
+        fee_tiers.sort_unstable();
+        fee_tiers.dedup();
+        config.fee_tiers = fee_tiers;
+    }
+
+    if let Some(fee_split) = params.fee_split {
+        fee_split.validate()?;
+        config.fee_split = fee_split;
+    }
+
+    if let Some(router) = params.router {
+        config.router = Some(deps.api.addr_canonicalize(&router)?);
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
 // Anyone can execute it to create swap pair
+#[allow(clippy::too_many_arguments)]
 pub fn execute_create_pair(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
     info: MessageInfo,
     assets: [Asset; 2],
+    commission_bps: Option<u16>,
+    pair_type: Option<PairType>,
+    amp: Option<u64>,
+    weights: Option<[Decimal; 2]>,
+    launch_protection: Option<LaunchProtection>,
+    allowlist: Option<Allowlist>,
 ) -> StdResult<Response> {
     let config: Config = CONFIG.load(deps.storage)?;
 
@@ -129,6 +301,11 @@ pub fn execute_create_pair(
         return Err(StdError::generic_err("same asset"));
     }
 
+    let commission_bps = commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS);
+    if !config.fee_tiers.contains(&commission_bps) {
+        return Err(StdError::generic_err("fee tier is not allowed"));
+    }
+
     let asset_1_decimal = match assets[0]
         .info
         .query_decimals(env.contract.address.clone(), &deps.querier)
@@ -155,17 +332,49 @@ pub fn execute_create_pair(
 
     let asset_decimals = [asset_1_decimal, asset_2_decimal];
 
-    let pair_key = pair_key(&raw_infos);
-    if let Ok(Some(_)) = PAIRS.may_load(deps.storage, &pair_key) {
+    let pair_type = pair_type.unwrap_or_default();
+
+    // a pair is the only one of its assets and fee tier, so it cannot be
+    // created with launch limits or an allowlist by anyone able to squat the
+    // slot
+    let is_owner = deps.api.addr_canonicalize(info.sender.as_str())? == config.owner;
+    if launch_protection.is_some() && !is_owner {
+        return Err(StdError::generic_err(
+            "only the owner can create pairs with launch protection",
+        ));
+    }
+    if allowlist.is_some() && !is_owner {
+        return Err(StdError::generic_err(
+            "only the owner can create pairs with an allowlist",
+        ));
+    }
+
+    // one pair per assets and fee tier, whatever its type
+    let pair_key = pair_key(&raw_infos, commission_bps);
+    if let Ok(Some(pair_info)) = PAIRS.may_load(deps.storage, &pair_key) {
+        if pair_info.pair_type != pair_type {
+            return Err(StdError::generic_err(format!(
+                "a {} pair already exists for these assets and fee tier, create the {} pair at another fee tier",
+                pair_info.pair_type, pair_type
+            )));
+        }
         return Err(StdError::generic_err("Pair already exists"));
     }
 
+    // the creator manages the allowlist unless it names a manager
+    let allowlist = allowlist.map(|allowlist| Allowlist {
+        manager: Some(allowlist.manager.unwrap_or_else(|| info.sender.to_string())),
+        ..allowlist
+    });
+
     TMP_PAIR_INFO.save(
         deps.storage,
         &TmpPairInfo {
             pair_key,
             assets: raw_assets,
             asset_decimals,
+            commission_bps,
+            pair_type: pair_type.clone(),
             sender: info.sender,
         },
     )?;
@@ -205,6 +414,124 @@ pub fn execute_create_pair(
                         .api
                         .addr_humanize(&config.fee_wallet_address)?
                         .to_string(), // Pass fee wallet address
+                    commission_bps,
+                    pair_type,
+                    amp,
+                    weights,
+                    launch_protection,
+                    allowlist,
+                })?,
+            }),
+            reply_on: ReplyOn::Success,
+        }))
+}
+
+// Anyone can execute it to create a multi-asset pool
+pub fn execute_create_pool(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    commission_bps: Option<u16>,
+    amp: u64,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool_code_id = config
+        .pool_code_id
+        .ok_or_else(|| StdError::generic_err("pool code id is not set"))?;
+
+    if asset_infos.len() < MIN_POOL_ASSETS || asset_infos.len() > MAX_POOL_ASSETS {
+        return Err(StdError::generic_err(format!(
+            "pools require between {} and {} assets",
+            MIN_POOL_ASSETS, MAX_POOL_ASSETS
+        )));
+    }
+
+    for (i, asset_info) in asset_infos.iter().enumerate() {
+        if asset_infos[i + 1..].contains(asset_info) {
+            return Err(StdError::generic_err("same asset"));
+        }
+    }
+
+    let commission_bps = commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS);
+    if !config.fee_tiers.contains(&commission_bps) {
+        return Err(StdError::generic_err("fee tier is not allowed"));
+    }
+
+    let mut asset_decimals: Vec<u8> = vec![];
+    for asset_info in asset_infos.iter() {
+        match asset_info.query_decimals(env.contract.address.clone(), &deps.querier) {
+            Ok(decimals) => asset_decimals.push(decimals),
+            Err(_) => {
+                return Err(StdError::generic_err(format!(
+                    "asset {} is invalid",
+                    asset_info
+                )))
+            }
+        }
+    }
+
+    let raw_infos = asset_infos
+        .iter()
+        .map(|asset_info| asset_info.to_raw(deps.api))
+        .collect::<StdResult<Vec<AssetInfoRaw>>>()?;
+
+    let pool_key = pool_key(&raw_infos, commission_bps);
+    if let Ok(Some(_)) = POOLS.may_load(deps.storage, &pool_key) {
+        return Err(StdError::generic_err("Pool already exists"));
+    }
+
+    TMP_POOL_INFO.save(
+        deps.storage,
+        &TmpPoolInfo {
+            pool_key,
+            asset_infos: raw_infos,
+            asset_decimals: asset_decimals.clone(),
+            commission_bps,
+            amp,
+        },
+    )?;
+
+    let creation_fee: Vec<Coin> = query_token_factory_denom_create_fee(&deps.querier).unwrap();
+
+    // Check that the sender provided at least the required funds for each coin in the creation fee.
+    for fee in creation_fee.iter() {
+        let coin_opt = info.funds.iter().find(|c| c.denom == fee.denom);
+        if coin_opt.is_none() || coin_opt.unwrap().amount < fee.amount {
+            return Err(StdError::generic_err(format!(
+                "Insufficient funds: require at least {} {}",
+                fee.amount, fee.denom
+            )));
+        }
+    }
+
+    let pool_name = asset_infos
+        .iter()
+        .map(|asset_info| asset_info.to_string())
+        .collect::<Vec<String>>()
+        .join("-");
+
+    Ok(Response::new()
+        .add_attributes(vec![("action", "create_pool"), ("pool", &pool_name)])
+        .add_submessage(SubMsg {
+            id: CREATE_POOL_REPLY_ID,
+            payload: Binary::default(),
+            gas_limit: None,
+            msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id: pool_code_id,
+                funds: creation_fee,
+                admin: Some(env.contract.address.to_string()),
+                label: "pool".to_string(),
+                msg: to_json_binary(&PoolInstantiateMsg {
+                    asset_infos,
+                    asset_decimals,
+                    burn_address: deps.api.addr_humanize(&config.burn_address)?.to_string(),
+                    fee_wallet_address: deps
+                        .api
+                        .addr_humanize(&config.fee_wallet_address)?
+                        .to_string(),
+                    commission_bps,
+                    amp,
                 })?,
             }),
             reply_on: ReplyOn::Success,
@@ -346,9 +673,133 @@ pub fn execute_cancel_ownership_proposal(
         .add_attribute("owner", info.sender))
 }
 
+// Only owner can execute it
+pub fn execute_update_pair_fee_split(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    pair: String,
+    fee_split: Option<FeeSplit>,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair_raw = deps.api.addr_canonicalize(&pair)?;
+    let fee_split_attr = match fee_split {
+        Some(fee_split) => {
+            fee_split.validate()?;
+            PAIR_FEE_SPLITS.save(deps.storage, pair_raw.as_slice(), &fee_split)?;
+            format!("{}/{}", fee_split.burn, fee_split.fee_wallet)
+        }
+        None => {
+            PAIR_FEE_SPLITS.remove(deps.storage, pair_raw.as_slice());
+            "default".to_string()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_pair_fee_split"),
+        ("pair", &pair),
+        ("fee_split", &fee_split_attr),
+    ]))
+}
+
+// Only owner can execute it
+pub fn execute_update_fee_discount(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    address: String,
+    discount: Option<Decimal>,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let discount_attr = match discount {
+        Some(discount) => {
+            if discount > Decimal::one() {
+                return Err(StdError::generic_err("fee discount must not exceed 100%"));
+            }
+            FEE_DISCOUNTS.save(deps.storage, address_raw.as_slice(), &discount)?;
+            discount.to_string()
+        }
+        None => {
+            FEE_DISCOUNTS.remove(deps.storage, address_raw.as_slice());
+            "none".to_string()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_fee_discount"),
+        ("address", &address),
+        ("discount", &discount_attr),
+    ]))
+}
+
+// Only owner can execute it, the pair enforces the status and the factory keeps
+// a copy for the pair queries
+pub fn execute_update_pair_status(
+    mut deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    pair: String,
+    status: PairStatus,
+) -> StdResult<Response> {
+    let res = execute_forward_to_pair(
+        deps.branch(),
+        info,
+        "update_pair_status",
+        pair.clone(),
+        PairExecuteMsg::UpdateStatus {
+            status: status.clone(),
+        },
+    )?;
+
+    let pair_raw = deps.api.addr_canonicalize(&pair)?;
+    if status == PairStatus::Active {
+        PAIR_STATUSES.remove(deps.storage, pair_raw.as_slice());
+    } else {
+        PAIR_STATUSES.save(deps.storage, pair_raw.as_slice(), &status)?;
+    }
+
+    Ok(res.add_attribute("status", status.to_string()))
+}
+
+// Only owner can execute it, used for pair settings that only the factory may change
+pub fn execute_forward_to_pair(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    action: &str,
+    pair: String,
+    msg: PairExecuteMsg,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair = deps.api.addr_validate(&pair)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![("action", action), ("pair", pair.as_str())]))
+}
+
 pub fn execute_migrate_pair(
     deps: DepsMut<InjectiveQueryWrapper>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     contract: String,
     code_id: Option<u64>,
@@ -366,7 +817,9 @@ pub fn execute_migrate_pair(
         Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Migrate {
             contract_addr: contract,
             new_code_id: code_id,
-            msg: to_json_binary(&PairMigrateMsg {})?,
+            msg: to_json_binary(&PairMigrateMsg {
+                factory: Some(env.contract.address.to_string()),
+            })?,
         })),
     )
 }
@@ -374,12 +827,15 @@ pub fn execute_migrate_pair(
 /// This just stores the result for future query
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdResult<Response> {
-    if msg.id != CREATE_PAIR_REPLY_ID {
-        return Err(StdError::generic_err("invalid reply msg"));
+    match msg.id {
+        CREATE_PAIR_REPLY_ID => reply_create_pair(deps, env, msg),
+        CREATE_POOL_REPLY_ID => reply_create_pool(deps, msg),
+        _ => Err(StdError::generic_err("invalid reply msg")),
     }
+}
 
-    let tmp_pair_info = TMP_PAIR_INFO.load(deps.storage)?;
-
+/// Address of the contract instantiated by a submessage
+fn instantiated_contract_address(msg: Reply) -> StdResult<String> {
     let sub_msg_response = match msg.result {
         SubMsgResult::Ok(resp) => resp,
         SubMsgResult::Err(err) => {
@@ -401,7 +857,17 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
             StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
         })?;
 
-    let pair_contract = &res.address;
+    Ok(res.address)
+}
+
+fn reply_create_pair(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    msg: Reply,
+) -> StdResult<Response> {
+    let tmp_pair_info = TMP_PAIR_INFO.load(deps.storage)?;
+
+    let pair_contract = &instantiated_contract_address(msg)?;
     let pair_info = query_pair_info_from_pair(&deps.querier, Addr::unchecked(pair_contract))?;
 
     let raw_infos = [
@@ -423,6 +889,8 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
             asset_decimals: tmp_pair_info.asset_decimals,
             burn_address,       // Add burn address
             fee_wallet_address, // Add fee wallet address
+            commission_bps: tmp_pair_info.commission_bps,
+            pair_type: tmp_pair_info.pair_type,
         },
     )?;
 
@@ -480,17 +948,86 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
         .add_messages(messages))
 }
 
+fn reply_create_pool(deps: DepsMut<InjectiveQueryWrapper>, msg: Reply) -> StdResult<Response> {
+    let tmp_pool_info = TMP_POOL_INFO.load(deps.storage)?;
+
+    let pool_contract = &instantiated_contract_address(msg)?;
+    let pool_info = query_pool_info_from_pool(&deps.querier, Addr::unchecked(pool_contract))?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    POOLS.save(
+        deps.storage,
+        &tmp_pool_info.pool_key,
+        &PoolInfoRaw {
+            asset_infos: tmp_pool_info.asset_infos,
+            contract_addr: deps.api.addr_canonicalize(pool_contract)?,
+            liquidity_token: pool_info.liquidity_token.clone(),
+            asset_decimals: tmp_pool_info.asset_decimals,
+            burn_address: config.burn_address,
+            fee_wallet_address: config.fee_wallet_address,
+            commission_bps: tmp_pool_info.commission_bps,
+            amp: tmp_pool_info.amp,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("pool_contract_addr", pool_contract),
+        ("liquidity_token_addr", &pool_info.liquidity_token),
+    ]))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::Pair { asset_infos } => to_json_binary(&query_pair(deps, asset_infos)?),
-        QueryMsg::Pairs { start_after, limit } => {
-            to_json_binary(&query_pairs(deps, start_after, limit)?)
-        }
+        QueryMsg::Pair {
+            asset_infos,
+            commission_bps,
+        } => to_json_binary(&query_pair(deps, asset_infos, commission_bps)?),
+        QueryMsg::Pairs {
+            start_after,
+            start_after_commission_bps,
+            limit,
+        } => to_json_binary(&query_pairs(
+            deps,
+            start_after,
+            start_after_commission_bps,
+            limit,
+        )?),
+        QueryMsg::Pool {
+            asset_infos,
+            commission_bps,
+        } => to_json_binary(&query_pool(deps, asset_infos, commission_bps)?),
+        QueryMsg::Pools {
+            start_after,
+            start_after_commission_bps,
+            limit,
+        } => to_json_binary(&query_pools(
+            deps,
+            start_after,
+            start_after_commission_bps,
+            limit,
+        )?),
         QueryMsg::NativeTokenDecimals { denom } => {
             to_json_binary(&query_native_token_decimal(deps, denom)?)
         }
+        QueryMsg::FeeSplit { pair } => to_json_binary(&query_fee_split(deps, pair)?),
+        QueryMsg::FeeDiscount { sender, trader } => {
+            to_json_binary(&query_fee_discount(deps, sender, trader)?)
+        }
+        QueryMsg::FeeDiscounts { start_after, limit } => {
+            to_json_binary(&query_fee_discounts(deps, start_after, limit)?)
+        }
+        QueryMsg::PairsStats {
+            start_after,
+            start_after_commission_bps,
+            limit,
+        } => to_json_binary(&query_pairs_stats(
+            deps,
+            start_after,
+            start_after_commission_bps,
+            limit,
+        )?),
     }
 }
 
@@ -499,12 +1036,20 @@ pub fn query_config(deps: Deps<InjectiveQueryWrapper>) -> StdResult<ConfigRespon
     let resp = ConfigResponse {
         owner: deps.api.addr_humanize(&state.owner)?.to_string(),
         pair_code_id: state.pair_code_id,
+        pool_code_id: state.pool_code_id,
 
         burn_address: deps.api.addr_humanize(&state.burn_address)?.to_string(), // Return burn address
         fee_wallet_address: deps
             .api
             .addr_humanize(&state.fee_wallet_address)?
             .to_string(), // Return fee wallet address
+        fee_tiers: state.fee_tiers,
+        fee_split: state.fee_split,
+        router: state
+            .router
+            .map(|router| deps.api.addr_humanize(&router))
+            .transpose()?
+            .map(|router| router.to_string()),
     };
 
     Ok(resp)
@@ -513,25 +1058,35 @@ pub fn query_config(deps: Deps<InjectiveQueryWrapper>) -> StdResult<ConfigRespon
 pub fn query_pair(
     deps: Deps<InjectiveQueryWrapper>,
     asset_infos: [AssetInfo; 2],
+    commission_bps: Option<u16>,
 ) -> StdResult<PairInfo> {
-    let pair_key = pair_key(&[
-        asset_infos[0].to_raw(deps.api)?,
-        asset_infos[1].to_raw(deps.api)?,
-    ]);
+    let pair_key = pair_key(
+        &[
+            asset_infos[0].to_raw(deps.api)?,
+            asset_infos[1].to_raw(deps.api)?,
+        ],
+        commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS),
+    );
     let pair_info: PairInfoRaw = PAIRS.load(deps.storage, &pair_key)?;
-    pair_info.to_normal(deps.api)
+    let fee_split = read_fee_split(deps.storage, &pair_info.contract_addr)?;
+    let status = read_pair_status(deps.storage, &pair_info.contract_addr)?;
+    pair_info.to_normal(deps.api, fee_split, status)
 }
 
 pub fn query_pairs(
     deps: Deps<InjectiveQueryWrapper>,
     start_after: Option<[AssetInfo; 2]>,
+    start_after_commission_bps: Option<u16>,
     limit: Option<u32>,
 ) -> StdResult<PairsResponse> {
     let start_after = if let Some(start_after) = start_after {
-        Some([
-            start_after[0].to_raw(deps.api)?,
-            start_after[1].to_raw(deps.api)?,
-        ])
+        Some((
+            [
+                start_after[0].to_raw(deps.api)?,
+                start_after[1].to_raw(deps.api)?,
+            ],
+            start_after_commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS),
+        ))
     } else {
         None
     };
@@ -542,6 +1097,74 @@ pub fn query_pairs(
     Ok(resp)
 }
 
+pub fn query_pairs_stats(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<[AssetInfo; 2]>,
+    start_after_commission_bps: Option<u16>,
+    limit: Option<u32>,
+) -> StdResult<PairsStatsResponse> {
+    let pairs = query_pairs(deps, start_after, start_after_commission_bps, limit)?
+        .pairs
+        .into_iter()
+        .map(|pair| {
+            // only the totals, epochs are read from the pair itself
+            let stats = query_pair_stats(
+                &deps.querier,
+                deps.api.addr_validate(&pair.contract_addr)?,
+                None,
+                Some(0),
+            )?;
+
+            Ok(PairStats {
+                contract_addr: pair.contract_addr,
+                asset_infos: pair.asset_infos,
+                commission_bps: pair.commission_bps,
+                stats: stats.total,
+            })
+        })
+        .collect::<StdResult<Vec<PairStats>>>()?;
+
+    Ok(PairsStatsResponse { pairs })
+}
+
+pub fn query_pool(
+    deps: Deps<InjectiveQueryWrapper>,
+    asset_infos: Vec<AssetInfo>,
+    commission_bps: Option<u16>,
+) -> StdResult<PoolInfo> {
+    let raw_infos = asset_infos
+        .iter()
+        .map(|asset_info| asset_info.to_raw(deps.api))
+        .collect::<StdResult<Vec<AssetInfoRaw>>>()?;
+    let pool_key = pool_key(&raw_infos, commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS));
+    let pool_info: PoolInfoRaw = POOLS.load(deps.storage, &pool_key)?;
+    let fee_split = read_fee_split(deps.storage, &pool_info.contract_addr)?;
+    pool_info.to_normal(deps.api, fee_split)
+}
+
+pub fn query_pools(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<Vec<AssetInfo>>,
+    start_after_commission_bps: Option<u16>,
+    limit: Option<u32>,
+) -> StdResult<PoolsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some((
+            start_after
+                .iter()
+                .map(|asset_info| asset_info.to_raw(deps.api))
+                .collect::<StdResult<Vec<AssetInfoRaw>>>()?,
+            start_after_commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS),
+        ))
+    } else {
+        None
+    };
+
+    let pools: Vec<PoolInfo> = read_pools(deps.storage, deps.api, start_after, limit)?;
+
+    Ok(PoolsResponse { pools })
+}
+
 pub fn query_native_token_decimal(
     deps: Deps<InjectiveQueryWrapper>,
     denom: String,
@@ -551,19 +1174,60 @@ pub fn query_native_token_decimal(
     Ok(NativeTokenDecimalsResponse { decimals })
 }
 
+pub fn query_fee_split(deps: Deps<InjectiveQueryWrapper>, pair: String) -> StdResult<FeeSplit> {
+    read_fee_split(deps.storage, &deps.api.addr_canonicalize(&pair)?)
+}
+
+/// The sender's discount, or the trader's when larger and the sender is the
+/// router, which swaps on behalf of others
+pub fn query_fee_discount(
+    deps: Deps<InjectiveQueryWrapper>,
+    sender: String,
+    trader: Option<String>,
+) -> StdResult<FeeDiscountResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(&sender)?;
+
+    let mut discount = read_fee_discount(deps.storage, &sender_raw)?;
+    if let Some(trader) = trader {
+        if config.router.as_ref() == Some(&sender_raw) {
+            let trader_raw = deps.api.addr_canonicalize(&trader)?;
+            discount = discount.max(read_fee_discount(deps.storage, &trader_raw)?);
+        }
+    }
+
+    Ok(FeeDiscountResponse { discount })
+}
+
+pub fn query_fee_discounts(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeDiscountsResponse> {
+    let start_after = start_after
+        .map(|address| deps.api.addr_canonicalize(&address))
+        .transpose()?;
+
+    Ok(FeeDiscountsResponse {
+        fee_discounts: read_fee_discounts(deps.storage, deps.api, start_after, limit)?,
+    })
+}
+
 const TARGET_CONTRACT_VERSION: &str = "1.1.2";
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
-    deps: DepsMut<InjectiveQueryWrapper>,
+    mut deps: DepsMut<InjectiveQueryWrapper>,
     _env: Env,
     _msg: MigrateMsg,
 ) -> StdResult<Response> {
     migrate_version(
-        deps,
+        deps.branch(),
         TARGET_CONTRACT_VERSION,
         CONTRACT_NAME,
         CONTRACT_VERSION,
     )?;
 
+    migrate_pair_keys(deps.storage)?;
+
     Ok(Response::default())
 }
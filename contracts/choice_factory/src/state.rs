@@ -1,19 +1,40 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use choice::asset::{AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw};
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Order, StdResult, Storage};
+use choice::asset::{AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw, PoolInfo, PoolInfoRaw};
+use choice::factory::{FeeDiscountInfo, FeeSplit, DEFAULT_COMMISSION_BPS};
+use choice::pair::{PairStatus, PairType};
+use cosmwasm_std::{Addr, Api, CanonicalAddr, Decimal, Order, StdResult, Storage};
 use cw_storage_plus::{Bound, Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
     pub pair_code_id: u64,
+    /// code id of the multi-asset pool contract
+    pub pool_code_id: Option<u64>,
 
     pub burn_address: CanonicalAddr,
     pub fee_wallet_address: CanonicalAddr,
 
     pub proposed_owner: Option<Addr>,
+
+    /// fee tiers (in bps) a pair may be created with, factories from before
+    /// fee tiers allow the default tier
+    #[serde(default = "default_fee_tiers")]
+    pub fee_tiers: Vec<u16>,
+
+    /// commission split used by pairs without an override
+    #[serde(default)]
+    pub fee_split: FeeSplit,
+
+    /// router whose swaps get the fee discount of the trader they are made for
+    #[serde(default)]
+    pub router: Option<CanonicalAddr>,
+}
+
+fn default_fee_tiers() -> Vec<u16> {
+    vec![DEFAULT_COMMISSION_BPS]
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -23,17 +44,107 @@ pub struct TmpPairInfo {
     pub pair_key: Vec<u8>,
     pub assets: [AssetRaw; 2],
     pub asset_decimals: [u8; 2],
+    pub commission_bps: u16,
+    pub pair_type: PairType,
     pub sender: Addr,
 }
 
 pub const TMP_PAIR_INFO: Item<TmpPairInfo> = Item::new("tmp_pair_info");
 pub const PAIRS: Map<&[u8], PairInfoRaw> = Map::new("pair_info");
 
-pub fn pair_key(asset_infos: &[AssetInfoRaw; 2]) -> Vec<u8> {
+/// A pair is unique per asset pair and fee tier
+pub fn pair_key(asset_infos: &[AssetInfoRaw; 2], commission_bps: u16) -> Vec<u8> {
+    let mut asset_infos = asset_infos.to_vec();
+    asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+    [
+        asset_infos[0].as_bytes(),
+        asset_infos[1].as_bytes(),
+        &commission_bps.to_be_bytes(),
+    ]
+    .concat()
+}
+
+/// Moves pairs stored under a key without their fee tier, from before fee
+/// tiers, to the key of their tier
+pub fn migrate_pair_keys(storage: &mut dyn Storage) -> StdResult<()> {
+    let moved = PAIRS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((key, pair_info)) => {
+                *key != pair_key(&pair_info.asset_infos, pair_info.commission_bps)
+            }
+            Err(_) => true,
+        })
+        .collect::<StdResult<Vec<(Vec<u8>, PairInfoRaw)>>>()?;
+
+    for (key, pair_info) in moved {
+        PAIRS.remove(storage, &key);
+        PAIRS.save(
+            storage,
+            &pair_key(&pair_info.asset_infos, pair_info.commission_bps),
+            &pair_info,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TmpPoolInfo {
+    pub pool_key: Vec<u8>,
+    pub asset_infos: Vec<AssetInfoRaw>,
+    pub asset_decimals: Vec<u8>,
+    pub commission_bps: u16,
+    pub amp: u64,
+}
+
+pub const TMP_POOL_INFO: Item<TmpPoolInfo> = Item::new("tmp_pool_info");
+pub const POOLS: Map<&[u8], PoolInfoRaw> = Map::new("pool_info");
+
+/// A pool is unique per set of assets and fee tier. Each asset is length
+/// prefixed, so that keys of pools with different asset counts can't collide
+pub fn pool_key(asset_infos: &[AssetInfoRaw], commission_bps: u16) -> Vec<u8> {
     let mut asset_infos = asset_infos.to_vec();
     asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
 
-    [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()].concat()
+    let mut key = vec![];
+    for asset_info in asset_infos.iter() {
+        key.extend_from_slice(&(asset_info.as_bytes().len() as u16).to_be_bytes());
+        key.extend_from_slice(asset_info.as_bytes());
+    }
+    key.extend_from_slice(&commission_bps.to_be_bytes());
+
+    key
+}
+
+// key : pair contract / value: commission split override
+pub const PAIR_FEE_SPLITS: Map<&[u8], FeeSplit> = Map::new("pair_fee_split");
+
+/// The pair's override if one is set, otherwise the factory default
+pub fn read_fee_split(storage: &dyn Storage, pair: &CanonicalAddr) -> StdResult<FeeSplit> {
+    match PAIR_FEE_SPLITS.may_load(storage, pair.as_slice())? {
+        Some(fee_split) => Ok(fee_split),
+        None => Ok(CONFIG.load(storage)?.fee_split),
+    }
+}
+
+// key : trader address / value: share of the swap commission waived
+pub const FEE_DISCOUNTS: Map<&[u8], Decimal> = Map::new("fee_discount");
+
+pub fn read_fee_discount(storage: &dyn Storage, address: &CanonicalAddr) -> StdResult<Decimal> {
+    Ok(FEE_DISCOUNTS
+        .may_load(storage, address.as_slice())?
+        .unwrap_or_default())
+}
+
+// key : pair contract / value: trading status, pairs without an entry are active
+pub const PAIR_STATUSES: Map<&[u8], PairStatus> = Map::new("pair_status");
+
+pub fn read_pair_status(storage: &dyn Storage, pair: &CanonicalAddr) -> StdResult<PairStatus> {
+    Ok(PAIR_STATUSES
+        .may_load(storage, pair.as_slice())?
+        .unwrap_or_default())
 }
 
 // settings for pagination
@@ -42,7 +153,7 @@ const DEFAULT_LIMIT: u32 = 10;
 pub fn read_pairs(
     storage: &dyn Storage,
     api: &dyn Api,
-    start_after: Option<[AssetInfoRaw; 2]>,
+    start_after: Option<([AssetInfoRaw; 2], u16)>,
     limit: Option<u32>,
 ) -> StdResult<Vec<PairInfo>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
@@ -53,26 +164,71 @@ pub fn read_pairs(
         .take(limit)
         .map(|item| {
             let (_, v) = item?;
-            v.to_normal(api)
+            v.to_normal(
+                api,
+                read_fee_split(storage, &v.contract_addr)?,
+                read_pair_status(storage, &v.contract_addr)?,
+            )
         })
         .collect::<StdResult<Vec<PairInfo>>>()
 }
 
 // this will set the first key after the provided key, by appending a 1 byte
-fn calc_range_start(start_after: Option<[AssetInfoRaw; 2]>) -> Option<Vec<u8>> {
-    start_after.map(|asset_infos| {
-        let mut asset_infos = asset_infos.to_vec();
-        asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
-
-        let mut v = [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()]
-            .concat()
-            .as_slice()
-            .to_vec();
+fn calc_range_start(start_after: Option<([AssetInfoRaw; 2], u16)>) -> Option<Vec<u8>> {
+    start_after.map(|(asset_infos, commission_bps)| {
+        let mut v = pair_key(&asset_infos, commission_bps);
         v.push(1);
         v
     })
 }
 
+pub fn read_pools(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<(Vec<AssetInfoRaw>, u16)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PoolInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|(asset_infos, commission_bps)| {
+            let mut v = pool_key(&asset_infos, commission_bps);
+            v.push(1);
+            v
+        })
+        .map(Bound::ExclusiveRaw);
+
+    POOLS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, v) = item?;
+            v.to_normal(api, read_fee_split(storage, &v.contract_addr)?)
+        })
+        .collect::<StdResult<Vec<PoolInfo>>>()
+}
+
+pub fn read_fee_discounts(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<FeeDiscountInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|address| Bound::ExclusiveRaw(address.to_vec()));
+
+    FEE_DISCOUNTS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, discount) = item?;
+            Ok(FeeDiscountInfo {
+                address: api.addr_humanize(&address.into())?.to_string(),
+                discount,
+            })
+        })
+        .collect()
+}
+
 // key : asset info / value: decimals
 pub const ALLOW_NATIVE_TOKENS: Map<&[u8], u8> = Map::new("allow_native_token");
 pub fn add_allow_native_token(
@@ -1,27 +1,38 @@
-use crate::contract::{execute, execute_add_native_token_decimals, instantiate, query, reply};
+use crate::contract::{
+    execute, execute_add_native_token_decimals, instantiate, migrate, query, reply,
+};
 use choice::mock_querier::{mock_dependencies, WasmMockQuerier};
 use injective_cosmwasm::InjectiveQueryWrapper;
 
-use crate::state::{pair_key, Config, TmpPairInfo, CONFIG, TMP_PAIR_INFO};
+use crate::state::{
+    pair_key, pool_key, Config, TmpPairInfo, TmpPoolInfo, CONFIG, PAIRS, TMP_PAIR_INFO,
+    TMP_POOL_INFO,
+};
 
 use crate::response::MsgInstantiateContractResponse;
-use choice::asset::{Asset, AssetInfo, PairInfo};
+use choice::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw, PoolInfo};
 use choice::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, NativeTokenDecimalsResponse, QueryMsg,
-    UpdateConfigParams,
+    ConfigResponse, ExecuteMsg, FeeDiscountInfo, FeeDiscountResponse, FeeDiscountsResponse,
+    FeeSplit, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse, PairStats, PairsResponse,
+    PairsStatsResponse, PoolsResponse, QueryMsg, UpdateConfigParams, DEFAULT_COMMISSION_BPS,
 };
 use choice::pair::{
-    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
-    MigrateMsg as PairMigrateMsg,
+    Allowlist, BatchAuction, CircuitBreaker, DynamicFee, ExecuteMsg as PairExecuteMsg,
+    HoldingPeriod, InstantiateMsg as PairInstantiateMsg, LaunchProtection,
+    MigrateMsg as PairMigrateMsg, PairStatus, PairType, StatsResponse, TradingStats,
 };
+use choice::pool::InstantiateMsg as PoolInstantiateMsg;
 use cosmwasm_std::testing::{message_info, mock_env, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, coin, coins, from_json, to_json_binary, Api, Binary, Coin, CosmosMsg, MsgResponse,
-    OwnedDeps, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128,
-    WasmMsg,
+    attr, coin, coins, from_json, to_json_binary, to_json_vec, Addr, Api, Binary, CanonicalAddr,
+    Coin, CosmosMsg, Decimal, Deps, MsgResponse, OwnedDeps, Reply, ReplyOn, Response, StdError,
+    Storage, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
 };
+use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::Item;
 use protobuf::Message;
+use serde::{Deserialize, Serialize};
 
 #[test]
 fn proper_initialization() {
@@ -29,6 +40,7 @@ fn proper_initialization() {
 
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
     };
@@ -59,6 +71,7 @@ fn update_config() {
     // Instantiate the contract with initial config
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
     };
@@ -71,8 +84,12 @@ fn update_config() {
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     let update_params = UpdateConfigParams {
         pair_code_id: Some(100u64),
+        pool_code_id: None,
         burn_address: None,
         fee_wallet_address: None,
+        fee_tiers: None,
+        fee_split: None,
+        router: None,
     };
     let msg = ExecuteMsg::UpdateConfig {
         params: update_params,
@@ -92,8 +109,12 @@ fn update_config() {
     let info = message_info(&deps.api.addr_make("addr0001"), &[]);
     let update_params = UpdateConfigParams {
         pair_code_id: None,
+        pool_code_id: None,
         burn_address: None,
         fee_wallet_address: None,
+        fee_tiers: None,
+        fee_split: None,
+        router: None,
     };
     let msg = ExecuteMsg::UpdateConfig {
         params: update_params,
@@ -113,6 +134,7 @@ fn init(
 
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: Some(654u64),
         burn_address: mock_api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: mock_api.addr_make("feeaddr0000").to_string(), // New field
     };
@@ -160,6 +182,12 @@ fn create_pair() {
 
     let msg = ExecuteMsg::CreatePair {
         assets: assets.clone(),
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
@@ -203,6 +231,12 @@ fn create_pair() {
                     asset_decimals: [6u8, 8u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // Add burn address
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // Add fee wallet address
+                    commission_bps: 30,
+                    pair_type: PairType::ConstantProduct,
+                    amp: None,
+                    weights: None,
+                    launch_protection: None,
+                    allowlist: None,
                 })
                 .unwrap(),
                 code_id: 321u64,
@@ -231,9 +265,11 @@ fn create_pair() {
         TMP_PAIR_INFO.load(&deps.storage).unwrap(),
         TmpPairInfo {
             assets: raw_assets,
-            pair_key: pair_key(&raw_infos),
+            pair_key: pair_key(&raw_infos, 30),
             sender: deps.api.addr_make("addr0000"),
-            asset_decimals: [6u8, 8u8]
+            asset_decimals: [6u8, 8u8],
+            commission_bps: 30,
+            pair_type: PairType::ConstantProduct,
         }
     );
 }
@@ -273,6 +309,12 @@ fn create_pair_native_token_and_ibc_token() {
 
     let msg = ExecuteMsg::CreatePair {
         assets: assets.clone(),
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
@@ -310,6 +352,12 @@ fn create_pair_native_token_and_ibc_token() {
                     asset_decimals: [6u8, 6u8],
                     burn_address: mock_api.addr_make("burnaddr0000").to_string(), // Add burn address
                     fee_wallet_address: mock_api.addr_make("feeaddr0000").to_string(), // Add fee wallet address
+                    commission_bps: 30,
+                    pair_type: PairType::ConstantProduct,
+                    amp: None,
+                    weights: None,
+                    launch_protection: None,
+                    allowlist: None,
                 })
                 .unwrap(),
                 code_id: 321u64,
@@ -338,9 +386,11 @@ fn create_pair_native_token_and_ibc_token() {
         TMP_PAIR_INFO.load(&deps.storage).unwrap(),
         TmpPairInfo {
             assets: raw_assets,
-            pair_key: pair_key(&raw_infos),
+            pair_key: pair_key(&raw_infos, 30),
             sender: mock_api.addr_make("addr0000"),
-            asset_decimals: [6u8, 6u8]
+            asset_decimals: [6u8, 6u8],
+            commission_bps: 30,
+            pair_type: PairType::ConstantProduct,
         }
     );
 }
@@ -365,7 +415,15 @@ fn fail_to_create_same_pair() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -399,7 +457,15 @@ fn fail_to_create_pair_with_unknown_denom() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -433,7 +499,15 @@ fn fail_to_create_pair_with_unknown_token() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -457,6 +531,7 @@ fn reply_only_create_pair() {
                     .addr_canonicalize(&deps.api.addr_make("owner0000").to_string())
                     .unwrap(),
                 pair_code_id: 321u64,
+                pool_code_id: None,
                 burn_address: deps
                     .api
                     .addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string())
@@ -466,6 +541,9 @@ fn reply_only_create_pair() {
                     .addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string())
                     .unwrap(),
                 proposed_owner: None,
+                fee_tiers: vec![30],
+                fee_split: FeeSplit::default(),
+                router: None,
             },
         )
         .unwrap();
@@ -509,7 +587,7 @@ fn reply_only_create_pair() {
         assets[1].info.to_raw(deps.as_ref().api).unwrap(),
     ];
 
-    let pair_key = pair_key(&raw_infos);
+    let pair_key = pair_key(&raw_infos, 30);
     TMP_PAIR_INFO
         .save(
             &mut deps.storage,
@@ -518,6 +596,8 @@ fn reply_only_create_pair() {
                 pair_key,
                 sender: deps.api.addr_make("addr0000"),
                 asset_decimals: [8u8, 8u8],
+                commission_bps: 30,
+                pair_type: PairType::ConstantProduct,
             },
         )
         .unwrap();
@@ -564,6 +644,10 @@ fn reply_only_create_pair() {
                 asset_decimals: [8u8, 8u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_bps: 30,
+                pair_type: PairType::ConstantProduct,
+                fee_split: FeeSplit::default(),
+                status: PairStatus::Active,
             },
         )],
         &[],
@@ -595,6 +679,7 @@ fn reply_create_pair_with_provide() {
                     .addr_canonicalize(&deps.api.addr_make("owner0000").to_string())
                     .unwrap(),
                 pair_code_id: 321u64,
+                pool_code_id: None,
                 burn_address: deps
                     .api
                     .addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string())
@@ -604,6 +689,9 @@ fn reply_create_pair_with_provide() {
                     .addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string())
                     .unwrap(),
                 proposed_owner: None,
+                fee_tiers: vec![30],
+                fee_split: FeeSplit::default(),
+                router: None,
             },
         )
         .unwrap();
@@ -644,7 +732,7 @@ fn reply_create_pair_with_provide() {
         assets[1].info.to_raw(deps.as_ref().api).unwrap(),
     ];
 
-    let pair_key = pair_key(&raw_infos);
+    let pair_key = pair_key(&raw_infos, 30);
     TMP_PAIR_INFO
         .save(
             &mut deps.storage,
@@ -653,6 +741,8 @@ fn reply_create_pair_with_provide() {
                 pair_key,
                 sender: deps.api.addr_make("addr0000"),
                 asset_decimals: [18u8, 8u8],
+                commission_bps: 30,
+                pair_type: PairType::ConstantProduct,
             },
         )
         .unwrap();
@@ -699,6 +789,10 @@ fn reply_create_pair_with_provide() {
                 asset_decimals: [18u8, 8u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_bps: 30,
+                pair_type: PairType::ConstantProduct,
+                fee_split: FeeSplit::default(),
+                status: PairStatus::Active,
             },
         )],
         &[("inj".to_string(), 18u8)],
@@ -934,7 +1028,10 @@ fn normal_migrate_pair() {
         Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Migrate {
             contract_addr: "contract0000".to_string(),
             new_code_id: 123u64,
-            msg: to_json_binary(&PairMigrateMsg {}).unwrap(),
+            msg: to_json_binary(&PairMigrateMsg {
+                factory: Some(MOCK_CONTRACT_ADDR.to_string()),
+            })
+            .unwrap(),
         })),
     );
 }
@@ -956,7 +1053,10 @@ fn normal_migrate_pair_with_none_code_id_will_config_code_id() {
         Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Migrate {
             contract_addr: "contract0000".to_string(),
             new_code_id: 321u64,
-            msg: to_json_binary(&PairMigrateMsg {}).unwrap(),
+            msg: to_json_binary(&PairMigrateMsg {
+                factory: Some(MOCK_CONTRACT_ADDR.to_string()),
+            })
+            .unwrap(),
         })),
     );
 }
@@ -997,6 +1097,7 @@ fn test_execute_add_native_token_decimals_factory() {
                     .addr_canonicalize(&deps.api.addr_make("owner0000").to_string())
                     .unwrap(),
                 pair_code_id: 321u64,
+                pool_code_id: None,
                 burn_address: deps
                     .api
                     .addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string())
@@ -1006,6 +1107,9 @@ fn test_execute_add_native_token_decimals_factory() {
                     .addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string())
                     .unwrap(),
                 proposed_owner: None,
+                fee_tiers: vec![30],
+                fee_split: FeeSplit::default(),
+                router: None,
             },
         )
         .unwrap();
@@ -1069,6 +1173,7 @@ fn propose_and_accept_ownership() {
     // Instantiate contract
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
     };
@@ -1126,6 +1231,7 @@ fn unauthorized_propose_new_owner() {
     // Instantiate contract
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
     };
@@ -1158,6 +1264,7 @@ fn unauthorized_accept_ownership() {
     // Instantiate contract
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
     };
@@ -1202,6 +1309,7 @@ fn test_cancel_ownership_proposal() {
     // Instantiate
     let msg = InstantiateMsg {
         pair_code_id: 321u64,
+        pool_code_id: None,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
     };
@@ -1228,3 +1336,1786 @@ fn test_cancel_ownership_proposal() {
     let config = CONFIG.load(deps.as_ref().storage).unwrap();
     assert_eq!(config.proposed_owner, None);
 }
+
+#[test]
+fn update_config_fee_tiers() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.fee_tiers, vec![30u16]);
+
+    // tiers are stored sorted and deduplicated
+    let msg = ExecuteMsg::UpdateConfig {
+        params: UpdateConfigParams {
+            pair_code_id: None,
+            pool_code_id: None,
+            burn_address: None,
+            fee_wallet_address: None,
+            fee_tiers: Some(vec![100u16, 5u16, 30u16, 5u16]),
+            fee_split: None,
+            router: None,
+        },
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.fee_tiers, vec![5u16, 30u16, 100u16]);
+
+    // zero and oversized tiers are rejected
+    for fee_tiers in [vec![0u16, 30u16], vec![1_001u16]] {
+        let msg = ExecuteMsg::UpdateConfig {
+            params: UpdateConfigParams {
+                pair_code_id: None,
+                pool_code_id: None,
+                burn_address: None,
+                fee_wallet_address: None,
+                fee_tiers: Some(fee_tiers),
+                fee_split: None,
+                router: None,
+            },
+        };
+        let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+            StdError::GenericErr { msg, .. } => {
+                assert_eq!(msg, "fee tiers must be between 1 and 1000 bps")
+            }
+            _ => panic!("Must return generic error"),
+        }
+    }
+}
+
+#[test]
+fn create_pair_with_fee_tier() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: UpdateConfigParams {
+            pair_code_id: None,
+            pool_code_id: None,
+            burn_address: None,
+            fee_wallet_address: None,
+            fee_tiers: Some(vec![5u16, 30u16]),
+            fee_split: None,
+            router: None,
+        },
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0001").to_string(),
+            },
+            amount: Uint128::zero(),
+        },
+    ];
+    let creation_fee = vec![Coin {
+        denom: "inj".to_string(),
+        amount: Uint128::from(1_000_000_000_000_000_000u128),
+    }];
+
+    // a tier outside the allowed list is rejected
+    let msg = ExecuteMsg::CreatePair {
+        assets: assets.clone(),
+        commission_bps: Some(100u16),
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &creation_fee);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "fee tier is not allowed"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: assets.clone(),
+        commission_bps: Some(5u16),
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &creation_fee);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let instantiate_msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(instantiate_msg.commission_bps, 5u16);
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+
+    let raw_infos = [
+        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
+        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let tmp_pair_info = TMP_PAIR_INFO.load(&deps.storage).unwrap();
+    assert_eq!(tmp_pair_info.commission_bps, 5u16);
+    assert_eq!(tmp_pair_info.pair_key, pair_key(&raw_infos, 5u16));
+    assert_ne!(pair_key(&raw_infos, 5u16), pair_key(&raw_infos, 30u16));
+}
+
+#[test]
+fn query_pairs_by_fee_tier() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0001").to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+
+    for (commission_bps, pair) in [(5u16, "pair0005"), (30u16, "pair0030")] {
+        PAIRS
+            .save(
+                &mut deps.storage,
+                &pair_key(&raw_infos, commission_bps),
+                &PairInfoRaw {
+                    asset_infos: raw_infos.clone(),
+                    contract_addr: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make(pair).as_str())
+                        .unwrap(),
+                    liquidity_token: format!("factory/{}/lp", deps.api.addr_make(pair)),
+                    asset_decimals: [6u8, 8u8],
+                    burn_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+                        .unwrap(),
+                    fee_wallet_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+                        .unwrap(),
+                    commission_bps,
+                    pair_type: PairType::ConstantProduct,
+                },
+            )
+            .unwrap();
+    }
+
+    // default tier is used when commission_bps is omitted
+    let pair_info: PairInfo = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pair {
+                asset_infos: asset_infos.clone(),
+                commission_bps: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        pair_info.contract_addr,
+        deps.api.addr_make("pair0030").to_string()
+    );
+    assert_eq!(pair_info.commission_bps, 30u16);
+
+    let pair_info: PairInfo = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pair {
+                asset_infos: asset_infos.clone(),
+                commission_bps: Some(5u16),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        pair_info.contract_addr,
+        deps.api.addr_make("pair0005").to_string()
+    );
+
+    // pagination continues with the next tier of the same assets
+    let pairs: PairsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pairs {
+                start_after: Some(asset_infos),
+                start_after_commission_bps: Some(5u16),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(pairs.pairs.len(), 1);
+    assert_eq!(pairs.pairs[0].commission_bps, 30u16);
+}
+
+#[test]
+fn migrate_pairs_to_fee_tier_keys() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    set_contract_version(&mut deps.storage, "crates.io:choice-factory", "1.1.2").unwrap();
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0001").to_string(),
+        },
+    ];
+    let mut raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let pair_info = PairInfoRaw {
+        asset_infos: raw_infos.clone(),
+        contract_addr: deps
+            .api
+            .addr_canonicalize(deps.api.addr_make("pair0000").as_str())
+            .unwrap(),
+        liquidity_token: format!("factory/{}/lp", deps.api.addr_make("pair0000")),
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps
+            .api
+            .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+            .unwrap(),
+        fee_wallet_address: deps
+            .api
+            .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+            .unwrap(),
+        commission_bps: DEFAULT_COMMISSION_BPS,
+        pair_type: PairType::ConstantProduct,
+    };
+
+    // stored before fee tiers and pair types: no commission_bps and
+    // pair_type, keyed by the sorted assets only
+    raw_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+    let legacy_key = [raw_infos[0].as_bytes(), raw_infos[1].as_bytes()].concat();
+    let legacy_value = String::from_utf8(to_json_vec(&pair_info).unwrap())
+        .unwrap()
+        .replace(",\"commission_bps\":30", "")
+        .replace(",\"pair_type\":\"constant_product\"", "");
+    assert!(!legacy_value.contains("pair_type"));
+    deps.storage
+        .set(&PAIRS.key(&legacy_key), legacy_value.as_bytes());
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(PAIRS.may_load(&deps.storage, &legacy_key).unwrap(), None);
+
+    let res: PairInfo = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pair {
+                asset_infos: asset_infos.clone(),
+                commission_bps: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.contract_addr,
+        deps.api.addr_make("pair0000").to_string()
+    );
+    assert_eq!(res.commission_bps, DEFAULT_COMMISSION_BPS);
+
+    // the migrated pair still holds its slot
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: asset_infos[0].clone(),
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: asset_infos[1].clone(),
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "Pair already exists"),
+        _ => panic!("Must return generic error"),
+    }
+}
+#[test]
+fn migrate_config_from_before_fee_tiers() {
+    #[derive(Serialize, Deserialize)]
+    struct BaselineConfig {
+        owner: CanonicalAddr,
+        pair_code_id: u64,
+        burn_address: CanonicalAddr,
+        fee_wallet_address: CanonicalAddr,
+        proposed_owner: Option<Addr>,
+    }
+
+    let mut deps = mock_dependencies(&[]);
+    set_contract_version(&mut deps.storage, "crates.io:choice-factory", "1.1.2").unwrap();
+    let api = deps.api;
+    let canonical = |name: &str| api.addr_canonicalize(api.addr_make(name).as_str()).unwrap();
+    Item::new("config")
+        .save(
+            &mut deps.storage,
+            &BaselineConfig {
+                owner: canonical("addr0000"),
+                pair_code_id: 321u64,
+                burn_address: canonical("burnaddr0000"),
+                fee_wallet_address: canonical("feeaddr0000"),
+                proposed_owner: None,
+            },
+        )
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: deps.api.addr_make("addr0000").to_string(),
+            pair_code_id: 321u64,
+            pool_code_id: None,
+            burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+            fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+            fee_tiers: vec![DEFAULT_COMMISSION_BPS],
+            fee_split: FeeSplit::default(),
+            router: None,
+        }
+    );
+}
+#[test]
+fn update_config_fee_split() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.fee_split, FeeSplit::default());
+
+    let fee_split = FeeSplit {
+        burn: Decimal::percent(10),
+        fee_wallet: Decimal::percent(20),
+    };
+    let msg = ExecuteMsg::UpdateConfig {
+        params: UpdateConfigParams {
+            pair_code_id: None,
+            pool_code_id: None,
+            burn_address: None,
+            fee_wallet_address: None,
+            fee_tiers: None,
+            fee_split: Some(fee_split.clone()),
+            router: None,
+        },
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.fee_split, fee_split);
+
+    // shares above 100% are rejected
+    let msg = ExecuteMsg::UpdateConfig {
+        params: UpdateConfigParams {
+            pair_code_id: None,
+            pool_code_id: None,
+            burn_address: None,
+            fee_wallet_address: None,
+            fee_tiers: None,
+            fee_split: Some(FeeSplit {
+                burn: Decimal::percent(60),
+                fee_wallet: Decimal::percent(50),
+            }),
+            router: None,
+        },
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "fee split shares must not exceed 100%")
+        }
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn update_pair_fee_split() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let other_pair = deps.api.addr_make("pair0001").to_string();
+    let fee_split = FeeSplit {
+        burn: Decimal::zero(),
+        fee_wallet: Decimal::percent(50),
+    };
+
+    // only the owner may override a pair
+    let msg = ExecuteMsg::UpdatePairFeeSplit {
+        pair: pair.clone(),
+        fee_split: Some(fee_split.clone()),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_fee_split"),
+            attr("pair", pair.clone()),
+            attr("fee_split", "0/0.5"),
+        ]
+    );
+
+    let query_fee_split = |deps: Deps<InjectiveQueryWrapper>, pair: &str| -> FeeSplit {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::FeeSplit {
+                    pair: pair.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(query_fee_split(deps.as_ref(), &pair), fee_split);
+    assert_eq!(
+        query_fee_split(deps.as_ref(), &other_pair),
+        FeeSplit::default()
+    );
+
+    // clearing the override falls back to the factory default
+    let msg = ExecuteMsg::UpdatePairFeeSplit {
+        pair: pair.clone(),
+        fee_split: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(query_fee_split(deps.as_ref(), &pair), FeeSplit::default());
+}
+
+#[test]
+fn create_stable_swap_pair() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0001").to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: Some(PairType::StableSwap),
+        amp: Some(100u64),
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let instantiate_msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(instantiate_msg.pair_type, PairType::StableSwap);
+            assert_eq!(instantiate_msg.amp, Some(100u64));
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+
+    let tmp_pair_info = TMP_PAIR_INFO.load(&deps.storage).unwrap();
+    assert_eq!(tmp_pair_info.pair_type, PairType::StableSwap);
+}
+
+#[test]
+fn create_weighted_pair() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let weights = [Decimal::percent(80), Decimal::percent(20)];
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0001").to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: Some(PairType::Weighted),
+        amp: None,
+        weights: Some(weights),
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+    // weights are passed through in the order the assets were given
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let instantiate_msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(instantiate_msg.pair_type, PairType::Weighted);
+            assert_eq!(instantiate_msg.weights, Some(weights));
+            assert_eq!(
+                instantiate_msg.asset_infos[0],
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                }
+            );
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+
+    // a constant product pair holding the slot of the assets and fee tier
+    let tmp_pair_info = TMP_PAIR_INFO.load(&deps.storage).unwrap();
+    let raw_infos = [
+        tmp_pair_info.assets[0].info.clone(),
+        tmp_pair_info.assets[1].info.clone(),
+    ];
+    PAIRS
+        .save(
+            &mut deps.storage,
+            &tmp_pair_info.pair_key,
+            &PairInfoRaw {
+                asset_infos: raw_infos,
+                contract_addr: deps
+                    .api
+                    .addr_canonicalize(deps.api.addr_make("pair0000").as_str())
+                    .unwrap(),
+                liquidity_token: format!("factory/{}/lp", deps.api.addr_make("pair0000")),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps
+                    .api
+                    .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+                    .unwrap(),
+                fee_wallet_address: deps
+                    .api
+                    .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+                    .unwrap(),
+                commission_bps: DEFAULT_COMMISSION_BPS,
+                pair_type: PairType::ConstantProduct,
+            },
+        )
+        .unwrap();
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "a constant_product pair already exists for these assets and fee tier, create the weighted pair at another fee tier"
+        ),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn create_permissioned_pair() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let creator = deps.api.addr_make("addr0000");
+    let allowlist = Allowlist {
+        manager: None,
+        addresses: vec![deps.api.addr_make("addr0001").to_string()],
+        restrict_swaps: true,
+    };
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0001").to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: Some(allowlist.clone()),
+    };
+    let info = message_info(
+        &creator,
+        &[Coin {
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the creator manages the allowlist by default
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let instantiate_msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(
+                instantiate_msg.allowlist,
+                Some(Allowlist {
+                    manager: Some(creator.to_string()),
+                    ..allowlist
+                })
+            );
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+}
+
+#[test]
+fn fail_to_create_allowlisted_pair_with_non_owner() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0001").to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: Some(Allowlist {
+            manager: None,
+            addresses: vec![deps.api.addr_make("addr0001").to_string()],
+            restrict_swaps: true,
+        }),
+    };
+
+    // anyone else could hold the slot of the assets closed to swaps
+    let info = message_info(
+        &deps.api.addr_make("addr0001"),
+        &[Coin {
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128),
+        }],
+    );
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "only the owner can create pairs with an allowlist")
+        }
+        _ => panic!("Must return generic error"),
+    }
+}
+#[test]
+fn ramp_and_stop_ramp_amp() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+
+    let msg = ExecuteMsg::RampAmp {
+        pair: pair.clone(),
+        future_amp: 200u64,
+        future_time: 1_000_000u64,
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "ramp_amp"), attr("pair", pair.clone())]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair.clone(),
+            msg: to_json_binary(&PairExecuteMsg::RampAmp {
+                future_amp: 200u64,
+                future_time: 1_000_000u64,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    let msg = ExecuteMsg::StopRampAmp { pair: pair.clone() };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::StopRampAmp {}).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+fn pool_asset_infos(api: &MockApi) -> Vec<AssetInfo> {
+    vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: "uusdc".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: api.addr_make("asset0001").to_string(),
+        },
+    ]
+}
+
+#[test]
+fn create_pool() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    deps.querier.with_choice_factory(
+        &[],
+        &[("uusd".to_string(), 6u8), ("uusdc".to_string(), 6u8)],
+    );
+
+    deps.querier
+        .with_token_factory_denom_create_fee(&[("inj", Uint128::from(1_000_000u128))]);
+
+    let asset_infos = pool_asset_infos(&deps.api);
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos: asset_infos.clone(),
+        commission_bps: None,
+        amp: 100u64,
+    };
+
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(1_000_000u128, "inj"),
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "create_pool"),
+            attr(
+                "pool",
+                "uusd-uusdc-".to_string() + deps.api.addr_make("asset0001").as_str()
+            )
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg {
+            id: 2,
+            payload: Binary::default(),
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+            msg: WasmMsg::Instantiate {
+                msg: to_json_binary(&PoolInstantiateMsg {
+                    asset_infos: asset_infos.clone(),
+                    asset_decimals: vec![6u8, 6u8, 8u8],
+                    burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                    commission_bps: 30,
+                    amp: 100u64,
+                })
+                .unwrap(),
+                code_id: 654u64,
+                funds: coins(1_000_000u128, "inj"),
+                label: "pool".to_string(),
+                admin: Some(MOCK_CONTRACT_ADDR.to_string()),
+            }
+            .into()
+        }]
+    );
+
+    let tmp_pool_info = TMP_POOL_INFO.load(&deps.storage).unwrap();
+    assert_eq!(tmp_pool_info.asset_decimals, vec![6u8, 6u8, 8u8]);
+    assert_eq!(tmp_pool_info.amp, 100u64);
+
+    // the key doesn't depend on the order of the assets
+    let mut reversed = tmp_pool_info.asset_infos.clone();
+    reversed.reverse();
+    assert_eq!(tmp_pool_info.pool_key, pool_key(&reversed, 30));
+    assert_ne!(
+        tmp_pool_info.pool_key,
+        pool_key(&tmp_pool_info.asset_infos, 10)
+    );
+}
+
+#[test]
+fn fail_to_create_invalid_pool() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    deps.querier.with_choice_factory(
+        &[],
+        &[("uusd".to_string(), 6u8), ("uusdc".to_string(), 6u8)],
+    );
+
+    let asset_infos = pool_asset_infos(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos: asset_infos[..2].to_vec(),
+        commission_bps: None,
+        amp: 100u64,
+    };
+    match execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "pools require between 3 and 8 assets")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos: vec![
+            asset_infos[0].clone(),
+            asset_infos[1].clone(),
+            asset_infos[0].clone(),
+        ],
+        commission_bps: None,
+        amp: 100u64,
+    };
+    match execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "same asset"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos: asset_infos.clone(),
+        commission_bps: Some(25),
+        amp: 100u64,
+    };
+    match execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "fee tier is not allowed"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos: vec![
+            asset_infos[0].clone(),
+            asset_infos[1].clone(),
+            AssetInfo::NativeToken {
+                denom: "uatom".to_string(),
+            },
+        ],
+        commission_bps: None,
+        amp: 100u64,
+    };
+    match execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "asset uatom is invalid"),
+        _ => panic!("Must return generic error"),
+    }
+
+    // pools can't be created before the pool code id is configured
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+    config.pool_code_id = None;
+    CONFIG.save(&mut deps.storage, &config).unwrap();
+
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos,
+        commission_bps: None,
+        amp: 100u64,
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "pool code id is not set"),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn reply_create_pool() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = pool_asset_infos(&deps.api);
+    let raw_infos = asset_infos
+        .iter()
+        .map(|asset_info| asset_info.to_raw(deps.as_ref().api).unwrap())
+        .collect::<Vec<_>>();
+    TMP_POOL_INFO
+        .save(
+            &mut deps.storage,
+            &TmpPoolInfo {
+                pool_key: pool_key(&raw_infos, 30),
+                asset_infos: raw_infos,
+                asset_decimals: vec![6u8, 6u8, 8u8],
+                commission_bps: 30,
+                amp: 100u64,
+            },
+        )
+        .unwrap();
+
+    let pool_info = PoolInfo {
+        asset_infos: asset_infos.clone(),
+        contract_addr: deps.api.addr_make("pool0000").to_string(),
+        liquidity_token: "factory/pool0000/lp".to_string(),
+        asset_decimals: vec![6u8, 6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        fee_split: FeeSplit::default(),
+        amp: 100u64,
+    };
+    deps.querier.with_choice_pools(&[&pool_info]);
+
+    let expected = MsgInstantiateContractResponse {
+        address: deps.api.addr_make("pool0000").to_string(),
+        data: vec![],
+        special_fields: Default::default(),
+    };
+
+    #[allow(deprecated)]
+    let reply_msg = Reply {
+        id: 2,
+        payload: Binary::default(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![MsgResponse {
+                type_url: "".to_string(),
+                value: Binary::from(expected.write_to_bytes().unwrap()),
+            }],
+        }),
+    };
+
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("pool_contract_addr", deps.api.addr_make("pool0000")),
+            attr("liquidity_token_addr", "factory/pool0000/lp"),
+        ]
+    );
+
+    // the pool is found with its assets in any order
+    let mut reversed = asset_infos.clone();
+    reversed.reverse();
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Pool {
+            asset_infos: reversed,
+            commission_bps: None,
+        },
+    )
+    .unwrap();
+    let pool_res: PoolInfo = from_json(&query_res).unwrap();
+    assert_eq!(pool_res, pool_info);
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Pools {
+            start_after: None,
+            start_after_commission_bps: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let pools_res: PoolsResponse = from_json(&query_res).unwrap();
+    assert_eq!(pools_res.pools, vec![pool_info]);
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Pools {
+            start_after: Some(asset_infos.clone()),
+            start_after_commission_bps: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let pools_res: PoolsResponse = from_json(&query_res).unwrap();
+    assert_eq!(pools_res.pools, vec![]);
+
+    // the same pool can't be created twice
+    deps.querier.with_choice_factory(
+        &[],
+        &[("uusd".to_string(), 6u8), ("uusdc".to_string(), 6u8)],
+    );
+    let msg = ExecuteMsg::CreatePool {
+        asset_infos,
+        commission_bps: None,
+        amp: 100u64,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "Pool already exists"),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn update_pair_status() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0001").to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let burn_address = deps.api.addr_make("burnaddr0000").to_string();
+    let fee_wallet_address = deps.api.addr_make("feeaddr0000").to_string();
+    PAIRS
+        .save(
+            &mut deps.storage,
+            &pair_key(&raw_infos, 30),
+            &PairInfoRaw {
+                asset_infos: raw_infos,
+                contract_addr: deps.api.addr_canonicalize(&pair).unwrap(),
+                liquidity_token: "factory/pair0000/lp".to_string(),
+                asset_decimals: [6u8, 8u8],
+                burn_address: deps.api.addr_canonicalize(&burn_address).unwrap(),
+                fee_wallet_address: deps.api.addr_canonicalize(&fee_wallet_address).unwrap(),
+                commission_bps: 30,
+                pair_type: PairType::ConstantProduct,
+            },
+        )
+        .unwrap();
+
+    let msg = ExecuteMsg::UpdatePairStatus {
+        pair: pair.clone(),
+        status: PairStatus::WithdrawOnly,
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // the status is pushed to the pair, which enforces it
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_status"),
+            attr("pair", pair.clone()),
+            attr("status", "withdraw_only"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair.clone(),
+            msg: to_json_binary(&PairExecuteMsg::UpdateStatus {
+                status: PairStatus::WithdrawOnly,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    let query_pair = |deps: Deps<InjectiveQueryWrapper>| -> PairInfo {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::Pair {
+                    asset_infos: asset_infos.clone(),
+                    commission_bps: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(query_pair(deps.as_ref()).status, PairStatus::WithdrawOnly);
+
+    let pairs: PairsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pairs {
+                start_after: None,
+                start_after_commission_bps: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(pairs.pairs[0].status, PairStatus::WithdrawOnly);
+
+    let msg = ExecuteMsg::UpdatePairStatus {
+        pair,
+        status: PairStatus::Active,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(query_pair(deps.as_ref()).status, PairStatus::Active);
+}
+
+#[test]
+fn update_pair_dynamic_fee() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let dynamic_fee = DynamicFee {
+        min_fee_bps: 10,
+        max_fee_bps: 100,
+        volatility_multiplier: Decimal::percent(50),
+        decay_period: 600,
+    };
+
+    let msg = ExecuteMsg::UpdatePairDynamicFee {
+        pair: pair.clone(),
+        dynamic_fee: Some(dynamic_fee.clone()),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_dynamic_fee"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::UpdateDynamicFee {
+                dynamic_fee: Some(dynamic_fee),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn sync_pair() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let msg = ExecuteMsg::SyncPair { pair: pair.clone() };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "sync_pair"), attr("pair", pair.clone())]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::Sync {}).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_pair_swap_hooks() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let hooks = vec![deps.api.addr_make("hook0000").to_string()];
+    let msg = ExecuteMsg::UpdatePairSwapHooks {
+        pair: pair.clone(),
+        hooks: hooks.clone(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_swap_hooks"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::UpdateSwapHooks { hooks }).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_pair_batch_auction() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let batch_auction = Some(BatchAuction {
+        interval_blocks: 1,
+        min_offer_amounts: [Uint128::from(1_000u128); 2],
+    });
+    let msg = ExecuteMsg::UpdatePairBatchAuction {
+        pair: pair.clone(),
+        batch_auction: batch_auction.clone(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_batch_auction"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::UpdateBatchAuction { batch_auction }).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_pair_circuit_breaker() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let circuit_breaker = Some(CircuitBreaker {
+        window: 3_600,
+        max_price_drop: Decimal::percent(10),
+        max_outflow: Decimal::percent(5),
+    });
+    let msg = ExecuteMsg::UpdatePairCircuitBreaker {
+        pair: pair.clone(),
+        circuit_breaker: circuit_breaker.clone(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_circuit_breaker"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair.clone(),
+            msg: to_json_binary(&PairExecuteMsg::UpdateCircuitBreaker { circuit_breaker }).unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    let msg = ExecuteMsg::ResetPairCircuitBreaker { pair: pair.clone() };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::ResetCircuitBreaker {}).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_pair_holding_period() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let holding_period = Some(HoldingPeriod {
+        seconds: 86_400,
+        early_withdrawal_penalty: Some(Decimal::percent(10)),
+    });
+    let msg = ExecuteMsg::UpdatePairHoldingPeriod {
+        pair: pair.clone(),
+        holding_period: holding_period.clone(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_holding_period"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::UpdateHoldingPeriod { holding_period }).unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_pair_allowlist() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let add = vec![deps.api.addr_make("addr0002").to_string()];
+    let msg = ExecuteMsg::UpdatePairAllowlist {
+        pair: pair.clone(),
+        add: add.clone(),
+        remove: vec![],
+        restrict_swaps: Some(true),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_allowlist"),
+            attr("pair", pair.clone())
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::UpdateAllowlist {
+                add,
+                remove: vec![],
+                restrict_swaps: Some(true),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_fee_discount() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let owner = deps.api.addr_make("addr0000");
+    let trader = deps.api.addr_make("addr0002").to_string();
+    let router = deps.api.addr_make("router0000").to_string();
+    let msg = ExecuteMsg::UpdateFeeDiscount {
+        address: trader.clone(),
+        discount: Some(Decimal::percent(50)),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let invalid_msg = ExecuteMsg::UpdateFeeDiscount {
+        address: trader.clone(),
+        discount: Some(Decimal::percent(101)),
+    };
+    let info = message_info(&owner, &[]);
+    match execute(deps.as_mut(), mock_env(), info, invalid_msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "fee discount must not exceed 100%"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let info = message_info(&owner, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_fee_discount"),
+            attr("address", trader.clone()),
+            attr("discount", "0.5"),
+        ]
+    );
+
+    let query_discount = |deps: Deps<InjectiveQueryWrapper>, sender: &str| {
+        let res = query(
+            deps,
+            mock_env(),
+            QueryMsg::FeeDiscount {
+                sender: sender.to_string(),
+                trader: Some(trader.clone()),
+            },
+        )
+        .unwrap();
+        from_json::<FeeDiscountResponse>(&res).unwrap().discount
+    };
+    assert_eq!(query_discount(deps.as_ref(), &trader), Decimal::percent(50));
+
+    // the trader's discount is only passed on by the factory's router
+    assert_eq!(query_discount(deps.as_ref(), &router), Decimal::zero());
+    let info = message_info(&owner, &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        params: UpdateConfigParams {
+            pair_code_id: None,
+            pool_code_id: None,
+            burn_address: None,
+            fee_wallet_address: None,
+            fee_tiers: None,
+            fee_split: None,
+            router: Some(router.clone()),
+        },
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(query_discount(deps.as_ref(), &router), Decimal::percent(50));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::FeeDiscounts {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_json::<FeeDiscountsResponse>(&res)
+            .unwrap()
+            .fee_discounts,
+        vec![FeeDiscountInfo {
+            address: trader.clone(),
+            discount: Decimal::percent(50),
+        }]
+    );
+
+    // removing the entry ends the discount
+    let msg = ExecuteMsg::UpdateFeeDiscount {
+        address: trader.clone(),
+        discount: None,
+    };
+    let info = message_info(&owner, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(query_discount(deps.as_ref(), &trader), Decimal::zero());
+}
+#[test]
+fn query_pairs_stats() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0001").to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+
+    for (commission_bps, pair) in [(5u16, "pair0005"), (30u16, "pair0030")] {
+        let pair_addr = deps.api.addr_make(pair);
+        PAIRS
+            .save(
+                &mut deps.storage,
+                &pair_key(&raw_infos, commission_bps),
+                &PairInfoRaw {
+                    asset_infos: raw_infos.clone(),
+                    contract_addr: deps.api.addr_canonicalize(pair_addr.as_str()).unwrap(),
+                    liquidity_token: format!("factory/{}/lp", pair_addr),
+                    asset_decimals: [6u8, 8u8],
+                    burn_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+                        .unwrap(),
+                    fee_wallet_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+                        .unwrap(),
+                    commission_bps,
+                    pair_type: PairType::ConstantProduct,
+                },
+            )
+            .unwrap();
+
+        deps.querier.with_pair_stats(
+            pair_addr.as_str(),
+            StatsResponse {
+                asset_infos: asset_infos.clone(),
+                total: pair_trading_stats(commission_bps),
+                epochs: vec![],
+            },
+        );
+    }
+
+    let res: PairsStatsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsStats {
+                start_after: None,
+                start_after_commission_bps: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pairs,
+        vec![
+            PairStats {
+                contract_addr: deps.api.addr_make("pair0005").to_string(),
+                asset_infos: asset_infos.clone(),
+                commission_bps: 5,
+                stats: pair_trading_stats(5),
+            },
+            PairStats {
+                contract_addr: deps.api.addr_make("pair0030").to_string(),
+                asset_infos: asset_infos.clone(),
+                commission_bps: 30,
+                stats: pair_trading_stats(30),
+            },
+        ]
+    );
+
+    let res: PairsStatsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsStats {
+                start_after: Some(asset_infos),
+                start_after_commission_bps: Some(5u16),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+    assert_eq!(res.pairs[0].stats, pair_trading_stats(30));
+}
+
+fn pair_trading_stats(commission_bps: u16) -> TradingStats {
+    let amount = Uint128::from(commission_bps as u128);
+    TradingStats {
+        swap_count: commission_bps as u64,
+        volume: [amount * Uint128::from(10_000u128), amount],
+        commission_amount: [amount, Uint128::zero()],
+        burn_amount: [amount, Uint128::zero()],
+        fee_wallet_amount: [amount, Uint128::zero()],
+    }
+}
+#[test]
+fn create_pair_with_launch_protection() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[(
+        "inj",
+        Uint128::from(1_000_000_000_000_000_000u128),
+    )]);
+
+    let launch_protection = LaunchProtection {
+        trading_start: mock_env().block.time.seconds() + 3600,
+        duration: 1800,
+        max_swap_ratio: Decimal::percent(1),
+        cooldown: 60,
+    };
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0001").to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_bps: None,
+        pair_type: None,
+        amp: None,
+        weights: None,
+        launch_protection: Some(launch_protection.clone()),
+        allowlist: None,
+    };
+    let creation_fee = [Coin {
+        denom: "inj".to_string(),
+        amount: Uint128::from(1_000_000_000_000_000_000u128),
+    }];
+
+    // anyone else could hold the slot of the assets closed for a week
+    let info = message_info(&deps.api.addr_make("addr0001"), &creation_fee);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "only the owner can create pairs with launch protection"
+        ),
+        _ => panic!("Must return generic error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &creation_fee);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let instantiate_msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(instantiate_msg.launch_protection, Some(launch_protection));
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+}
@@ -0,0 +1,76 @@
+// Allowlist of permissioned pairs. A pair created with an allowlist only lets
+// the addresses on it provide liquidity, and swap as well with `restrict_swaps`.
+// Both the sender and the recipient of the LP or of the swap have to be on it,
+// and for swaps through the router the trader it forwards as well.
+// Withdrawals stay open, so an address removed from the list can still exit.
+
+use crate::error::ContractError;
+use crate::state::{ALLOWLIST, ALLOWLIST_CONFIG};
+use cosmwasm_std::{Addr, Api, Empty, Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn add(storage: &mut dyn Storage, api: &dyn Api, addresses: &[String]) -> StdResult<()> {
+    for address in addresses {
+        ALLOWLIST.save(storage, &api.addr_validate(address)?, &Empty {})?;
+    }
+
+    Ok(())
+}
+
+pub fn remove(storage: &mut dyn Storage, api: &dyn Api, addresses: &[String]) -> StdResult<()> {
+    for address in addresses {
+        ALLOWLIST.remove(storage, &api.addr_validate(address)?);
+    }
+
+    Ok(())
+}
+
+/// Fails unless all of `accounts` may provide liquidity
+pub fn assert_provide(storage: &dyn Storage, accounts: &[&Addr]) -> Result<(), ContractError> {
+    match ALLOWLIST_CONFIG.may_load(storage)? {
+        Some(_) => assert_listed(storage, accounts),
+        None => Ok(()),
+    }
+}
+
+/// Fails unless all of `accounts` may swap
+pub fn assert_swap(storage: &dyn Storage, accounts: &[&Addr]) -> Result<(), ContractError> {
+    match ALLOWLIST_CONFIG.may_load(storage)? {
+        Some(config) if config.restrict_swaps => assert_listed(storage, accounts),
+        _ => Ok(()),
+    }
+}
+
+pub fn read_addresses(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    ALLOWLIST
+        .keys(
+            storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| Ok(address?.to_string()))
+        .collect()
+}
+
+fn assert_listed(storage: &dyn Storage, accounts: &[&Addr]) -> Result<(), ContractError> {
+    for account in accounts {
+        if !ALLOWLIST.has(storage, account) {
+            return Err(ContractError::NotAllowlisted {
+                address: account.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Frequent batch auction. A pair in batch mode queues its swaps instead of
+// executing them, and anyone can settle the batch once it has been open for
+// the configured blocks. Every order of a batch trades at the same price, so
+// an order placed around another one cannot profit from its price impact.
+//
+// Offers of asset 0 totalling `a` and of asset 1 totalling `b` clear against
+// reserves `x` and `y` at `(y + b) / (x + a)` asset 1 per asset 0. The pool
+// only takes the other side of the net imbalance and its reserves end at
+// `(x + a) * y / (y + b)` and `(y + b) * x / (x + a)`, on the `x * y` curve.
+
+use crate::error::ContractError;
+use crate::state::{Batch, QueuedOrder, BATCH, BATCH_ORDERS};
+use choice::asset::Asset;
+use choice::pair::{BatchAuction, BatchOrder};
+use cosmwasm_std::{Decimal256, Order, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::Bound;
+use std::convert::TryInto;
+
+/// Most swaps a single batch can queue, settlement pays out all of them
+pub const MAX_BATCH_ORDERS: u64 = 50;
+
+/// Most swaps a single sender can queue in a batch
+pub const MAX_SENDER_ORDERS: u64 = 5;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Queues `order` offering asset `offer_index`. The first order of a batch
+/// opens it at `height`, to settle `interval_blocks` later. Returns the order
+/// id and the settle height of its batch.
+pub fn queue(
+    storage: &mut dyn Storage,
+    height: u64,
+    batch_auction: &BatchAuction,
+    offer_index: usize,
+    order: &QueuedOrder,
+) -> Result<(u64, u64), ContractError> {
+    let min_offer_amount = batch_auction.min_offer_amounts[offer_index];
+    if order.offer_asset.amount < min_offer_amount {
+        return Err(ContractError::BatchOrderTooSmall {
+            min_offer: Asset {
+                info: order.offer_asset.info.clone(),
+                amount: min_offer_amount,
+            }
+            .to_string(),
+        });
+    }
+
+    let mut batch = BATCH.may_load(storage)?.unwrap_or(Batch {
+        settle_height: height + batch_auction.interval_blocks,
+        order_count: 0,
+        offer_totals: [Uint128::zero(); 2],
+    });
+    if batch.order_count >= MAX_BATCH_ORDERS {
+        return Err(ContractError::BatchFull {
+            max: MAX_BATCH_ORDERS as u32,
+        });
+    }
+    let mut sender_orders = 0;
+    for item in BATCH_ORDERS.range(storage, None, None, Order::Ascending) {
+        if item?.1.sender == order.sender {
+            sender_orders += 1;
+        }
+    }
+    if sender_orders >= MAX_SENDER_ORDERS {
+        return Err(ContractError::BatchSenderFull {
+            max: MAX_SENDER_ORDERS as u32,
+        });
+    }
+
+    let id = batch.order_count;
+    BATCH_ORDERS.save(storage, id, order)?;
+
+    batch.order_count += 1;
+    batch.offer_totals[offer_index] =
+        batch.offer_totals[offer_index].checked_add(order.offer_asset.amount)?;
+    BATCH.save(storage, &batch)?;
+
+    Ok((id, batch.settle_height))
+}
+
+/// Closes the batch and returns its orders by id
+pub fn take(storage: &mut dyn Storage) -> StdResult<Vec<QueuedOrder>> {
+    let orders = BATCH_ORDERS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect::<StdResult<Vec<QueuedOrder>>>()?;
+
+    BATCH_ORDERS.clear(storage);
+    BATCH.remove(storage);
+
+    Ok(orders)
+}
+
+pub fn read_orders(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BatchOrder>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    BATCH_ORDERS
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (id, order) = item?;
+            Ok(BatchOrder {
+                id,
+                sender: order.sender.to_string(),
+                receiver: order.receiver.to_string(),
+                offer_asset: order.offer_asset,
+                belief_price: order.belief_price,
+                max_spread: order.max_spread,
+                deadline: order.deadline,
+            })
+        })
+        .collect()
+}
+
+/// Swap of `offer_amount` of asset `offer_index` in a batch clearing
+/// `offer_totals` against `pools`, returns (return_amount, spread_amount,
+/// commission_amount) like `compute_swap`. The spread is measured against the
+/// spot price before the batch and is zero for orders the batch improves on.
+pub fn compute_swap(
+    pools: [Uint128; 2],
+    offer_totals: [Uint128; 2],
+    offer_index: usize,
+    offer_amount: Uint128,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let ask_index = 1 - offer_index;
+    let offer_amount = Uint256::from(offer_amount);
+
+    let return_amount = offer_amount.multiply_ratio(
+        Uint256::from(pools[ask_index]) + Uint256::from(offer_totals[ask_index]),
+        Uint256::from(pools[offer_index]) + Uint256::from(offer_totals[offer_index]),
+    );
+    let spread_amount = offer_amount
+        .multiply_ratio(pools[ask_index], pools[offer_index])
+        .saturating_sub(return_amount);
+    let commission_amount = return_amount.mul_ceil(commission_rate);
+
+    Ok((
+        (return_amount - commission_amount).try_into()?,
+        spread_amount.try_into()?,
+        commission_amount.try_into()?,
+    ))
+}
@@ -0,0 +1,130 @@
+// Circuit breaker. Swaps are measured over a window opened by the first swap
+// after the previous one ended. A trade that would leave the price of an asset
+// fallen, or the net amount swapped out of a reserve grown, past the thresholds
+// set by the factory is rejected, so that direction stays closed until the
+// window ends.
+// Offering asset 0 lowers its price and drains reserve 1, so both thresholds of
+// a direction are checked against the offered asset's price and the ask reserve.
+// Prices are the spot prices of the pair's curve, so stable swap pairs are not
+// measured by their far more volatile reserve ratio.
+
+use crate::error::ContractError;
+use crate::state::{BreakerWindow, BREAKER_WINDOW, CIRCUIT_BREAKER};
+use crate::twap;
+use choice::pair::CircuitBreaker;
+use cosmwasm_std::{Decimal, Decimal256, StdResult, Storage, Uint128};
+use std::convert::TryInto;
+
+pub fn validate(circuit_breaker: &CircuitBreaker) -> Result<(), ContractError> {
+    let thresholds = [circuit_breaker.max_price_drop, circuit_breaker.max_outflow];
+    if circuit_breaker.window == 0
+        || thresholds
+            .iter()
+            .any(|threshold| threshold.is_zero() || *threshold > Decimal::one())
+    {
+        return Err(ContractError::InvalidCircuitBreaker {});
+    }
+
+    Ok(())
+}
+
+/// Window of `circuit_breaker` still open at `now`
+pub fn current_window(
+    storage: &dyn Storage,
+    circuit_breaker: &CircuitBreaker,
+    now: u64,
+) -> StdResult<Option<BreakerWindow>> {
+    Ok(BREAKER_WINDOW
+        .may_load(storage)?
+        .filter(|window| now < window.start.saturating_add(circuit_breaker.window)))
+}
+
+/// Adds a trade moving the reserves from `reserves` to `reserves_post` to the
+/// window. Fails, reverting the trade, when it moves the price of the offered
+/// asset or the net outflow of the other reserve further past its threshold.
+pub fn record(
+    storage: &mut dyn Storage,
+    now: u64,
+    decimals: [u8; 2],
+    reserves: [Uint128; 2],
+    reserves_post: [Uint128; 2],
+) -> Result<(), ContractError> {
+    let circuit_breaker = match CIRCUIT_BREAKER.may_load(storage)? {
+        Some(circuit_breaker) => circuit_breaker,
+        None => return Ok(()),
+    };
+
+    let mut window = match current_window(storage, &circuit_breaker, now)? {
+        Some(window) => window,
+        None => {
+            let (price0, price1) = twap::spot_prices(storage, now, reserves, decimals)?;
+            BreakerWindow {
+                start: now,
+                start_prices: [price0, price1],
+                start_reserves: reserves,
+                inflows: [Uint128::zero(); 2],
+                outflows: [Uint128::zero(); 2],
+            }
+        }
+    };
+    let price_drops_pre = price_drops(storage, now, &window, reserves, decimals)?;
+    let outflows_pre = net_outflows(&window);
+    for i in 0..2 {
+        if reserves_post[i] > reserves[i] {
+            window.inflows[i] = window.inflows[i].checked_add(reserves_post[i] - reserves[i])?;
+        } else {
+            window.outflows[i] = window.outflows[i].checked_add(reserves[i] - reserves_post[i])?;
+        }
+    }
+
+    let price_drops = price_drops(storage, now, &window, reserves_post, decimals)?;
+    let outflows = net_outflows(&window);
+    for offer_index in 0..2 {
+        let ask_index = 1 - offer_index;
+        let max_outflow = window.start_reserves[ask_index].mul_floor(circuit_breaker.max_outflow);
+        if (price_drops[offer_index] > circuit_breaker.max_price_drop
+            && price_drops[offer_index] > price_drops_pre[offer_index])
+            || (outflows[ask_index] > max_outflow && outflows[ask_index] > outflows_pre[ask_index])
+        {
+            return Err(ContractError::CircuitBreakerExceeded {
+                window_end: window.start + circuit_breaker.window,
+            });
+        }
+    }
+    BREAKER_WINDOW.save(storage, &window)?;
+
+    Ok(())
+}
+
+/// Relative fall of each asset's price from the window start to `reserves` at `now`
+pub fn price_drops(
+    storage: &dyn Storage,
+    now: u64,
+    window: &BreakerWindow,
+    reserves: [Uint128; 2],
+    decimals: [u8; 2],
+) -> StdResult<[Decimal; 2]> {
+    let (price0, price1) = twap::spot_prices(storage, now, reserves, decimals)?;
+    let mut drops = [Decimal::zero(); 2];
+    for (drop, (start, price)) in drops
+        .iter_mut()
+        .zip(window.start_prices.iter().zip([price0, price1].iter()))
+    {
+        if price < start {
+            // at most one, so it fits a Decimal
+            let ratio = Decimal256::from_ratio((*start - *price).atomics(), start.atomics());
+            *drop = Decimal::new(ratio.atomics().try_into()?);
+        }
+    }
+
+    Ok(drops)
+}
+
+/// Amounts swapped out of each reserve since the window start, net of the
+/// amounts swapped in
+pub fn net_outflows(window: &BreakerWindow) -> [Uint128; 2] {
+    [
+        window.outflows[0].saturating_sub(window.inflows[0]),
+        window.outflows[1].saturating_sub(window.inflows[1]),
+    ]
+}
@@ -1,20 +1,45 @@
+use crate::allowlist;
+use crate::batch;
+use crate::circuit_breaker;
+use crate::dynamic_fee;
 use crate::error::ContractError;
-use crate::state::PAIR_INFO;
+use crate::holding;
+use crate::launch;
+use crate::stableswap::{self, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_RAMP_TIME};
+use crate::state::{
+    AllowlistConfig, AmpConfig, FlashSwapState, QueuedOrder, ALLOWLIST_CONFIG, AMP, BATCH,
+    BATCH_AUCTION, BREAKER_WINDOW, CIRCUIT_BREAKER, DEPOSITS, DEPOSITS_TOTAL, DYNAMIC_FEE, FACTORY,
+    FLASH_SWAP, HOLDING_PERIOD, LAUNCH_PROTECTION, PAIR_INFO, RESERVES, STATUS, SWAP_HOOKS,
+    VOLATILITY, WEIGHTS,
+};
+use crate::stats;
+use crate::twap;
+use crate::weighted::{self, MIN_WEIGHT};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    coins, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Decimal256,
-    Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, Uint256, WasmMsg,
+    coins, from_json, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal,
+    Decimal256, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, Storage,
+    SubMsg, SubMsgResult, Uint128, Uint256, WasmMsg,
 };
 
 use choice::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw};
+use choice::factory::MAX_COMMISSION_BPS;
 use choice::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse,
+    AllowlistResponse, AmpResponse, BatchAuction, BatchAuctionResponse, BatchOrdersResponse,
+    CircuitBreaker, CircuitBreakerResponse, Cw20HookMsg, DepthLevel, DepthResponse, DynamicFee,
+    DynamicFeeResponse, ExecuteMsg, FlashSwapCallbackMsg, HoldingPeriod, HoldingPeriodResponse,
+    InstantiateMsg, LaunchProtectionResponse, LpDepositResponse, MigrateMsg, PairStatus, PairType,
+    PendingDepositResponse, PoolResponse, QueryMsg, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulateWithdrawResponse, SimulationResponse, SpotPriceResponse,
+    StatsResponse, SwapHookMsg, SwapHooksResponse, TradingStats, TwapResponse, WeightsResponse,
+};
+use choice::querier::{
+    query_balance, query_factory_router, query_fee_discount, query_fee_split,
+    query_token_factory_denom_total_supply,
 };
-use choice::querier::query_token_factory_denom_total_supply;
 use choice::util::migrate_version;
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
@@ -37,20 +62,104 @@ use injective_cosmwasm::query::InjectiveQueryWrapper;
 const CONTRACT_NAME: &str = "crates.io:choice-pair";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Commission rate == 0.3%
-const COMMISSION_RATE: u64 = 3;
-
 const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
 
+const FLASH_SWAP_REPLY_ID: u64 = 1;
+
+const SWAP_HOOK_REPLY_ID: u64 = 2;
+
+/// Most contracts a pair notifies after each swap
+const MAX_SWAP_HOOKS: usize = 5;
+
+/// Gas each swap hook may use, a hook running out of it fails like any other
+const SWAP_HOOK_GAS_LIMIT: u64 = 300_000;
+
+/// Most price impacts a single depth query may ask for
+const MAX_PRICE_IMPACTS: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response<InjectiveMsgWrapper>> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    if msg.commission_bps == 0 {
+        return Err(StdError::generic_err(
+            "commission_bps must be greater than zero",
+        ));
+    }
+
+    match (&msg.pair_type, msg.amp) {
+        (PairType::ConstantProduct, None) | (PairType::Weighted, None) => {}
+        (PairType::StableSwap, Some(amp)) if amp > 0 && amp <= MAX_AMP => {
+            let now = env.block.time.seconds();
+            AMP.save(
+                deps.storage,
+                &AmpConfig {
+                    init_amp: amp,
+                    init_amp_time: now,
+                    next_amp: amp,
+                    next_amp_time: now,
+                },
+            )?;
+        }
+        (PairType::StableSwap, _) => {
+            return Err(StdError::generic_err(format!(
+                "stable swap pairs require an amp between 1 and {}",
+                MAX_AMP
+            )));
+        }
+        (PairType::ConstantProduct, Some(_)) | (PairType::Weighted, Some(_)) => {
+            return Err(StdError::generic_err(
+                "amp is only supported by stable swap pairs",
+            ));
+        }
+    }
+
+    match (&msg.pair_type, msg.weights) {
+        (PairType::Weighted, Some(weights))
+            if weights.iter().all(|weight| *weight >= MIN_WEIGHT)
+                && weights[0] + weights[1] == Decimal::one() =>
+        {
+            WEIGHTS.save(deps.storage, &weights)?;
+        }
+        (PairType::Weighted, _) => {
+            return Err(StdError::generic_err(format!(
+                "weighted pairs require two weights of at least {} that sum to 1",
+                MIN_WEIGHT
+            )));
+        }
+        (_, Some(_)) => {
+            return Err(StdError::generic_err(
+                "weights are only supported by weighted pairs",
+            ));
+        }
+        (_, None) => {}
+    }
+
+    if let Some(launch_protection) = msg.launch_protection {
+        launch::validate(&launch_protection, env.block.time.seconds())?;
+        LAUNCH_PROTECTION.save(deps.storage, &launch_protection)?;
+    }
+
+    if let Some(allowlist) = msg.allowlist {
+        let manager = match allowlist.manager {
+            Some(manager) => Some(deps.api.addr_validate(&manager)?),
+            None => None,
+        };
+        ALLOWLIST_CONFIG.save(
+            deps.storage,
+            &AllowlistConfig {
+                manager,
+                restrict_swaps: allowlist.restrict_swaps,
+            },
+        )?;
+        allowlist::add(deps.storage, deps.api, &allowlist.addresses)?;
+    }
+
     let subdenom = "lp".to_string();
     let lp_denom = format!("factory/{}/{}", env.contract.address, subdenom);
 
@@ -64,9 +173,16 @@ pub fn instantiate(
         asset_decimals: msg.asset_decimals,
         burn_address: deps.api.addr_canonicalize(&msg.burn_address)?,
         fee_wallet_address: deps.api.addr_canonicalize(&msg.fee_wallet_address)?,
+        commission_bps: msg.commission_bps,
+        pair_type: msg.pair_type,
     };
 
     PAIR_INFO.save(deps.storage, pair_info)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(); 2])?;
+    FACTORY.save(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+    )?;
 
     let create_msg = create_new_denom_msg(env.contract.address.to_string(), subdenom.clone());
 
@@ -89,6 +205,11 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    // reserves are lent out until the flash swap reply has checked the repayment
+    if FLASH_SWAP.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashSwapInProgress {});
+    }
+
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
@@ -106,6 +227,29 @@ pub fn execute(
             slippage_tolerance,
         ),
 
+        ExecuteMsg::ZapIn {
+            offer_asset,
+            min_lp,
+            receiver,
+            deadline,
+        } => {
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let sender = info.sender.clone();
+            zap_in(
+                deps,
+                env,
+                info,
+                sender,
+                offer_asset,
+                min_lp,
+                receiver,
+                deadline,
+            )
+        }
+
         ExecuteMsg::WithdrawLiquidity {
             amount,
             min_assets,
@@ -115,12 +259,20 @@ pub fn execute(
             withdraw_liquidity(deps, env, info, sender_addr, amount, min_assets, deadline)
         }
 
+        ExecuteMsg::ZapOut {
+            amount,
+            target_asset,
+            min_return,
+            deadline,
+        } => zap_out(deps, env, info, amount, target_asset, min_return, deadline),
+
         ExecuteMsg::Swap {
             offer_asset,
             belief_price,
             max_spread,
             to,
             deadline,
+            trader,
         } => {
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
@@ -142,8 +294,74 @@ pub fn execute(
                 max_spread,
                 to_addr,
                 deadline,
+                trader,
             )
         }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer,
+            to,
+            deadline,
+        } => {
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(&to_addr)?)
+            } else {
+                None
+            };
+
+            swap_exact_out(deps, env, info, ask_asset, max_offer, to_addr, deadline)
+        }
+        ExecuteMsg::RampAmp {
+            future_amp,
+            future_time,
+        } => ramp_amp(deps, env, info, future_amp, future_time),
+        ExecuteMsg::StopRampAmp {} => stop_ramp_amp(deps, env, info),
+        ExecuteMsg::UpdateStatus { status } => update_status(deps, info, status),
+        ExecuteMsg::UpdateDynamicFee { dynamic_fee } => update_dynamic_fee(deps, info, dynamic_fee),
+        ExecuteMsg::UpdateSwapHooks { hooks } => update_swap_hooks(deps, info, hooks),
+        ExecuteMsg::FlashSwap { asset, to, msg } => flash_swap(deps, env, info, asset, to, msg),
+        ExecuteMsg::Skim { to } => skim(deps, env, info, to),
+        ExecuteMsg::Sync {} => sync(deps, env, info),
+        ExecuteMsg::Donate { asset } => {
+            if !asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            asset.assert_sent_native_token_balance(&info)?;
+            let sender = info.sender;
+            donate(deps, env, sender, asset)
+        }
+        ExecuteMsg::Deposit {} => {
+            let assets = info
+                .funds
+                .iter()
+                .map(|coin| Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: coin.denom.clone(),
+                    },
+                    amount: coin.amount,
+                })
+                .collect();
+            deposit(deps, info.sender, assets)
+        }
+        ExecuteMsg::RefundDeposit {} => refund_deposit(deps, info),
+        ExecuteMsg::UpdateBatchAuction { batch_auction } => {
+            update_batch_auction(deps, info, batch_auction)
+        }
+        ExecuteMsg::SettleBatch {} => settle_batch(deps, env),
+        ExecuteMsg::UpdateCircuitBreaker { circuit_breaker } => {
+            update_circuit_breaker(deps, info, circuit_breaker)
+        }
+        ExecuteMsg::ResetCircuitBreaker {} => reset_circuit_breaker(deps, info),
+        ExecuteMsg::UpdateHoldingPeriod { holding_period } => {
+            update_holding_period(deps, info, holding_period)
+        }
+        ExecuteMsg::RecordLpDeposit {} => record_lp_deposit(deps, env, info),
+        ExecuteMsg::UpdateAllowlist {
+            add,
+            remove,
+            restrict_swaps,
+        } => update_allowlist(deps, info, add, remove, restrict_swaps),
     }
 }
 
@@ -161,25 +379,9 @@ pub fn receive_cw20(
             max_spread,
             to,
             deadline,
+            trader,
         }) => {
-            // only asset contract can execute this message
-            let mut authorized: bool = false;
-            let config: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-            let pools: [Asset; 2] =
-                config.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
-
-            for pool in pools.iter() {
-                if let AssetInfo::Token { contract_addr, .. } = &pool.info {
-                    if contract_addr == &info.sender.to_string() {
-                        authorized = true;
-                        break;
-                    }
-                }
-            }
-
-            if !authorized {
-                return Err(ContractError::Unauthorized {});
-            }
+            assert_pair_token(deps.as_ref(), &env, &info.sender)?;
 
             let to_addr = if let Some(to_addr) = to {
                 Some(deps.api.addr_validate(to_addr.as_str())?)
@@ -202,12 +404,113 @@ pub fn receive_cw20(
                 max_spread,
                 to_addr,
                 deadline,
+                trader,
+            )
+        }
+        Ok(Cw20HookMsg::ZapIn {
+            min_lp,
+            receiver,
+            deadline,
+        }) => {
+            assert_pair_token(deps.as_ref(), &env, &info.sender)?;
+
+            zap_in(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: contract_addr.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                min_lp,
+                receiver,
+                deadline,
+            )
+        }
+        Ok(Cw20HookMsg::Donate {}) => {
+            assert_pair_token(deps.as_ref(), &env, &info.sender)?;
+
+            donate(
+                deps,
+                env,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: contract_addr.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+            )
+        }
+        Ok(Cw20HookMsg::ProvideLiquidity {
+            receiver,
+            deadline,
+            slippage_tolerance,
+        }) => {
+            assert_pair_token(deps.as_ref(), &env, &info.sender)?;
+
+            provide_liquidity_with_deposit(
+                deps,
+                env,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: contract_addr.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                receiver,
+                deadline,
+                slippage_tolerance,
+            )
+        }
+        Ok(Cw20HookMsg::Deposit {}) => {
+            assert_pair_token(deps.as_ref(), &env, &info.sender)?;
+
+            deposit(
+                deps,
+                Addr::unchecked(cw20_msg.sender),
+                vec![Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: contract_addr.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                }],
             )
         }
         Err(err) => Err(ContractError::Std(err)),
     }
 }
 
+/// Only the CW20 assets of the pair can execute receive hooks
+fn assert_pair_token(
+    deps: Deps<InjectiveQueryWrapper>,
+    _env: &Env,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let mut authorized: bool = false;
+    let config: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &config)?;
+
+    for pool in pools.iter() {
+        if let AssetInfo::Token { contract_addr, .. } = &pool.info {
+            if contract_addr == &sender.to_string() {
+                authorized = true;
+                break;
+            }
+        }
+    }
+
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
 /// CONTRACT - should approve contract to use the amount of token
 pub fn provide_liquidity(
     deps: DepsMut<InjectiveQueryWrapper>,
@@ -219,56 +522,94 @@ pub fn provide_liquidity(
     slippage_tolerance: Option<Decimal>,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_provide_enabled(deps.storage)?;
 
     for asset in assets.iter() {
         asset.assert_sent_native_token_balance(&info)?;
     }
 
-    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    add_liquidity(
+        deps,
+        env,
+        info.sender,
+        assets,
+        true,
+        receiver,
+        slippage_tolerance,
+    )
+}
 
-    let mut pools: [Asset; 2] =
-        pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+/// Completes a provision staged with `deposit`, the sent token is provided
+/// along with the whole pending deposit of `sender`
+pub fn provide_liquidity_with_deposit(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    asset: Asset,
+    receiver: Option<String>,
+    deadline: Option<u64>,
+    slippage_tolerance: Option<Decimal>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_provide_enabled(deps.storage)?;
 
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pending = take_deposit(deps.storage, &sender)?;
 
-    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
-        if pool.is_native_token() {
-            // If the asset is native token, balance is already increased
-            // To calculated properly we should subtract user deposit from the pool
-            pool.amount = pool.amount.checked_sub(deposits[i])?;
+    let mut assets: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    for (i, deposit) in assets.iter_mut().enumerate() {
+        deposit.amount = pending[i];
+        if deposit.info.equal(&asset.info) {
+            deposit.amount = deposit.amount.checked_add(asset.amount)?;
         }
     }
 
+    add_liquidity(
+        deps,
+        env,
+        sender,
+        assets,
+        false,
+        receiver,
+        slippage_tolerance,
+    )
+}
+
+/// Mints LP for `assets`. Native assets are already held by the pair and their
+/// unused part is sent back. CW20 assets are pulled with `TransferFrom` when
+/// `pull_tokens` is set, otherwise they were received and are refunded the same way.
+fn add_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    assets: [Asset; 2],
+    pull_tokens: bool,
+    receiver: Option<String>,
+    slippage_tolerance: Option<Decimal>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let deposits = provision_deposits(&pools, &assets)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+    )?;
+
     let total_share: Uint128 =
         query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())
             .unwrap();
 
-    let share: Uint128 = if total_share.is_zero() {
-        // Initial share = collateral amount
-        let deposit0: Uint256 = deposits[0].into();
-        let deposit1: Uint256 = deposits[1].into();
-
-        // Compute the square root of the product.
-        let computed = Decimal256::from_ratio(deposit0.mul(deposit1), 1u8).sqrt();
-        // Assume Decimal256 uses 18 decimals. Its internal representation of 1 is 1e18.
-        // To get the integer value 1, we divide by 10^18.
-        let scaling_factor = Uint256::from(1_000_000_000_000_000_000u128);
-        let share: Uint128 = (computed.atomics() / scaling_factor)
-            .try_into()
-            .map_err(ContractError::ConversionOverflowError)?;
+    let provision = compute_provision(deps.storage, &pair_info, &pools, deposits, total_share)?;
+    let share = provision.share;
 
+    if total_share.is_zero() {
         // Mint the minimum liquidity tokens to lock forever (to protect the pair)
         messages.push(create_mint_tokens_msg(
             env.contract.address.clone(),
@@ -278,55 +619,18 @@ pub fn provide_liquidity(
             },
             env.contract.address.to_string(),
         ));
-
-        // Deduct the minimum liquidity amount and return the result.
-        share
-            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.into())
-            .map_err(|_| ContractError::MinimumLiquidityAmountError {
-                min_lp_token: MINIMUM_LIQUIDITY_AMOUNT.to_string(),
-                given_lp: share.to_string(),
-            })?
-    } else {
-        std::cmp::min(
-            deposits[0].multiply_ratio(total_share, pools[0].amount),
-            deposits[1].multiply_ratio(total_share, pools[1].amount),
-        )
-    };
-
-    // prevent providing free token
-    if share.is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
-
-    // the total lp token cannot exceed the max value of a Uint128
-    if total_share
-        .checked_add(share)
-        .is_err()                       
-    {
-        return Err(ContractError::LpSupplyOverflow{});  
     }
 
     // refund of remaining native token & desired of token
     let mut refund_assets: Vec<Asset> = vec![];
+    let mut reserves = [Uint128::zero(); 2];
     for (i, pool) in pools.iter().enumerate() {
-        let desired_amount = match total_share.is_zero() {
-            true => deposits[i],
-            false => {
-                let mut desired_amount = pool.amount.multiply_ratio(share, total_share);
-                if desired_amount.multiply_ratio(total_share, share) != pool.amount {
-                    desired_amount += Uint128::from(1u8);
-                }
-
-                desired_amount
-            }
+        let desired_amount = provision.amounts[i];
+        let remain_amount = match &pool.info {
+            AssetInfo::Token { .. } if !pull_tokens => deposits[i] - desired_amount,
+            _ => provision.refunds[i],
         };
-
-        let mut remain_amount = deposits[i] - desired_amount;
-
-        // Override remain_amount to 0 if CW20
-        if let AssetInfo::Token { .. } = &pool.info {
-            remain_amount = Uint128::zero();
-        }
+        reserves[i] = pool.amount.checked_add(desired_amount)?;
 
         if let Some(slippage_tolerance) = slippage_tolerance {
             if remain_amount > deposits[i].mul_floor(slippage_tolerance) {
@@ -339,28 +643,35 @@ pub fn provide_liquidity(
             amount: remain_amount,
         });
 
-        if let AssetInfo::NativeToken { denom, .. } = &pool.info {
-            if !remain_amount.is_zero() {
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: info.sender.to_string(),
-                    amount: coins(remain_amount.u128(), denom),
-                }))
+        match &pool.info {
+            AssetInfo::Token { contract_addr, .. } if pull_tokens => {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: desired_amount,
+                    })?,
+                    funds: vec![],
+                }));
+            }
+            _ => {
+                if !remain_amount.is_zero() {
+                    messages.push(refund_assets[i].clone().into_msg(sender.clone())?);
+                }
             }
-        } else if let AssetInfo::Token { contract_addr, .. } = &pool.info {
-            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: contract_addr.to_string(),
-                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
-                    owner: info.sender.to_string(),
-                    recipient: env.contract.address.to_string(),
-                    amount: desired_amount,
-                })?,
-                funds: vec![],
-            }));
         }
     }
 
+    RESERVES.save(deps.storage, &reserves)?;
+
     // mint LP token to sender
-    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => sender.clone(),
+    };
+    allowlist::assert_provide(deps.storage, &[&sender, &receiver])?;
+    holding::record_mint(deps.storage, env.block.time.seconds(), &receiver, share)?;
     messages.push(create_mint_tokens_msg(
         env.contract.address.clone(), // use contract as the minter/sender
         Coin {
@@ -372,7 +683,7 @@ pub fn provide_liquidity(
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         ("action", "provide_liquidity"),
-        ("sender", info.sender.as_str()),
+        ("sender", sender.as_str()),
         ("receiver", receiver.as_str()),
         ("assets", &format!("{}, {}", assets[0], assets[1])),
         ("share", &share.to_string()),
@@ -383,51 +694,159 @@ pub fn provide_liquidity(
     ]))
 }
 
-pub fn withdraw_liquidity(
-    deps: DepsMut<InjectiveQueryWrapper>,
-    env: Env,
-    _info: MessageInfo,
-    sender: Addr,
-    amount: Uint128,
-    min_assets: Option<[Asset; 2]>,
-    deadline: Option<u64>,
-) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
-    assert_deadline(env.block.time.seconds(), deadline)?;
+/// Deposit amounts of `assets` in the pair's asset order
+fn provision_deposits(
+    pools: &[Asset; 2],
+    assets: &[Asset; 2],
+) -> Result<[Uint128; 2], ContractError> {
+    let deposit = |pool: &Asset| {
+        assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})
+    };
 
-    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    Ok([deposit(&pools[0])?, deposit(&pools[1])?])
+}
 
-    // Ensure that the transaction includes at least one coin matching the LP token denomination
-    // and with an amount exactly equal to the withdrawal amount.
-    let valid = _info
-        .funds
-        .iter()
-        .any(|coin| coin.denom == pair_info.liquidity_token && coin.amount == amount);
-    if !valid {
-        return Err(ContractError::InvalidLiquidityFunds {});
+/// LP share of a provision and how it splits each deposit
+struct Provision {
+    /// Net of the minimum liquidity locked by the first provision
+    share: Uint128,
+    /// Part of each deposit added to the reserves
+    amounts: [Uint128; 2],
+    /// Part of each deposit sent back, zero for CW20 tokens which are only
+    /// pulled up to the amount taken
+    refunds: [Uint128; 2],
+}
+
+fn compute_provision(
+    storage: &dyn Storage,
+    pair_info: &PairInfoRaw,
+    pools: &[Asset; 2],
+    deposits: [Uint128; 2],
+    total_share: Uint128,
+) -> Result<Provision, ContractError> {
+    let share: Uint128 = if total_share.is_zero() {
+        let share: Uint128 = if pair_info.pair_type == PairType::Weighted {
+            // Initial share = weighted geometric mean of the deposits
+            weighted::compute_initial_share(deposits, WEIGHTS.load(storage)?)?
+        } else {
+            // Initial share = collateral amount
+            let deposit0: Uint256 = deposits[0].into();
+            let deposit1: Uint256 = deposits[1].into();
+
+            // Compute the square root of the product.
+            let computed = Decimal256::from_ratio(deposit0.mul(deposit1), 1u8).sqrt();
+            // Assume Decimal256 uses 18 decimals. Its internal representation of 1 is 1e18.
+            // To get the integer value 1, we divide by 10^18.
+            let scaling_factor = Uint256::from(1_000_000_000_000_000_000u128);
+            (computed.atomics() / scaling_factor)
+                .try_into()
+                .map_err(ContractError::ConversionOverflowError)?
+        };
+
+        // Deduct the minimum liquidity amount and return the result.
+        share
+            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.into())
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {
+                min_lp_token: MINIMUM_LIQUIDITY_AMOUNT.to_string(),
+                given_lp: share.to_string(),
+            })?
+    } else {
+        std::cmp::min(
+            deposits[0].multiply_ratio(total_share, pools[0].amount),
+            deposits[1].multiply_ratio(total_share, pools[1].amount),
+        )
+    };
+
+    // prevent providing free token
+    if share.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    // the total lp token cannot exceed the max value of a Uint128
+    if total_share.checked_add(share).is_err() {
+        return Err(ContractError::LpSupplyOverflow {});
+    }
+
+    let mut amounts = [Uint128::zero(); 2];
+    let mut refunds = [Uint128::zero(); 2];
+    for (i, pool) in pools.iter().enumerate() {
+        let desired_amount = match total_share.is_zero() {
+            true => deposits[i],
+            false => {
+                let mut desired_amount = pool.amount.multiply_ratio(share, total_share);
+                if desired_amount.multiply_ratio(total_share, share) != pool.amount {
+                    desired_amount += Uint128::from(1u8);
+                }
+
+                desired_amount
+            }
+        };
+
+        amounts[i] = desired_amount;
+        // Override remain_amount to 0 if CW20
+        if let AssetInfo::NativeToken { .. } = &pool.info {
+            refunds[i] = deposits[i] - desired_amount;
+        }
     }
 
+    Ok(Provision {
+        share,
+        amounts,
+        refunds,
+    })
+}
+
+pub fn withdraw_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    _info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    min_assets: Option<[Asset; 2]>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    assert_liquidity_funds(&_info, &pair_info, amount)?;
+
     let contract_addr = env.contract.address.clone();
 
-    let pools: [Asset; 2] =
-        pair_info.query_pools(&deps.querier, deps.api, contract_addr.clone())?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+    )?;
 
     let total_share: Uint128 =
         query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())
             .unwrap();
 
-    let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
-    let refund_assets: Vec<Asset> = pools
-        .iter()
-        .map(|a| Asset {
-            info: a.info.clone(),
-            amount: a.amount.mul_floor(share_ratio),
-        })
-        .collect();
+    let (mut refund_assets, mut reserves) = compute_withdrawal(&pools, amount, total_share)?;
+
+    // the early withdrawal penalty stays in the pool
+    let penalty = holding::record_burn(deps.storage, env.block.time.seconds(), &sender, amount)?;
+    let mut penalty_amounts = [Uint128::zero(); 2];
+    for i in 0..2 {
+        penalty_amounts[i] = refund_assets[i].amount.mul_floor(penalty);
+        refund_assets[i].amount -= penalty_amounts[i];
+        reserves[i] += penalty_amounts[i];
+    }
 
     assert_minimum_assets(refund_assets.to_vec(), min_assets)?;
 
+    RESERVES.save(deps.storage, &reserves)?;
+
     // update pool info
-    Ok(Response::new()
+    let response = Response::new()
         .add_messages(vec![
             refund_assets[0].clone().into_msg(sender.clone())?,
             refund_assets[1].clone().into_msg(sender.clone())?,
@@ -448,7 +867,281 @@ pub fn withdraw_liquidity(
                 "refund_assets",
                 &format!("{}, {}", refund_assets[0], refund_assets[1]),
             ),
-        ]))
+        ]);
+
+    Ok(penalty_attribute(response, &pools, penalty_amounts))
+}
+
+/// Reports the early withdrawal penalty of a withdrawal, if any
+fn penalty_attribute(
+    response: Response<InjectiveMsgWrapper>,
+    pools: &[Asset; 2],
+    penalty_amounts: [Uint128; 2],
+) -> Response<InjectiveMsgWrapper> {
+    if penalty_amounts.iter().all(|amount| amount.is_zero()) {
+        return response;
+    }
+
+    response.add_attribute(
+        "penalty_assets",
+        format!(
+            "{}{}, {}{}",
+            penalty_amounts[0], pools[0].info, penalty_amounts[1], pools[1].info
+        ),
+    )
+}
+
+/// Assets refunded for burning `amount` of the `total_share` LP supply, and the
+/// reserves left behind
+fn compute_withdrawal(
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+) -> Result<([Asset; 2], [Uint128; 2]), ContractError> {
+    if total_share.is_zero() {
+        return Err(ContractError::EmptyPool {});
+    }
+    if amount > total_share {
+        return Err(StdError::generic_err("withdraw amount exceeds the lp supply").into());
+    }
+
+    let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
+    let refund_assets = [
+        Asset {
+            info: pools[0].info.clone(),
+            amount: pools[0].amount.mul_floor(share_ratio),
+        },
+        Asset {
+            info: pools[1].info.clone(),
+            amount: pools[1].amount.mul_floor(share_ratio),
+        },
+    ];
+    let reserves = [
+        pools[0].amount.checked_sub(refund_assets[0].amount)?,
+        pools[1].amount.checked_sub(refund_assets[1].amount)?,
+    ];
+
+    Ok((refund_assets, reserves))
+}
+
+/// Withdraws `amount` of LP like `withdraw_liquidity`, then swaps the other
+/// asset's refund to `target_asset` against the reserves left after the withdrawal
+pub fn zap_out(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    target_asset: AssetInfo,
+    min_return: Option<Uint128>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_swaps_enabled(deps.storage)?;
+    assert_no_batch_auction(deps.storage)?;
+
+    allowlist::assert_swap(deps.storage, &[&info.sender])?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    assert_liquidity_funds(&info, &pair_info, amount)?;
+
+    let contract_addr = env.contract.address.clone();
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let target_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&target_asset))
+        .ok_or(ContractError::AssetMismatch {})?;
+    let offer_index = 1 - target_index;
+
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+    )?;
+    let commission_bps = dynamic_fee::record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+
+    let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
+    let penalty =
+        holding::record_burn(deps.storage, env.block.time.seconds(), &info.sender, amount)?;
+    let mut withdrawn: [Uint128; 2] = [
+        pools[0].amount.mul_floor(share_ratio),
+        pools[1].amount.mul_floor(share_ratio),
+    ];
+    // the early withdrawal penalty stays in the pool
+    let mut penalty_amounts = [Uint128::zero(); 2];
+    for i in 0..2 {
+        penalty_amounts[i] = withdrawn[i].mul_floor(penalty);
+        withdrawn[i] -= penalty_amounts[i];
+    }
+
+    let remaining: [Uint128; 2] = [
+        pools[0].amount.checked_sub(withdrawn[0])?,
+        pools[1].amount.checked_sub(withdrawn[1])?,
+    ];
+
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        &pair_info,
+        &curve,
+        offer_index,
+        remaining[offer_index],
+        remaining[target_index],
+        withdrawn[offer_index],
+        commission_bps,
+    )?;
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &info.sender,
+        withdrawn[offer_index],
+        remaining[offer_index],
+    )?;
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let discount = query_fee_discount(&deps.querier, factory_addr.clone(), &info.sender, None)?;
+    let (return_amount, commission_amount) =
+        apply_fee_discount(return_amount, commission_amount, discount);
+    let fee_split = query_fee_split(&deps.querier, factory_addr, contract_addr.clone())?;
+    let burn_amount = commission_amount.mul_floor(fee_split.burn);
+    let fee_wallet_amount = commission_amount.mul_floor(fee_split.fee_wallet);
+
+    let mut refund_assets: Vec<Asset> = pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: Uint128::zero(),
+        })
+        .collect();
+    refund_assets[target_index].amount = withdrawn[target_index].checked_add(return_amount)?;
+
+    if let Some(min_return) = min_return {
+        if refund_assets[target_index].amount < min_return {
+            return Err(ContractError::MinAmountAssertion {
+                min_asset: Asset {
+                    info: target_asset,
+                    amount: min_return,
+                }
+                .to_string(),
+                asset: refund_assets[target_index].to_string(),
+            });
+        }
+    }
+
+    // the withdrawn offer asset is swapped back into the pool
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    reserves[target_index] = remaining[target_index]
+        .checked_sub(return_amount)?
+        .checked_sub(burn_amount)?
+        .checked_sub(fee_wallet_amount)?;
+    circuit_breaker::record(
+        deps.storage,
+        env.block.time.seconds(),
+        pair_info.asset_decimals,
+        remaining,
+        reserves,
+    )?;
+    RESERVES.save(deps.storage, &reserves)?;
+    stats::record(
+        deps.storage,
+        env.block.time.seconds(),
+        &stats::swap(
+            offer_index,
+            withdrawn[offer_index],
+            return_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        ),
+    )?;
+
+    let hook_messages = swap_hook_messages(
+        deps.storage,
+        &SwapHookMsg::SwapHook {
+            sender: info.sender.to_string(),
+            receiver: info.sender.to_string(),
+            offer_asset: Asset {
+                info: pools[offer_index].info.clone(),
+                amount: withdrawn[offer_index],
+            },
+            return_asset: Asset {
+                info: pools[target_index].info.clone(),
+                amount: return_amount,
+            },
+            spread_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        },
+    )?;
+
+    let mut messages = vec![refund_assets[target_index]
+        .clone()
+        .into_msg(info.sender.clone())?];
+    messages.extend(fee_messages(
+        deps.api,
+        &pair_info,
+        &pools[target_index].info,
+        burn_amount,
+        fee_wallet_amount,
+    )?);
+    // burn liquidity token
+    messages.push(create_burn_tokens_msg(
+        contract_addr,
+        Coin {
+            denom: pair_info.liquidity_token.clone(),
+            amount,
+        },
+    ));
+
+    let response = Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "withdraw_liquidity"),
+            ("sender", info.sender.as_str()),
+            ("withdrawn_share", &amount.to_string()),
+            (
+                "refund_assets",
+                &format!("{}, {}", refund_assets[0], refund_assets[1]),
+            ),
+            ("target_asset", &pools[target_index].info.to_string()),
+            ("swap_amount", &withdrawn[offer_index].to_string()),
+            ("return_amount", &return_amount.to_string()),
+            ("commission_amount", &commission_amount.to_string()),
+            ("burn_amount", &burn_amount.to_string()),
+            ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+        ]);
+
+    Ok(penalty_attribute(response, &pools, penalty_amounts))
+}
+
+/// The transaction must include a coin of the LP token denomination with an amount
+/// exactly equal to the withdrawal amount
+fn assert_liquidity_funds(
+    info: &MessageInfo,
+    pair_info: &PairInfoRaw,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let valid = info
+        .funds
+        .iter()
+        .any(|coin| coin.denom == pair_info.liquidity_token && coin.amount == amount);
+    if !valid {
+        return Err(ContractError::InvalidLiquidityFunds {});
+    }
+
+    Ok(())
 }
 
 // CONTRACT - a user must do token approval
@@ -463,47 +1156,113 @@ pub fn swap(
     max_spread: Option<Decimal>,
     to: Option<Addr>,
     deadline: Option<u64>,
+    trader: Option<String>,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_swaps_enabled(deps.storage)?;
+    // swaps through the router are checked and limited per trader, not as
+    // one address
+    let trader_addr = swap_trader(deps.as_ref(), &sender, trader.as_ref())?;
+    allowlist::assert_swap(
+        deps.storage,
+        &[&sender, &trader_addr, to.as_ref().unwrap_or(&sender)],
+    )?;
 
     offer_asset.assert_sent_native_token_balance(&info)?;
 
+    if let Some(batch_auction) = BATCH_AUCTION.may_load(deps.storage)? {
+        // the router would be paid the queued return, and the refunds, on
+        // behalf of all of its users
+        if is_router(deps.as_ref(), &sender)? {
+            return Err(ContractError::BatchAuctionRouted {});
+        }
+        let receiver = to.unwrap_or_else(|| sender.clone());
+        return queue_swap(
+            deps,
+            env,
+            &batch_auction,
+            QueuedOrder {
+                sender,
+                receiver,
+                offer_asset,
+                belief_price,
+                max_spread,
+                deadline,
+            },
+        );
+    }
+
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
 
+    let offer_index: usize;
     let offer_decimal: u8;
     let ask_decimal: u8;
-    // If the asset balance is already increased
-    // To calculated properly we should subtract user deposit from the pool
     if offer_asset.info.equal(&pools[0].info) {
-        offer_pool = Asset {
-            amount: pools[0].amount.checked_sub(offer_asset.amount)?,
-            info: pools[0].info.clone(),
-        };
+        offer_pool = pools[0].clone();
         ask_pool = pools[1].clone();
 
+        offer_index = 0;
         offer_decimal = pair_info.asset_decimals[0];
         ask_decimal = pair_info.asset_decimals[1];
     } else if offer_asset.info.equal(&pools[1].info) {
-        offer_pool = Asset {
-            amount: pools[1].amount.checked_sub(offer_asset.amount)?,
-            info: pools[1].info.clone(),
-        };
+        offer_pool = pools[1].clone();
         ask_pool = pools[0].clone();
 
+        offer_index = 1;
         offer_decimal = pair_info.asset_decimals[1];
         ask_decimal = pair_info.asset_decimals[0];
     } else {
         return Err(ContractError::AssetMismatch {});
     }
 
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &trader_addr,
+        offer_asset.amount,
+        offer_pool.amount,
+    )?;
+
+    // prices are accumulated with the reserves from before the swap
+    let reserves = if offer_pool.info.equal(&pools[0].info) {
+        [offer_pool.amount, ask_pool.amount]
+    } else {
+        [ask_pool.amount, offer_pool.amount]
+    };
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+    )?;
+    let commission_bps = dynamic_fee::record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
     let offer_amount = offer_asset.amount;
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_amount, offer_decimal, ask_decimal)?;
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        &pair_info,
+        &curve,
+        offer_index,
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_amount,
+        commission_bps,
+    )?;
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let discount = query_fee_discount(&deps.querier, factory_addr.clone(), &sender, trader)?;
+    let (return_amount, commission_amount) =
+        apply_fee_discount(return_amount, commission_amount, discount);
 
     let return_asset = Asset {
         info: ask_pool.info.clone(),
@@ -523,63 +1282,53 @@ pub fn swap(
 
     let receiver = to.unwrap_or_else(|| sender.clone());
 
-    let total_fee = commission_amount; // Total fee, assumed to be 0.3% of the transaction
-    let fee_wallet_amount = total_fee.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
-    let burn_amount = total_fee.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
+    let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address)?;
+
+    let total_fee = commission_amount; // Total fee, set by the pair's fee tier or dynamic fee
+    let fee_wallet_amount = total_fee.mul_floor(fee_split.fee_wallet);
+    let burn_amount = total_fee.mul_floor(fee_split.burn);
     let lp_amount = total_fee
         .checked_sub(fee_wallet_amount)?
         .checked_sub(burn_amount)?;
+    stats::record(
+        deps.storage,
+        env.block.time.seconds(),
+        &stats::swap(
+            offer_index,
+            offer_amount,
+            return_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        ),
+    )?;
+
+    let hook_messages = swap_hook_messages(
+        deps.storage,
+        &SwapHookMsg::SwapHook {
+            sender: sender.to_string(),
+            receiver: receiver.to_string(),
+            offer_asset: offer_asset.clone(),
+            return_asset: return_asset.clone(),
+            spread_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        },
+    )?;
 
     let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
     if !return_amount.is_zero() {
         messages.push(return_asset.into_msg(receiver.clone())?);
     }
 
-    // Handle the burn amount
-    if !burn_amount.is_zero() {
-        let burn_asset = Asset {
-            info: ask_pool.info.clone(),
-            amount: burn_amount,
-        };
-
-        let burn_handler_address = deps.api.addr_humanize(&pair_info.burn_address)?;
-
-        if let AssetInfo::NativeToken { denom } = &burn_asset.info {
-            // Call send_native for native tokens
-            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: burn_handler_address.to_string(),
-                msg: to_json_binary(&BurnAuctionExecuteMsg::SendNative {
-                    asset: burn_asset.clone(),
-                })?,
-                funds: vec![Coin {
-                    denom: denom.clone(),
-                    amount: burn_amount,
-                }],
-            }));
-        } else if let AssetInfo::Token { contract_addr } = &burn_asset.info {
-            // Send CW20 tokens directly to the burn address
-            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: contract_addr.clone(),
-                msg: to_json_binary(&Cw20ExecuteMsg::Send {
-                    contract: burn_handler_address.to_string(),
-                    amount: burn_amount,
-                    msg: Binary::default(),
-                })?,
-                funds: vec![],
-            }));
-        }
-    }
-
-    // Handle the fee wallet amount
-    if !fee_wallet_amount.is_zero() {
-        let fee_wallet_asset = Asset {
-            info: ask_pool.info.clone(),
-            amount: fee_wallet_amount,
-        };
-        messages.push(
-            fee_wallet_asset.into_msg(deps.api.addr_humanize(&pair_info.fee_wallet_address)?)?,
-        );
-    }
+    messages.extend(fee_messages(
+        deps.api,
+        &pair_info,
+        &ask_pool.info,
+        burn_amount,
+        fee_wallet_amount,
+    )?);
 
     // new pool amounts
     let offer_pool_post = offer_pool.amount.checked_add(offer_amount)?;
@@ -587,56 +1336,1867 @@ pub fn swap(
         .checked_sub(return_amount)?
         .checked_sub(fee_wallet_amount)?
         .checked_sub(burn_amount)?;
+    let reserves_post = if offer_index == 0 {
+        [offer_pool_post, ask_pool_post]
+    } else {
+        [ask_pool_post, offer_pool_post]
+    };
+    circuit_breaker::record(
+        deps.storage,
+        env.block.time.seconds(),
+        pair_info.asset_decimals,
+        reserves,
+        reserves_post,
+    )?;
+    RESERVES.save(deps.storage, &reserves_post)?;
+
+    // 1. send collateral token from the contract to a user
+    // 2. send inactive commission to collector
+    // 3. notify the swap hooks
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "swap"),
+            ("sender", sender.as_str()),
+            ("receiver", receiver.as_str()),
+            ("offer_asset", &offer_asset.info.to_string()),
+            ("ask_asset", &ask_pool.info.to_string()),
+            ("offer_amount", &offer_amount.to_string()),
+            ("return_amount", &return_amount.to_string()),
+            ("spread_amount", &spread_amount.to_string()),
+            ("commission_amount", &commission_amount.to_string()),
+            ("burn_amount", &burn_amount.to_string()),
+            ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+            ("pool_amount", &lp_amount.to_string()),
+            ("offer_pool_balance", &offer_pool_post.to_string()),
+            ("ask_pool_balance", &ask_pool_post.to_string()),
+        ]))
+}
+
+/// Queues a swap of a pair in batch auction mode, its offer is held until the
+/// batch settles
+fn queue_swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    batch_auction: &BatchAuction,
+    order: QueuedOrder,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if order.offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&order.offer_asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &order.sender,
+        order.offer_asset.amount,
+        pools[offer_index].amount,
+    )?;
+
+    let (order_id, settle_height) = batch::queue(
+        deps.storage,
+        env.block.height,
+        batch_auction,
+        offer_index,
+        &order,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "queue_swap"),
+        ("sender", order.sender.as_str()),
+        ("receiver", order.receiver.as_str()),
+        ("offer_asset", &order.offer_asset.info.to_string()),
+        ("offer_amount", &order.offer_asset.amount.to_string()),
+        ("order_id", &order_id.to_string()),
+        ("settle_height", &settle_height.to_string()),
+    ]))
+}
+
+/// Clears the queued swaps of a closed batch at a single price. Orders past
+/// their deadline are refunded, and so are orders the price breaks the spread
+/// limit of, the price being found again without them until every remaining
+/// order accepts it. A pair that stopped trading refunds the whole batch.
+pub fn settle_batch(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let batch = BATCH
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoBatch {})?;
+    if env.block.height < batch.settle_height {
+        return Err(ContractError::BatchNotClosed {
+            settle_height: batch.settle_height,
+        });
+    }
+    let orders = batch::take(deps.storage)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let reserves = [pools[0].amount, pools[1].amount];
+    let now = env.block.time.seconds();
+
+    let trading = assert_swaps_enabled(deps.storage).is_ok()
+        && !reserves[0].is_zero()
+        && !reserves[1].is_zero();
+    let (mut filled, mut refunded): (Vec<_>, Vec<_>) = orders
+        .into_iter()
+        .map(|order| {
+            let offer_index = if order.offer_asset.info.equal(&pools[0].info) {
+                0
+            } else {
+                1
+            };
+            (offer_index, order)
+        })
+        .partition(|(_, order)| trading && assert_deadline(now, order.deadline).is_ok());
+
+    let commission_bps = dynamic_fee::commission_bps(
+        deps.storage,
+        now,
+        reserves,
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+    let commission_rate = commission_rate(commission_bps);
+
+    // each order gets the fee discount of its sender
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let mut discounts = filled
+        .iter()
+        .map(|(_, order)| {
+            query_fee_discount(&deps.querier, factory_addr.clone(), &order.sender, None)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let (mut offer_totals, swaps) = loop {
+        let mut offer_totals = [Uint128::zero(); 2];
+        for (offer_index, order) in filled.iter() {
+            offer_totals[*offer_index] =
+                offer_totals[*offer_index].checked_add(order.offer_asset.amount)?;
+        }
+
+        let mut swaps = vec![];
+        let mut rejected = vec![];
+        for (i, (offer_index, order)) in filled.iter().enumerate() {
+            let ask_index = 1 - offer_index;
+            let (return_amount, spread_amount, commission_amount) = batch::compute_swap(
+                reserves,
+                offer_totals,
+                *offer_index,
+                order.offer_asset.amount,
+                commission_rate,
+            )?;
+            let (return_amount, commission_amount) =
+                apply_fee_discount(return_amount, commission_amount, discounts[i]);
+
+            let accepted = assert_max_spread(
+                order.belief_price,
+                order.max_spread,
+                order.offer_asset.clone(),
+                Asset {
+                    info: pools[ask_index].info.clone(),
+                    amount: return_amount,
+                },
+                spread_amount,
+                pair_info.asset_decimals[*offer_index],
+                pair_info.asset_decimals[ask_index],
+            )
+            .is_ok();
+            if accepted {
+                swaps.push((return_amount, spread_amount, commission_amount));
+            } else {
+                rejected.push(i);
+            }
+        }
+
+        if rejected.is_empty() {
+            break (offer_totals, swaps);
+        }
+        for i in rejected.into_iter().rev() {
+            refunded.push(filled.remove(i));
+            discounts.remove(i);
+        }
+    };
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut hook_messages: Vec<SubMsg<InjectiveMsgWrapper>> = vec![];
+    let mut reserves_post = reserves;
+    let mut fills = vec![];
+    if !filled.is_empty() {
+        let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address)?;
+
+        let mut batch_reserves = [
+            reserves[0].checked_add(offer_totals[0])?,
+            reserves[1].checked_add(offer_totals[1])?,
+        ];
+        for ((offer_index, _), (return_amount, spread_amount, commission_amount)) in
+            filled.iter().zip(swaps)
+        {
+            let ask_index = 1 - offer_index;
+            let fee_wallet_amount = commission_amount.mul_floor(fee_split.fee_wallet);
+            let burn_amount = commission_amount.mul_floor(fee_split.burn);
+            batch_reserves[ask_index] = batch_reserves[ask_index]
+                .checked_sub(return_amount)?
+                .checked_sub(fee_wallet_amount)?
+                .checked_sub(burn_amount)?;
+            fills.push((
+                return_amount,
+                spread_amount,
+                commission_amount,
+                burn_amount,
+                fee_wallet_amount,
+            ));
+        }
+
+        // a batch the circuit breaker rejects is refunded instead of
+        // blocking the settlement
+        match circuit_breaker::record(
+            deps.storage,
+            now,
+            pair_info.asset_decimals,
+            reserves,
+            batch_reserves,
+        ) {
+            Ok(()) => reserves_post = batch_reserves,
+            Err(ContractError::CircuitBreakerExceeded { .. }) => {
+                refunded.append(&mut filled);
+                offer_totals = [Uint128::zero(); 2];
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    if !filled.is_empty() {
+        // prices are accumulated with the reserves from before the batch
+        twap::accumulate_prices(deps.storage, now, reserves, pair_info.asset_decimals)?;
+        dynamic_fee::record_swap(
+            deps.storage,
+            now,
+            reserves,
+            pair_info.asset_decimals,
+            pair_info.commission_bps,
+        )?;
+
+        let mut burn_amounts = [Uint128::zero(); 2];
+        let mut fee_wallet_amounts = [Uint128::zero(); 2];
+        for (
+            (offer_index, order),
+            (return_amount, spread_amount, commission_amount, burn_amount, fee_wallet_amount),
+        ) in filled.iter().zip(fills)
+        {
+            let ask_index = 1 - offer_index;
+            burn_amounts[ask_index] += burn_amount;
+            fee_wallet_amounts[ask_index] += fee_wallet_amount;
+
+            stats::record(
+                deps.storage,
+                now,
+                &stats::swap(
+                    *offer_index,
+                    order.offer_asset.amount,
+                    return_amount,
+                    commission_amount,
+                    burn_amount,
+                    fee_wallet_amount,
+                ),
+            )?;
+
+            let return_asset = Asset {
+                info: pools[ask_index].info.clone(),
+                amount: return_amount,
+            };
+            hook_messages.extend(swap_hook_messages(
+                deps.storage,
+                &SwapHookMsg::SwapHook {
+                    sender: order.sender.to_string(),
+                    receiver: order.receiver.to_string(),
+                    offer_asset: order.offer_asset.clone(),
+                    return_asset: return_asset.clone(),
+                    spread_amount,
+                    commission_amount,
+                    burn_amount,
+                    fee_wallet_amount,
+                },
+            )?);
+
+            if !return_amount.is_zero() {
+                messages.push(return_asset.into_msg(order.receiver.clone())?);
+            }
+        }
+
+        for (pool, (burn_amount, fee_wallet_amount)) in pools
+            .iter()
+            .zip(burn_amounts.iter().zip(fee_wallet_amounts.iter()))
+        {
+            messages.extend(fee_messages(
+                deps.api,
+                &pair_info,
+                &pool.info,
+                *burn_amount,
+                *fee_wallet_amount,
+            )?);
+        }
+
+        RESERVES.save(deps.storage, &reserves_post)?;
+    }
+
+    for (_, order) in refunded.iter() {
+        messages.push(order.offer_asset.clone().into_msg(order.sender.clone())?);
+    }
+
+    let (clearing_price, _) = twap::prices(
+        [
+            reserves[0].checked_add(offer_totals[0])?,
+            reserves[1].checked_add(offer_totals[1])?,
+        ],
+        pair_info.asset_decimals,
+    );
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "settle_batch"),
+            ("filled_orders", &filled.len().to_string()),
+            ("refunded_orders", &refunded.len().to_string()),
+            ("clearing_price", &clearing_price.to_string()),
+            (
+                "reserves",
+                &format!(
+                    "{}{}, {}{}",
+                    reserves_post[0], pools[0].info, reserves_post[1], pools[1].info
+                ),
+            ),
+        ]))
+}
+
+/// Swaps the least offer asset that returns `ask_asset`, priced like a
+/// `reverse_simulation` against the reserves before the swap
+pub fn swap_exact_out(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    ask_asset: Asset,
+    max_offer: Asset,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_swaps_enabled(deps.storage)?;
+    assert_no_batch_auction(deps.storage)?;
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    allowlist::assert_swap(
+        deps.storage,
+        &[&info.sender, to.as_ref().unwrap_or(&info.sender)],
+    )?;
+
+    max_offer.assert_sent_native_token_balance(&info)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&max_offer.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    let ask_index = 1 - offer_index;
+    if !pools[ask_index].info.equal(&ask_asset.info) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let reserves = [pools[0].amount, pools[1].amount];
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+    )?;
+    let commission_bps = dynamic_fee::record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let discount = query_fee_discount(&deps.querier, factory_addr.clone(), &info.sender, None)?;
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let (offer_amount, spread_amount, commission_amount) = compute_pair_offer_amount(
+        &pair_info,
+        &curve,
+        offer_index,
+        pools[offer_index].amount,
+        pools[ask_index].amount,
+        ask_asset.amount,
+        discounted_commission_rate(commission_bps, discount),
+    )?;
+
+    if offer_amount > max_offer.amount {
+        return Err(ContractError::MaxOfferAssertion {
+            max_offer: max_offer.amount.to_string(),
+            offer: offer_amount.to_string(),
+        });
+    }
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &info.sender,
+        offer_amount,
+        pools[offer_index].amount,
+    )?;
+
+    let receiver = to.unwrap_or_else(|| info.sender.clone());
+
+    let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address.clone())?;
+    let fee_wallet_amount = commission_amount.mul_floor(fee_split.fee_wallet);
+    let burn_amount = commission_amount.mul_floor(fee_split.burn);
+
+    let mut reserves = [Uint128::zero(); 2];
+    reserves[offer_index] = pools[offer_index].amount.checked_add(offer_amount)?;
+    reserves[ask_index] = pools[ask_index]
+        .amount
+        .checked_sub(ask_asset.amount)?
+        .checked_sub(burn_amount)?
+        .checked_sub(fee_wallet_amount)?;
+    circuit_breaker::record(
+        deps.storage,
+        env.block.time.seconds(),
+        pair_info.asset_decimals,
+        [pools[0].amount, pools[1].amount],
+        reserves,
+    )?;
+    RESERVES.save(deps.storage, &reserves)?;
+    stats::record(
+        deps.storage,
+        env.block.time.seconds(),
+        &stats::swap(
+            offer_index,
+            offer_amount,
+            ask_asset.amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        ),
+    )?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    match &max_offer.info {
+        AssetInfo::NativeToken { denom } => {
+            let refund_amount = max_offer.amount - offer_amount;
+            if !refund_amount.is_zero() {
+                messages.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: coins(refund_amount.u128(), denom),
+                }));
+            }
+        }
+        AssetInfo::Token { contract_addr } => {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: offer_amount,
+                })?,
+                funds: vec![],
+            }));
+        }
+    }
+
+    messages.push(ask_asset.clone().into_msg(receiver.clone())?);
+    messages.extend(fee_messages(
+        deps.api,
+        &pair_info,
+        &ask_asset.info,
+        burn_amount,
+        fee_wallet_amount,
+    )?);
+
+    let hook_messages = swap_hook_messages(
+        deps.storage,
+        &SwapHookMsg::SwapHook {
+            sender: info.sender.to_string(),
+            receiver: receiver.to_string(),
+            offer_asset: Asset {
+                info: max_offer.info.clone(),
+                amount: offer_amount,
+            },
+            return_asset: ask_asset.clone(),
+            spread_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "swap_exact_out"),
+            ("sender", info.sender.as_str()),
+            ("receiver", receiver.as_str()),
+            ("offer_asset", &max_offer.info.to_string()),
+            ("ask_asset", &ask_asset.info.to_string()),
+            ("offer_amount", &offer_amount.to_string()),
+            ("return_amount", &ask_asset.amount.to_string()),
+            ("spread_amount", &spread_amount.to_string()),
+            ("commission_amount", &commission_amount.to_string()),
+            ("burn_amount", &burn_amount.to_string()),
+            ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+        ]))
+}
+
+/// Outcome of swapping `swap_amount` of a zap in and depositing the rest
+struct ZapInSwap {
+    swap_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+    commission_amount: Uint128,
+    burn_amount: Uint128,
+    fee_wallet_amount: Uint128,
+    share: Uint128,
+    /// The offer asset is not deposited in a lower ratio than the returned asset
+    offer_in_excess: bool,
+}
+
+// CONTRACT - a user must do token approval
+#[allow(clippy::too_many_arguments)]
+pub fn zap_in(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    min_lp: Option<Uint128>,
+    receiver: Option<String>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+    assert_swaps_enabled(deps.storage)?;
+    assert_no_batch_auction(deps.storage)?;
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&offer_asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    let ask_index = 1 - offer_index;
+
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+    )?;
+    let commission_bps = dynamic_fee::record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    if total_share.is_zero() {
+        return Err(ContractError::ZapInEmptyPool {});
+    }
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let discount = query_fee_discount(&deps.querier, factory_addr.clone(), &sender, None)?;
+    let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address.clone())?;
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+
+    let offer_pool = pools[offer_index].amount;
+    let ask_pool = pools[ask_index].amount;
+    let zap = |swap_amount: Uint128| -> Result<ZapInSwap, ContractError> {
+        let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+            &pair_info,
+            &curve,
+            offer_index,
+            offer_pool,
+            ask_pool,
+            swap_amount,
+            commission_bps,
+        )?;
+        let (return_amount, commission_amount) =
+            apply_fee_discount(return_amount, commission_amount, discount);
+        let burn_amount = commission_amount.mul_floor(fee_split.burn);
+        let fee_wallet_amount = commission_amount.mul_floor(fee_split.fee_wallet);
+
+        let offer_deposit = offer_asset.amount - swap_amount;
+        let offer_pool_post = offer_pool.checked_add(swap_amount)?;
+        let ask_pool_post = ask_pool
+            .checked_sub(return_amount)?
+            .checked_sub(burn_amount)?
+            .checked_sub(fee_wallet_amount)?;
+
+        Ok(ZapInSwap {
+            swap_amount,
+            return_amount,
+            spread_amount,
+            commission_amount,
+            burn_amount,
+            fee_wallet_amount,
+            share: std::cmp::min(
+                offer_deposit.multiply_ratio(total_share, offer_pool_post),
+                return_amount.multiply_ratio(total_share, ask_pool_post),
+            ),
+            offer_in_excess: offer_deposit.full_mul(ask_pool_post)
+                >= return_amount.full_mul(offer_pool_post),
+        })
+    };
+
+    // the offer deposit shrinks and the returned amount grows with the swapped
+    // amount, search for the point where both are deposited in the pool ratio
+    let max_swap_amount = match curve {
+        Curve::Weighted { .. } => weighted::max_offer_amount(offer_pool).min(offer_asset.amount),
+        _ => offer_asset.amount,
+    };
+    let (mut low, mut high) = (zap(Uint128::zero())?, zap(max_swap_amount)?);
+    while high.swap_amount - low.swap_amount > Uint128::one() {
+        let mid = zap(low.swap_amount + (high.swap_amount - low.swap_amount) / Uint128::from(2u8))?;
+        if mid.offer_in_excess {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    let zap_in = if high.share > low.share { high } else { low };
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &sender,
+        zap_in.swap_amount,
+        offer_pool,
+    )?;
+
+    if zap_in.share.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    if let Some(min_lp) = min_lp {
+        if zap_in.share < min_lp {
+            return Err(ContractError::MinLpAssertion {
+                min_lp: min_lp.to_string(),
+                share: zap_in.share.to_string(),
+            });
+        }
+    }
+
+    // the total lp token cannot exceed the max value of a Uint128
+    if total_share.checked_add(zap_in.share).is_err() {
+        return Err(ContractError::LpSupplyOverflow {});
+    }
+
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => sender.clone(),
+    };
+    allowlist::assert_provide(deps.storage, &[&sender, &receiver])?;
+    holding::record_mint(
+        deps.storage,
+        env.block.time.seconds(),
+        &receiver,
+        zap_in.share,
+    )?;
+
+    // the whole offer is deposited, the returned asset stays in the pool
+    let mut reserves = [Uint128::zero(); 2];
+    reserves[offer_index] = offer_pool.checked_add(offer_asset.amount)?;
+    reserves[ask_index] = ask_pool
+        .checked_sub(zap_in.burn_amount)?
+        .checked_sub(zap_in.fee_wallet_amount)?;
+    // only the swap counts towards the circuit breaker, not the deposit after it
+    let mut swapped = [Uint128::zero(); 2];
+    swapped[offer_index] = offer_pool.checked_add(zap_in.swap_amount)?;
+    swapped[ask_index] = reserves[ask_index].checked_sub(zap_in.return_amount)?;
+    circuit_breaker::record(
+        deps.storage,
+        env.block.time.seconds(),
+        pair_info.asset_decimals,
+        [pools[0].amount, pools[1].amount],
+        swapped,
+    )?;
+    RESERVES.save(deps.storage, &reserves)?;
+    stats::record(
+        deps.storage,
+        env.block.time.seconds(),
+        &stats::swap(
+            offer_index,
+            zap_in.swap_amount,
+            zap_in.return_amount,
+            zap_in.commission_amount,
+            zap_in.burn_amount,
+            zap_in.fee_wallet_amount,
+        ),
+    )?;
+
+    let hook_messages = swap_hook_messages(
+        deps.storage,
+        &SwapHookMsg::SwapHook {
+            sender: sender.to_string(),
+            receiver: receiver.to_string(),
+            offer_asset: Asset {
+                info: offer_asset.info.clone(),
+                amount: zap_in.swap_amount,
+            },
+            return_asset: Asset {
+                info: pools[ask_index].info.clone(),
+                amount: zap_in.return_amount,
+            },
+            spread_amount: zap_in.spread_amount,
+            commission_amount: zap_in.commission_amount,
+            burn_amount: zap_in.burn_amount,
+            fee_wallet_amount: zap_in.fee_wallet_amount,
+        },
+    )?;
+
+    let mut messages = fee_messages(
+        deps.api,
+        &pair_info,
+        &pools[ask_index].info,
+        zap_in.burn_amount,
+        zap_in.fee_wallet_amount,
+    )?;
+    messages.push(create_mint_tokens_msg(
+        env.contract.address.clone(),
+        Coin {
+            denom: pair_info.liquidity_token.clone(),
+            amount: zap_in.share,
+        },
+        receiver.to_string(),
+    ));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "zap_in"),
+            ("sender", sender.as_str()),
+            ("receiver", receiver.as_str()),
+            ("offer_asset", &offer_asset.to_string()),
+            ("swap_amount", &zap_in.swap_amount.to_string()),
+            ("return_amount", &zap_in.return_amount.to_string()),
+            ("commission_amount", &zap_in.commission_amount.to_string()),
+            ("burn_amount", &zap_in.burn_amount.to_string()),
+            ("fee_wallet_amount", &zap_in.fee_wallet_amount.to_string()),
+            ("share", &zap_in.share.to_string()),
+        ]))
+}
+
+/// Messages sending the protocol share of a commission to the burn handler and the fee wallet
+fn fee_messages(
+    api: &dyn Api,
+    pair_info: &PairInfoRaw,
+    asset_info: &AssetInfo,
+    burn_amount: Uint128,
+    fee_wallet_amount: Uint128,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+
+    // Handle the burn amount
+    if !burn_amount.is_zero() {
+        let burn_asset = Asset {
+            info: asset_info.clone(),
+            amount: burn_amount,
+        };
+
+        let burn_handler_address = api.addr_humanize(&pair_info.burn_address)?;
+
+        if let AssetInfo::NativeToken { denom } = &burn_asset.info {
+            // Call send_native for native tokens
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: burn_handler_address.to_string(),
+                msg: to_json_binary(&BurnAuctionExecuteMsg::SendNative {
+                    asset: burn_asset.clone(),
+                })?,
+                funds: vec![Coin {
+                    denom: denom.clone(),
+                    amount: burn_amount,
+                }],
+            }));
+        } else if let AssetInfo::Token { contract_addr } = &burn_asset.info {
+            // Send CW20 tokens directly to the burn address
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: burn_handler_address.to_string(),
+                    amount: burn_amount,
+                    msg: Binary::default(),
+                })?,
+                funds: vec![],
+            }));
+        }
+    }
+
+    // Handle the fee wallet amount
+    if !fee_wallet_amount.is_zero() {
+        let fee_wallet_asset = Asset {
+            info: asset_info.clone(),
+            amount: fee_wallet_amount,
+        };
+        let fee_wallet_address = api.addr_humanize(&pair_info.fee_wallet_address)?;
+        messages.push(fee_wallet_asset.into_msg(fee_wallet_address)?);
+    }
+
+    Ok(messages)
+}
+
+/// Notifications of a swap to the pair's hooks. Each runs with a gas limit and
+/// its failure is caught by the reply, reverting only the hook.
+fn swap_hook_messages(
+    storage: &dyn Storage,
+    hook_msg: &SwapHookMsg,
+) -> StdResult<Vec<SubMsg<InjectiveMsgWrapper>>> {
+    let hooks = SWAP_HOOKS.may_load(storage)?.unwrap_or_default();
+    let msg = to_json_binary(hook_msg)?;
+
+    Ok(hooks
+        .into_iter()
+        .map(|hook| {
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: hook.to_string(),
+                    msg: msg.clone(),
+                    funds: vec![],
+                },
+                SWAP_HOOK_REPLY_ID,
+            )
+            .with_gas_limit(SWAP_HOOK_GAS_LIMIT)
+            .with_payload(hook.as_bytes().to_vec())
+        })
+        .collect())
+}
+
+pub fn flash_swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    to: Option<String>,
+    msg: Binary,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_swaps_enabled(deps.storage)?;
+    assert_no_batch_auction(deps.storage)?;
+    if asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let balances: [Asset; 2] =
+        pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    let index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    if asset.amount >= pools[index].amount {
+        return Err(ContractError::FlashSwapExceedsReserve {});
+    }
+    launch::assert_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &info.sender,
+        asset.amount,
+        pools[index].amount,
+    )?;
+
+    let reserves = [pools[0].amount, pools[1].amount];
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+    )?;
+    let commission_bps = dynamic_fee::record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
+    let receiver = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender.clone(),
+    };
+    allowlist::assert_swap(deps.storage, &[&info.sender, &receiver])?;
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let discount = query_fee_discount(&deps.querier, factory_addr, &info.sender, None)?;
+
+    // smallest repayment in the borrowed asset that still covers its own commission
+    let rate = discounted_commission_rate(commission_bps, discount);
+    let mut repay_amount: Uint128 = Uint256::from(asset.amount)
+        .multiply_ratio(
+            Decimal256::one().atomics(),
+            (Decimal256::one() - rate).atomics(),
+        )
+        .try_into()?;
+    while repay_amount - commission(repay_amount, rate)? < asset.amount {
+        repay_amount += Uint128::one();
+    }
+
+    let mut amounts_out = [Uint128::zero(); 2];
+    amounts_out[index] = asset.amount;
+    FLASH_SWAP.save(
+        deps.storage,
+        &FlashSwapState {
+            borrower: receiver.clone(),
+            pools: reserves,
+            balances: [balances[0].amount, balances[1].amount],
+            amounts_out,
+            commission_bps,
+            discount,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(asset.clone().into_msg(receiver.clone())?)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: receiver.to_string(),
+                msg: to_json_binary(&FlashSwapCallbackMsg::FlashSwapCallback {
+                    initiator: info.sender.to_string(),
+                    asset: asset.clone(),
+                    repay_amount,
+                    msg,
+                })?,
+                funds: vec![],
+            },
+            FLASH_SWAP_REPLY_ID,
+        ))
+        .add_attributes(vec![
+            ("action", "flash_swap"),
+            ("sender", info.sender.as_str()),
+            ("receiver", receiver.as_str()),
+            ("asset", &asset.to_string()),
+            ("repay_amount", &repay_amount.to_string()),
+        ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    match msg.id {
+        FLASH_SWAP_REPLY_ID => settle_flash_swap(deps, env),
+        SWAP_HOOK_REPLY_ID => Ok(swap_hook_failed(msg)),
+        _ => Err(StdError::generic_err("invalid reply msg").into()),
+    }
+}
+
+/// A swap hook failed, its changes are reverted and the swap stands
+fn swap_hook_failed(msg: Reply) -> Response<InjectiveMsgWrapper> {
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => String::new(),
+    };
+
+    Response::new().add_attributes(vec![
+        ("action", "swap_hook_failed"),
+        ("hook", &String::from_utf8_lossy(msg.payload.as_slice())),
+        ("error", &error),
+    ])
+}
+
+/// Checks that the borrower paid back enough for the invariant, net of the
+/// commission on everything paid in, to be no lower than before the loan
+fn settle_flash_swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let flash_swap = FLASH_SWAP.load(deps.storage)?;
+    FLASH_SWAP.remove(deps.storage);
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] =
+        pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    let rate = discounted_commission_rate(flash_swap.commission_bps, flash_swap.discount);
+    let mut amounts_in = [Uint128::zero(); 2];
+    let mut commissions = [Uint128::zero(); 2];
+    let mut reserves = [Uint128::zero(); 2];
+    let mut adjusted = [Uint128::zero(); 2];
+    for i in 0..2 {
+        let remaining = flash_swap.balances[i].checked_sub(flash_swap.amounts_out[i])?;
+        amounts_in[i] = pools[i].amount.saturating_sub(remaining);
+        commissions[i] = commission(amounts_in[i], rate)?;
+        reserves[i] =
+            (flash_swap.pools[i] - flash_swap.amounts_out[i]).checked_add(amounts_in[i])?;
+        adjusted[i] = reserves[i] - commissions[i];
+    }
+
+    let repaid = match load_curve(deps.storage, &env, &pair_info.pair_type)? {
+        Curve::ConstantProduct => {
+            Uint256::from(adjusted[0]) * Uint256::from(adjusted[1])
+                >= Uint256::from(flash_swap.pools[0]) * Uint256::from(flash_swap.pools[1])
+        }
+        Curve::StableSwap { amp } => {
+            stableswap::compute_invariant(amp, adjusted, pair_info.asset_decimals)?
+                >= stableswap::compute_invariant(amp, flash_swap.pools, pair_info.asset_decimals)?
+        }
+        Curve::Weighted { weights } => {
+            weighted::invariant_ratio(flash_swap.pools, adjusted, weights)? >= Decimal256::one()
+        }
+    };
+    if !repaid {
+        return Err(ContractError::FlashSwapNotRepaid {});
+    }
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut burn_amounts = [Uint128::zero(); 2];
+    let mut fee_wallet_amounts = [Uint128::zero(); 2];
+    for i in 0..2 {
+        burn_amounts[i] = commissions[i].mul_floor(fee_split.burn);
+        fee_wallet_amounts[i] = commissions[i].mul_floor(fee_split.fee_wallet);
+        messages.extend(fee_messages(
+            deps.api,
+            &pair_info,
+            &pools[i].info,
+            burn_amounts[i],
+            fee_wallet_amounts[i],
+        )?);
+        reserves[i] = reserves[i] - burn_amounts[i] - fee_wallet_amounts[i];
+    }
+    circuit_breaker::record(
+        deps.storage,
+        env.block.time.seconds(),
+        pair_info.asset_decimals,
+        flash_swap.pools,
+        reserves,
+    )?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    stats::record(
+        deps.storage,
+        env.block.time.seconds(),
+        &TradingStats {
+            swap_count: 1,
+            volume: [
+                flash_swap.amounts_out[0].checked_add(amounts_in[0])?,
+                flash_swap.amounts_out[1].checked_add(amounts_in[1])?,
+            ],
+            commission_amount: commissions,
+            burn_amount: burn_amounts,
+            fee_wallet_amount: fee_wallet_amounts,
+        },
+    )?;
+
+    // the borrowed asset is returned for what was paid in, the other asset
+    // when it was repaid in it and the borrowed one otherwise
+    let borrowed = if flash_swap.amounts_out[0].is_zero() {
+        1
+    } else {
+        0
+    };
+    let repaid = if amounts_in[1 - borrowed].is_zero() {
+        borrowed
+    } else {
+        1 - borrowed
+    };
+    let hook_messages = swap_hook_messages(
+        deps.storage,
+        &SwapHookMsg::SwapHook {
+            sender: flash_swap.borrower.to_string(),
+            receiver: flash_swap.borrower.to_string(),
+            offer_asset: Asset {
+                info: pools[repaid].info.clone(),
+                amount: amounts_in[repaid],
+            },
+            return_asset: Asset {
+                info: pools[borrowed].info.clone(),
+                amount: flash_swap.amounts_out[borrowed],
+            },
+            spread_amount: Uint128::zero(),
+            commission_amount: commissions[repaid],
+            burn_amount: burn_amounts[repaid],
+            fee_wallet_amount: fee_wallet_amounts[repaid],
+        },
+    )?;
+
+    let format_amounts = |amounts: [Uint128; 2]| {
+        format!(
+            "{}{}, {}{}",
+            amounts[0], pools[0].info, amounts[1], pools[1].info
+        )
+    };
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_messages)
+        .add_attributes(vec![
+            ("action", "settle_flash_swap"),
+            ("borrower", flash_swap.borrower.as_str()),
+            ("commission_amounts", &format_amounts(commissions)),
+            ("burn_amounts", &format_amounts(burn_amounts)),
+            ("fee_wallet_amounts", &format_amounts(fee_wallet_amounts)),
+        ]))
+}
+
+fn commission(amount: Uint128, rate: Decimal256) -> Result<Uint128, ContractError> {
+    Ok(Uint256::from(amount).mul_ceil(rate).try_into()?)
+}
+
+/// Sends the balances held above the reserves, tokens transferred to the pair
+/// outside of its messages, to `to`
+pub fn skim(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    to: Option<String>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let reserves = RESERVES.load(deps.storage)?;
+    let balances = query_owned_balances(deps.as_ref(), &pair_info, env.contract.address)?;
+
+    let receiver = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender.clone(),
+    };
+
+    let surplus: Vec<Asset> = balances
+        .iter()
+        .zip(reserves.iter())
+        .map(|(balance, reserve)| Asset {
+            info: balance.info.clone(),
+            amount: balance.amount.saturating_sub(*reserve),
+        })
+        .collect();
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for asset in surplus.iter() {
+        if !asset.amount.is_zero() {
+            messages.push(asset.clone().into_msg(receiver.clone())?);
+        }
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "skim"),
+        ("sender", info.sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("assets", &format!("{}, {}", surplus[0], surplus[1])),
+    ]))
+}
+
+/// Adopts the balances of the pair as its reserves, gifting any surplus to the
+/// liquidity providers
+pub fn sync(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let reserves = RESERVES.load(deps.storage)?;
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+    )?;
+
+    let balances = query_owned_balances(deps.as_ref(), &pair_info, env.contract.address)?;
+    RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "sync"),
+        ("reserves", &format!("{}, {}", balances[0], balances[1])),
+    ]))
+}
+
+/// Adds `asset` to the reserves without minting LP tokens, raising the value
+/// of every outstanding share
+pub fn donate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    asset: Asset,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_provide_enabled(deps.storage)?;
+    if asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    // nobody would own a donation to an empty pool until the first provide
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    if total_share.is_zero() {
+        return Err(ContractError::DonateEmptyPool {});
+    }
+
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    twap::accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        reserves,
+        pair_info.asset_decimals,
+    )?;
+    reserves[index] = reserves[index].checked_add(asset.amount)?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "donate"),
+        ("sender", sender.as_str()),
+        ("asset", &asset.to_string()),
+    ]))
+}
+
+/// Stages `assets` as a pending deposit of `sender`, held outside the reserves
+/// until a CW20 `provide_liquidity` hook of the sender provides them
+pub fn deposit(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    sender: Addr,
+    assets: Vec<Asset>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_provide_enabled(deps.storage)?;
+    if assets.iter().all(|asset| asset.amount.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let mut pending = DEPOSITS
+        .may_load(deps.storage, &sender)?
+        .unwrap_or_default();
+    let mut total = DEPOSITS_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    for asset in assets.iter() {
+        let index = pools
+            .iter()
+            .position(|pool| pool.info.equal(&asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        pending[index] = pending[index].checked_add(asset.amount)?;
+        total[index] = total[index].checked_add(asset.amount)?;
+    }
+    DEPOSITS.save(deps.storage, &sender, &pending)?;
+    DEPOSITS_TOTAL.save(deps.storage, &total)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "deposit"),
+        ("sender", sender.as_str()),
+        (
+            "pending_deposit",
+            &format!(
+                "{}{}, {}{}",
+                pending[0], pools[0].info, pending[1], pools[1].info
+            ),
+        ),
+    ]))
+}
+
+pub fn refund_deposit(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let pending = take_deposit(deps.storage, &info.sender)?;
+
+    let refund_assets: Vec<Asset> = pools
+        .iter()
+        .zip(pending.iter())
+        .map(|(pool, amount)| Asset {
+            info: pool.info.clone(),
+            amount: *amount,
+        })
+        .collect();
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for asset in refund_assets.iter() {
+        if !asset.amount.is_zero() {
+            messages.push(asset.clone().into_msg(info.sender.clone())?);
+        }
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "refund_deposit"),
+        ("sender", info.sender.as_str()),
+        (
+            "refund_assets",
+            &format!("{}, {}", refund_assets[0], refund_assets[1]),
+        ),
+    ]))
+}
+
+/// Removes the pending deposit of `sender`
+fn take_deposit(storage: &mut dyn Storage, sender: &Addr) -> Result<[Uint128; 2], ContractError> {
+    let pending = DEPOSITS
+        .may_load(storage, sender)?
+        .ok_or(ContractError::NoPendingDeposit {})?;
+    DEPOSITS.remove(storage, sender);
+
+    let total = DEPOSITS_TOTAL.load(storage)?;
+    DEPOSITS_TOTAL.save(
+        storage,
+        &[
+            total[0].checked_sub(pending[0])?,
+            total[1].checked_sub(pending[1])?,
+        ],
+    )?;
+
+    Ok(pending)
+}
+
+/// Balances of the pair, without the pending deposits and queued swaps it holds
+fn query_owned_balances(
+    deps: Deps<InjectiveQueryWrapper>,
+    pair_info: &PairInfoRaw,
+    contract_addr: Addr,
+) -> Result<[Asset; 2], ContractError> {
+    let mut balances: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let deposits = DEPOSITS_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    for (balance, deposit) in balances.iter_mut().zip(deposits.iter()) {
+        balance.amount = balance.amount.saturating_sub(*deposit);
+    }
+    let queued = BATCH
+        .may_load(deps.storage)?
+        .map(|batch| batch.offer_totals)
+        .unwrap_or_default();
+    for (balance, offer) in balances.iter_mut().zip(queued.iter()) {
+        balance.amount = balance.amount.saturating_sub(*offer);
+    }
+
+    Ok(balances)
+}
+
+pub fn ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    future_amp: u64,
+    future_time: u64,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let now = env.block.time.seconds();
+    let amp = AMP
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotStableSwap {})?
+        .current(now);
+
+    if future_amp == 0 || future_amp > MAX_AMP {
+        return Err(ContractError::InvalidAmp { max_amp: MAX_AMP });
+    }
+
+    if future_amp > amp * MAX_AMP_CHANGE || future_amp * MAX_AMP_CHANGE < amp {
+        return Err(ContractError::MaxAmpChangeAssertion {
+            max_change: MAX_AMP_CHANGE,
+        });
+    }
+
+    if future_time < now + MIN_AMP_RAMP_TIME {
+        return Err(ContractError::MinAmpRampTimeAssertion {
+            min_ramp_time: MIN_AMP_RAMP_TIME,
+        });
+    }
+
+    AMP.save(
+        deps.storage,
+        &AmpConfig {
+            init_amp: amp,
+            init_amp_time: now,
+            next_amp: future_amp,
+            next_amp_time: future_time,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "ramp_amp"),
+        ("init_amp", &amp.to_string()),
+        ("future_amp", &future_amp.to_string()),
+        ("future_time", &future_time.to_string()),
+    ]))
+}
+
+pub fn stop_ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let now = env.block.time.seconds();
+    let amp = AMP
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotStableSwap {})?
+        .current(now);
+
+    AMP.save(
+        deps.storage,
+        &AmpConfig {
+            init_amp: amp,
+            init_amp_time: now,
+            next_amp: amp,
+            next_amp_time: now,
+        },
+    )?;
+
+    Ok(
+        Response::new()
+            .add_attributes(vec![("action", "stop_ramp_amp"), ("amp", &amp.to_string())]),
+    )
+}
+
+pub fn update_status(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    status: PairStatus,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    STATUS.save(deps.storage, &status)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_status"),
+        ("status", &status.to_string()),
+    ]))
+}
+
+pub fn update_dynamic_fee(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    dynamic_fee: Option<DynamicFee>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let dynamic_fee = match dynamic_fee {
+        Some(dynamic_fee) => dynamic_fee,
+        None => {
+            DYNAMIC_FEE.remove(deps.storage);
+            VOLATILITY.remove(deps.storage);
+
+            return Ok(Response::new().add_attributes(vec![
+                ("action", "update_dynamic_fee"),
+                ("dynamic_fee", "none"),
+            ]));
+        }
+    };
+
+    if dynamic_fee.min_fee_bps == 0
+        || dynamic_fee.min_fee_bps > dynamic_fee.max_fee_bps
+        || dynamic_fee.max_fee_bps > MAX_COMMISSION_BPS
+    {
+        return Err(ContractError::InvalidDynamicFee {
+            max_fee_bps: MAX_COMMISSION_BPS,
+        });
+    }
+    if dynamic_fee.decay_period == 0 {
+        return Err(ContractError::InvalidDecayPeriod {});
+    }
+
+    DYNAMIC_FEE.save(deps.storage, &dynamic_fee)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_dynamic_fee"),
+        ("min_fee_bps", &dynamic_fee.min_fee_bps.to_string()),
+        ("max_fee_bps", &dynamic_fee.max_fee_bps.to_string()),
+        (
+            "volatility_multiplier",
+            &dynamic_fee.volatility_multiplier.to_string(),
+        ),
+        ("decay_period", &dynamic_fee.decay_period.to_string()),
+    ]))
+}
+
+pub fn update_swap_hooks(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    hooks: Vec<String>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    if hooks.len() > MAX_SWAP_HOOKS {
+        return Err(ContractError::TooManySwapHooks {
+            max: MAX_SWAP_HOOKS as u32,
+        });
+    }
+    let hooks = hooks
+        .iter()
+        .map(|hook| deps.api.addr_validate(hook))
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    if hooks.is_empty() {
+        SWAP_HOOKS.remove(deps.storage);
+    } else {
+        SWAP_HOOKS.save(deps.storage, &hooks)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_swap_hooks"),
+        (
+            "hooks",
+            &hooks
+                .iter()
+                .map(|hook| hook.as_str())
+                .collect::<Vec<&str>>()
+                .join(","),
+        ),
+    ]))
+}
+
+pub fn update_batch_auction(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    batch_auction: Option<BatchAuction>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let batch_auction = match batch_auction {
+        Some(batch_auction) => batch_auction,
+        None => {
+            BATCH_AUCTION.remove(deps.storage);
+
+            return Ok(Response::new().add_attributes(vec![
+                ("action", "update_batch_auction"),
+                ("batch_auction", "none"),
+            ]));
+        }
+    };
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    if pair_info.pair_type != PairType::ConstantProduct {
+        return Err(ContractError::BatchAuctionUnsupported {});
+    }
+    if batch_auction.interval_blocks == 0
+        || batch_auction
+            .min_offer_amounts
+            .iter()
+            .any(|amount| amount.is_zero())
+    {
+        return Err(ContractError::InvalidBatchAuction {});
+    }
+
+    BATCH_AUCTION.save(deps.storage, &batch_auction)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_batch_auction"),
+        (
+            "interval_blocks",
+            &batch_auction.interval_blocks.to_string(),
+        ),
+        (
+            "min_offer_amounts",
+            &format!(
+                "{}, {}",
+                batch_auction.min_offer_amounts[0], batch_auction.min_offer_amounts[1]
+            ),
+        ),
+    ]))
+}
+
+pub fn update_circuit_breaker(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    circuit_breaker: Option<CircuitBreaker>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    // flows of the current window were measured against the old thresholds
+    BREAKER_WINDOW.remove(deps.storage);
+    let circuit_breaker = match circuit_breaker {
+        Some(circuit_breaker) => circuit_breaker,
+        None => {
+            CIRCUIT_BREAKER.remove(deps.storage);
+
+            return Ok(Response::new().add_attributes(vec![
+                ("action", "update_circuit_breaker"),
+                ("circuit_breaker", "none"),
+            ]));
+        }
+    };
+
+    circuit_breaker::validate(&circuit_breaker)?;
+    CIRCUIT_BREAKER.save(deps.storage, &circuit_breaker)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_circuit_breaker"),
+        ("window", &circuit_breaker.window.to_string()),
+        (
+            "max_price_drop",
+            &circuit_breaker.max_price_drop.to_string(),
+        ),
+        ("max_outflow", &circuit_breaker.max_outflow.to_string()),
+    ]))
+}
+
+/// Closes the circuit breaker window, the next swap opens a new one
+pub fn reset_circuit_breaker(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    BREAKER_WINDOW.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![("action", "reset_circuit_breaker")]))
+}
+
+pub fn update_holding_period(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    holding_period: Option<HoldingPeriod>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_factory(deps.as_ref(), &info)?;
+
+    let holding_period = match holding_period {
+        Some(holding_period) => holding_period,
+        None => {
+            HOLDING_PERIOD.remove(deps.storage);
+            holding::reset(deps.storage)?;
+
+            return Ok(Response::new().add_attributes(vec![
+                ("action", "update_holding_period"),
+                ("holding_period", "none"),
+            ]));
+        }
+    };
+    holding::validate(&holding_period)?;
+    HOLDING_PERIOD.save(deps.storage, &holding_period)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_holding_period"),
+        ("seconds", &holding_period.seconds.to_string()),
+        (
+            "early_withdrawal_penalty",
+            &holding_period
+                .early_withdrawal_penalty
+                .map(|penalty| penalty.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ),
+    ]))
+}
+
+pub fn record_lp_deposit(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let balance = query_balance(
+        &deps.querier,
+        info.sender.clone(),
+        pair_info.liquidity_token,
+    )?;
+    let recorded = holding::record_balance(
+        deps.storage,
+        env.block.time.seconds(),
+        &info.sender,
+        balance,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "record_lp_deposit"),
+        ("sender", info.sender.as_str()),
+        ("recorded_amount", &recorded.to_string()),
+    ]))
+}
+
+/// The allowlist manager or the factory adds and removes addresses
+pub fn update_allowlist(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+    restrict_swaps: Option<bool>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let mut config = ALLOWLIST_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotPermissioned {})?;
+    if config.manager.as_ref() != Some(&info.sender) {
+        assert_factory(deps.as_ref(), &info)?;
+    }
+
+    allowlist::add(deps.storage, deps.api, &add)?;
+    allowlist::remove(deps.storage, deps.api, &remove)?;
+    if let Some(restrict_swaps) = restrict_swaps {
+        config.restrict_swaps = restrict_swaps;
+        ALLOWLIST_CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_allowlist"),
+        ("added", &add.len().to_string()),
+        ("removed", &remove.len().to_string()),
+        ("restrict_swaps", &config.restrict_swaps.to_string()),
+    ]))
+}
+
+/// Swaps, including the swaps of zaps and flash swaps, need an active pair
+fn assert_swaps_enabled(storage: &dyn Storage) -> Result<(), ContractError> {
+    match STATUS.may_load(storage)?.unwrap_or_default() {
+        PairStatus::Active => Ok(()),
+        PairStatus::SwapsPaused => Err(ContractError::SwapsPaused {}),
+        PairStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+fn assert_provide_enabled(storage: &dyn Storage) -> Result<(), ContractError> {
+    match STATUS.may_load(storage)?.unwrap_or_default() {
+        PairStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+        _ => Ok(()),
+    }
+}
+
+/// Trades other than queued swaps would execute ahead of the batch, at the
+/// price it is about to move
+fn assert_no_batch_auction(storage: &dyn Storage) -> Result<(), ContractError> {
+    if BATCH_AUCTION.may_load(storage)?.is_some() {
+        return Err(ContractError::BatchAuctionActive {});
+    }
+
+    Ok(())
+}
+
+/// Whether `sender` is the router set in the factory config
+fn is_router(deps: Deps<InjectiveQueryWrapper>, sender: &Addr) -> StdResult<bool> {
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let router = query_factory_router(&deps.querier, factory_addr)?;
+
+    Ok(router.is_some_and(|router| router == sender.as_str()))
+}
+
+/// Address a swap is made for: the forwarded `trader` when the router sends
+/// it, the sender otherwise
+fn swap_trader(
+    deps: Deps<InjectiveQueryWrapper>,
+    sender: &Addr,
+    trader: Option<&String>,
+) -> StdResult<Addr> {
+    match trader {
+        Some(trader) if is_router(deps, sender)? => deps.api.addr_validate(trader),
+        _ => Ok(sender.clone()),
+    }
+}
 
-    // 1. send collateral token from the contract to a user
-    // 2. send inactive commission to collector
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        ("action", "swap"),
-        ("sender", sender.as_str()),
-        ("receiver", receiver.as_str()),
-        ("offer_asset", &offer_asset.info.to_string()),
-        ("ask_asset", &ask_pool.info.to_string()),
-        ("offer_amount", &offer_amount.to_string()),
-        ("return_amount", &return_amount.to_string()),
-        ("spread_amount", &spread_amount.to_string()),
-        ("commission_amount", &commission_amount.to_string()),
-        ("burn_amount", &burn_amount.to_string()),
-        ("fee_wallet_amount", &fee_wallet_amount.to_string()),
-        ("pool_amount", &lp_amount.to_string()),
-        ("offer_pool_balance", &offer_pool_post.to_string()),
-        ("ask_pool_balance", &ask_pool_post.to_string()),
-    ]))
+fn assert_factory(
+    deps: Deps<InjectiveQueryWrapper>,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    if deps.api.addr_canonicalize(info.sender.as_str())? != FACTORY.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(
     deps: Deps<InjectiveQueryWrapper>,
-    _env: Env,
+    env: Env,
     msg: QueryMsg,
 ) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::Pair {} => Ok(to_json_binary(&query_pair_info(deps)?)?),
         QueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps)?)?),
-        QueryMsg::Simulation { offer_asset } => {
-            Ok(to_json_binary(&query_simulation(deps, offer_asset)?)?)
+        QueryMsg::Simulation {
+            offer_asset,
+            trader,
+        } => Ok(to_json_binary(&query_simulation(
+            deps,
+            env,
+            offer_asset,
+            trader,
+        )?)?),
+        QueryMsg::ReverseSimulation { ask_asset, trader } => Ok(to_json_binary(
+            &query_reverse_simulation(deps, env, ask_asset, trader)?,
+        )?),
+        QueryMsg::Amp {} => Ok(to_json_binary(&query_amp(deps, env)?)?),
+        QueryMsg::Twap {
+            asset,
+            window_seconds,
+        } => Ok(to_json_binary(&query_twap(
+            deps,
+            env,
+            asset,
+            window_seconds,
+        )?)?),
+        QueryMsg::Weights {} => Ok(to_json_binary(&query_weights(deps)?)?),
+        QueryMsg::DynamicFee {} => Ok(to_json_binary(&query_dynamic_fee(deps, env)?)?),
+        QueryMsg::Stats { start_after, limit } => {
+            Ok(to_json_binary(&query_stats(deps, start_after, limit)?)?)
+        }
+        QueryMsg::SpotPrice { asset } => Ok(to_json_binary(&query_spot_price(deps, env, asset)?)?),
+        QueryMsg::Depth {
+            asset,
+            price_impacts,
+        } => Ok(to_json_binary(&query_depth(
+            deps,
+            env,
+            asset,
+            price_impacts,
+        )?)?),
+        QueryMsg::SimulateProvide { assets } => {
+            Ok(to_json_binary(&query_simulate_provide(deps, assets)?)?)
+        }
+        QueryMsg::SimulateWithdraw { amount } => {
+            Ok(to_json_binary(&query_simulate_withdraw(deps, amount)?)?)
         }
-        QueryMsg::ReverseSimulation { ask_asset } => {
-            Ok(to_json_binary(&query_reverse_simulation(deps, ask_asset)?)?)
+        QueryMsg::PendingDeposit { address } => {
+            Ok(to_json_binary(&query_pending_deposit(deps, address)?)?)
         }
+        QueryMsg::SwapHooks {} => Ok(to_json_binary(&query_swap_hooks(deps)?)?),
+        QueryMsg::BatchAuction {} => Ok(to_json_binary(&query_batch_auction(deps)?)?),
+        QueryMsg::LaunchProtection {} => Ok(to_json_binary(&query_launch_protection(deps, env)?)?),
+        QueryMsg::CircuitBreaker {} => Ok(to_json_binary(&query_circuit_breaker(deps, env)?)?),
+        QueryMsg::HoldingPeriod {} => Ok(to_json_binary(&query_holding_period(deps)?)?),
+        QueryMsg::LpDeposit { address } => Ok(to_json_binary(&query_lp_deposit(deps, address)?)?),
+        QueryMsg::Allowlist { start_after, limit } => {
+            Ok(to_json_binary(&query_allowlist(deps, start_after, limit)?)?)
+        }
+        QueryMsg::BatchOrders { start_after, limit } => Ok(to_json_binary(&query_batch_orders(
+            deps,
+            start_after,
+            limit,
+        )?)?),
     }
 }
 
 pub fn query_pair_info(deps: Deps<InjectiveQueryWrapper>) -> Result<PairInfo, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-    let pair_info = pair_info.to_normal(deps.api)?;
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let pair_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
+    let fee_split = query_fee_split(&deps.querier, factory_addr, pair_addr)?;
+    let status = STATUS.may_load(deps.storage)?.unwrap_or_default();
+    let pair_info = pair_info.to_normal(deps.api, fee_split, status)?;
 
     Ok(pair_info)
 }
 
 pub fn query_pool(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let assets: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let assets: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
 
     let total_share: Uint128 =
         query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())
@@ -652,72 +3212,720 @@ pub fn query_pool(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolResponse, Con
 
 pub fn query_simulation(
     deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
     offer_asset: Asset,
+    trader: Option<String>,
 ) -> Result<SimulationResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
-    let offer_decimal: u8;
-    let ask_decimal: u8;
+    let offer_index: usize;
     if offer_asset.info.equal(&pools[0].info) {
         offer_pool = pools[0].clone();
         ask_pool = pools[1].clone();
-        offer_decimal = pair_info.asset_decimals[0];
-        ask_decimal = pair_info.asset_decimals[1];
+        offer_index = 0;
     } else if offer_asset.info.equal(&pools[1].info) {
         offer_pool = pools[1].clone();
         ask_pool = pools[0].clone();
-        offer_decimal = pair_info.asset_decimals[1];
-        ask_decimal = pair_info.asset_decimals[0];
+        offer_index = 1;
     } else {
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_asset.amount, offer_decimal, ask_decimal)?;
+    let commission_bps = dynamic_fee::commission_bps(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        &pair_info,
+        &curve,
+        offer_index,
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        commission_bps,
+    )?;
+    let (return_amount, commission_amount) = match trader {
+        Some(trader) => {
+            let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+            let trader = deps.api.addr_validate(&trader)?;
+            let discount = query_fee_discount(&deps.querier, factory_addr, &trader, None)?;
+            apply_fee_discount(return_amount, commission_amount, discount)
+        }
+        None => (return_amount, commission_amount),
+    };
 
     Ok(SimulationResponse {
         return_amount,
         spread_amount,
         commission_amount,
+        commission_bps,
     })
 }
 
 pub fn query_reverse_simulation(
     deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
     ask_asset: Asset,
+    trader: Option<String>,
 ) -> Result<ReverseSimulationResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
+    let offer_index: usize;
     if ask_asset.info.equal(&pools[0].info) {
         ask_pool = pools[0].clone();
         offer_pool = pools[1].clone();
+        offer_index = 1;
     } else if ask_asset.info.equal(&pools[1].info) {
         ask_pool = pools[1].clone();
         offer_pool = pools[0].clone();
+        offer_index = 0;
     } else {
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (offer_amount, spread_amount, commission_amount) =
-        compute_offer_amount(offer_pool.amount, ask_pool.amount, ask_asset.amount)?;
+    let commission_bps = dynamic_fee::commission_bps(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+    let discount = match trader {
+        Some(trader) => {
+            let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+            let trader = deps.api.addr_validate(&trader)?;
+            query_fee_discount(&deps.querier, factory_addr, &trader, None)?
+        }
+        None => Decimal::zero(),
+    };
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let (offer_amount, spread_amount, commission_amount) = compute_pair_offer_amount(
+        &pair_info,
+        &curve,
+        offer_index,
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        discounted_commission_rate(commission_bps, discount),
+    )?;
 
     Ok(ReverseSimulationResponse {
         offer_amount,
         spread_amount,
         commission_amount,
+        commission_bps,
+    })
+}
+
+pub fn query_amp(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<AmpResponse, ContractError> {
+    let amp_config = AMP
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotStableSwap {})?;
+
+    Ok(AmpResponse {
+        amp: amp_config.current(env.block.time.seconds()),
+        future_amp: amp_config.next_amp,
+        future_time: amp_config.next_amp_time,
+    })
+}
+
+pub fn query_weights(deps: Deps<InjectiveQueryWrapper>) -> Result<WeightsResponse, ContractError> {
+    let weights = WEIGHTS
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotWeighted {})?;
+
+    Ok(WeightsResponse { weights })
+}
+
+pub fn query_dynamic_fee(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<DynamicFeeResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let commission_bps = dynamic_fee::commission_bps(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        pair_info.commission_bps,
+    )?;
+
+    Ok(DynamicFeeResponse {
+        dynamic_fee: DYNAMIC_FEE.may_load(deps.storage)?,
+        commission_bps,
+    })
+}
+
+pub fn query_stats(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<StatsResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    Ok(StatsResponse {
+        asset_infos: [
+            pair_info.asset_infos[0].to_normal(deps.api)?,
+            pair_info.asset_infos[1].to_normal(deps.api)?,
+        ],
+        total: stats::read_total(deps.storage)?,
+        epochs: stats::read_epochs(deps.storage, start_after, limit)?,
+    })
+}
+
+pub fn query_twap(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    asset: AssetInfo,
+    window_seconds: u64,
+) -> Result<TwapResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let asset_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&asset))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    let end_time = env.block.time.seconds();
+    let price = twap::average_price(
+        deps.storage,
+        end_time,
+        [pools[0].amount, pools[1].amount],
+        pair_info.asset_decimals,
+        asset_index,
+        window_seconds,
+    )?;
+
+    Ok(TwapResponse {
+        asset,
+        price,
+        start_time: end_time - window_seconds,
+        end_time,
+    })
+}
+
+pub fn query_spot_price(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    asset: AssetInfo,
+) -> Result<SpotPriceResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&asset))
+        .ok_or(ContractError::AssetMismatch {})?;
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::EmptyPool {});
+    }
+
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let price = compute_pair_spot_price(
+        &pair_info,
+        &curve,
+        offer_index,
+        pools[offer_index].amount,
+        pools[1 - offer_index].amount,
+    )?;
+
+    Ok(SpotPriceResponse { asset, price })
+}
+
+pub fn query_depth(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    asset: AssetInfo,
+    price_impacts: Vec<Decimal>,
+) -> Result<DepthResponse, ContractError> {
+    if price_impacts.len() > MAX_PRICE_IMPACTS {
+        return Err(ContractError::TooManyPriceImpacts {
+            max: MAX_PRICE_IMPACTS as u32,
+        });
+    }
+    if price_impacts
+        .iter()
+        .any(|impact| impact.is_zero() || *impact >= Decimal::one())
+    {
+        return Err(ContractError::InvalidPriceImpact {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&asset))
+        .ok_or(ContractError::AssetMismatch {})?;
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::EmptyPool {});
+    }
+
+    let curve = load_curve(deps.storage, &env, &pair_info.pair_type)?;
+    let levels = price_impacts
+        .into_iter()
+        .map(|price_impact| {
+            let offer_amount = compute_pair_depth(
+                &pair_info,
+                &curve,
+                offer_index,
+                pools[offer_index].amount,
+                pools[1 - offer_index].amount,
+                Decimal256::from(price_impact),
+            )?;
+
+            Ok(DepthLevel {
+                price_impact,
+                offer_amount,
+            })
+        })
+        .collect::<StdResult<Vec<DepthLevel>>>()?;
+
+    Ok(DepthResponse { asset, levels })
+}
+
+pub fn query_simulate_provide(
+    deps: Deps<InjectiveQueryWrapper>,
+    assets: [Asset; 2],
+) -> Result<SimulateProvideResponse, ContractError> {
+    assert_provide_enabled(deps.storage)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+    let deposits = provision_deposits(&pools, &assets)?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    let provision = compute_provision(deps.storage, &pair_info, &pools, deposits, total_share)?;
+
+    let mut slippage = Decimal::zero();
+    for (refund, deposit) in provision.refunds.iter().zip(deposits.iter()) {
+        if !refund.is_zero() {
+            slippage = slippage.max(Decimal::from_ratio(*refund, *deposit));
+        }
+    }
+
+    Ok(SimulateProvideResponse {
+        share: provision.share,
+        refund_assets: [
+            Asset {
+                info: pools[0].info.clone(),
+                amount: provision.refunds[0],
+            },
+            Asset {
+                info: pools[1].info.clone(),
+                amount: provision.refunds[1],
+            },
+        ],
+        slippage,
+    })
+}
+
+pub fn query_simulate_withdraw(
+    deps: Deps<InjectiveQueryWrapper>,
+    amount: Uint128,
+) -> Result<SimulateWithdrawResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    let (refund_assets, _) = compute_withdrawal(&pools, amount, total_share)?;
+
+    Ok(SimulateWithdrawResponse { refund_assets })
+}
+
+pub fn query_pending_deposit(
+    deps: Deps<InjectiveQueryWrapper>,
+    address: String,
+) -> Result<PendingDepositResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let mut assets: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let pending = DEPOSITS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    for (asset, amount) in assets.iter_mut().zip(pending.iter()) {
+        asset.amount = *amount;
+    }
+
+    Ok(PendingDepositResponse { assets })
+}
+
+pub fn query_swap_hooks(
+    deps: Deps<InjectiveQueryWrapper>,
+) -> Result<SwapHooksResponse, ContractError> {
+    let hooks = SWAP_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(SwapHooksResponse {
+        hooks: hooks.into_iter().map(|hook| hook.to_string()).collect(),
+    })
+}
+
+pub fn query_batch_auction(
+    deps: Deps<InjectiveQueryWrapper>,
+) -> Result<BatchAuctionResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let mut offer_assets: [Asset; 2] = load_pools(deps.storage, deps.api, &pair_info)?;
+
+    let batch = BATCH.may_load(deps.storage)?;
+    let offer_totals = batch
+        .as_ref()
+        .map(|batch| batch.offer_totals)
+        .unwrap_or_default();
+    for (asset, amount) in offer_assets.iter_mut().zip(offer_totals.iter()) {
+        asset.amount = *amount;
+    }
+
+    Ok(BatchAuctionResponse {
+        batch_auction: BATCH_AUCTION.may_load(deps.storage)?,
+        settle_height: batch.as_ref().map(|batch| batch.settle_height),
+        order_count: batch.map(|batch| batch.order_count).unwrap_or_default(),
+        offer_assets,
+    })
+}
+
+pub fn query_batch_orders(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<BatchOrdersResponse, ContractError> {
+    Ok(BatchOrdersResponse {
+        orders: batch::read_orders(deps.storage, start_after, limit)?,
+    })
+}
+
+pub fn query_launch_protection(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<LaunchProtectionResponse, ContractError> {
+    let launch_protection = LAUNCH_PROTECTION.may_load(deps.storage)?;
+    let active = launch_protection
+        .as_ref()
+        .map(|launch| launch::is_active(launch, env.block.time.seconds()))
+        .unwrap_or(false);
+
+    Ok(LaunchProtectionResponse {
+        launch_protection,
+        active,
+    })
+}
+
+pub fn query_circuit_breaker(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<CircuitBreakerResponse, ContractError> {
+    let circuit_breaker = CIRCUIT_BREAKER.may_load(deps.storage)?;
+    let window = match &circuit_breaker {
+        Some(config) => {
+            circuit_breaker::current_window(deps.storage, config, env.block.time.seconds())?
+        }
+        None => None,
+    };
+
+    match (circuit_breaker, window) {
+        (Some(config), Some(window)) => {
+            let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+            let price_drops = circuit_breaker::price_drops(
+                deps.storage,
+                env.block.time.seconds(),
+                &window,
+                RESERVES.load(deps.storage)?,
+                pair_info.asset_decimals,
+            )?;
+
+            Ok(CircuitBreakerResponse {
+                window_end: Some(window.start + config.window),
+                circuit_breaker: Some(config),
+                price_drops,
+                outflows: circuit_breaker::net_outflows(&window),
+            })
+        }
+        (circuit_breaker, _) => Ok(CircuitBreakerResponse {
+            circuit_breaker,
+            window_end: None,
+            price_drops: [Decimal::zero(); 2],
+            outflows: [Uint128::zero(); 2],
+        }),
+    }
+}
+
+pub fn query_holding_period(
+    deps: Deps<InjectiveQueryWrapper>,
+) -> Result<HoldingPeriodResponse, ContractError> {
+    Ok(HoldingPeriodResponse {
+        holding_period: HOLDING_PERIOD.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_lp_deposit(
+    deps: Deps<InjectiveQueryWrapper>,
+    address: String,
+) -> Result<LpDepositResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let deposit = holding::load_deposit(deps.storage, &address)?;
+
+    let matures_at = match (HOLDING_PERIOD.may_load(deps.storage)?, &deposit) {
+        (Some(holding_period), Some(deposit)) => {
+            Some(holding::matures_at(&holding_period, deposit))
+        }
+        _ => None,
+    };
+
+    Ok(LpDepositResponse {
+        amount: deposit.map(|deposit| deposit.amount).unwrap_or_default(),
+        matures_at,
+    })
+}
+
+pub fn query_allowlist(
+    deps: Deps<InjectiveQueryWrapper>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<AllowlistResponse, ContractError> {
+    let config = ALLOWLIST_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotPermissioned {})?;
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_validate(&start_after)?),
+        None => None,
+    };
+
+    Ok(AllowlistResponse {
+        manager: config.manager.map(|manager| manager.to_string()),
+        restrict_swaps: config.restrict_swaps,
+        addresses: allowlist::read_addresses(deps.storage, start_after, limit)?,
+    })
+}
+
+/// Parameters of the pair's curve at the current block
+enum Curve {
+    ConstantProduct,
+    StableSwap { amp: u64 },
+    Weighted { weights: [Decimal; 2] },
+}
+
+/// Pair assets at the tracked reserves
+fn load_pools(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    pair_info: &PairInfoRaw,
+) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: pair_info.asset_infos[0].to_normal(api)?,
+            amount: reserves[0],
+        },
+        Asset {
+            info: pair_info.asset_infos[1].to_normal(api)?,
+            amount: reserves[1],
+        },
+    ])
+}
+
+fn load_curve(storage: &dyn Storage, env: &Env, pair_type: &PairType) -> StdResult<Curve> {
+    Ok(match pair_type {
+        PairType::ConstantProduct => Curve::ConstantProduct,
+        PairType::StableSwap => Curve::StableSwap {
+            amp: AMP.load(storage)?.current(env.block.time.seconds()),
+        },
+        PairType::Weighted => Curve::Weighted {
+            weights: WEIGHTS.load(storage)?,
+        },
     })
 }
 
+/// Swap math of the pair's curve, returns (return_amount, spread_amount, commission_amount)
+fn compute_pair_swap(
+    pair_info: &PairInfoRaw,
+    curve: &Curve,
+    offer_index: usize,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    commission_bps: u16,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    let ask_index = 1 - offer_index;
+    let offer_decimal = pair_info.asset_decimals[offer_index];
+    let ask_decimal = pair_info.asset_decimals[ask_index];
+    let commission_rate = commission_rate(commission_bps);
+    match curve {
+        Curve::ConstantProduct => Ok(compute_swap(
+            offer_pool,
+            ask_pool,
+            offer_amount,
+            offer_decimal,
+            ask_decimal,
+            commission_rate,
+        )?),
+        Curve::StableSwap { amp } => Ok(stableswap::compute_swap(
+            *amp,
+            offer_pool,
+            ask_pool,
+            offer_amount,
+            offer_decimal,
+            ask_decimal,
+            commission_rate,
+        )?),
+        Curve::Weighted { weights } => Ok(weighted::compute_swap(
+            offer_pool,
+            ask_pool,
+            offer_amount,
+            offer_decimal,
+            ask_decimal,
+            weights[offer_index],
+            weights[ask_index],
+            commission_rate,
+        )?),
+    }
+}
+
+/// Reverse swap math of the pair's curve, returns (offer_amount, spread_amount, commission_amount)
+fn compute_pair_offer_amount(
+    pair_info: &PairInfoRaw,
+    curve: &Curve,
+    offer_index: usize,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    commission_rate: Decimal256,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    let ask_index = 1 - offer_index;
+    let offer_decimal = pair_info.asset_decimals[offer_index];
+    let ask_decimal = pair_info.asset_decimals[ask_index];
+    match curve {
+        Curve::ConstantProduct => Ok(compute_offer_amount(
+            offer_pool,
+            ask_pool,
+            ask_amount,
+            offer_decimal,
+            ask_decimal,
+            commission_rate,
+        )?),
+        Curve::StableSwap { amp } => Ok(stableswap::compute_offer_amount(
+            *amp,
+            offer_pool,
+            ask_pool,
+            ask_amount,
+            offer_decimal,
+            ask_decimal,
+            commission_rate,
+        )?),
+        Curve::Weighted { weights } => Ok(weighted::compute_offer_amount(
+            offer_pool,
+            ask_pool,
+            ask_amount,
+            offer_decimal,
+            ask_decimal,
+            weights[offer_index],
+            weights[ask_index],
+            commission_rate,
+        )?),
+    }
+}
+
+/// Spot price of the offer asset on the pair's curve
+fn compute_pair_spot_price(
+    pair_info: &PairInfoRaw,
+    curve: &Curve,
+    offer_index: usize,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+) -> StdResult<Decimal256> {
+    let ask_index = 1 - offer_index;
+    let offer_decimal = pair_info.asset_decimals[offer_index];
+    let ask_decimal = pair_info.asset_decimals[ask_index];
+    match curve {
+        Curve::ConstantProduct => Ok(compute_spot_price(
+            offer_pool,
+            ask_pool,
+            offer_decimal,
+            ask_decimal,
+        )),
+        Curve::StableSwap { amp } => {
+            stableswap::spot_price(*amp, offer_pool, ask_pool, offer_decimal, ask_decimal)
+        }
+        Curve::Weighted { weights } => Ok(weighted::spot_price(
+            offer_pool,
+            ask_pool,
+            offer_decimal,
+            ask_decimal,
+            weights[offer_index],
+            weights[ask_index],
+        )),
+    }
+}
+
+/// Offer amount that lowers the offer asset's spot price on the pair's curve by `price_impact`
+fn compute_pair_depth(
+    pair_info: &PairInfoRaw,
+    curve: &Curve,
+    offer_index: usize,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    price_impact: Decimal256,
+) -> StdResult<Uint128> {
+    let ask_index = 1 - offer_index;
+    match curve {
+        Curve::ConstantProduct => compute_depth(offer_pool, price_impact),
+        Curve::StableSwap { amp } => stableswap::compute_depth(
+            *amp,
+            offer_pool,
+            ask_pool,
+            pair_info.asset_decimals[offer_index],
+            pair_info.asset_decimals[ask_index],
+            price_impact,
+        ),
+        Curve::Weighted { weights } => weighted::compute_depth(
+            offer_pool,
+            weights[offer_index],
+            weights[ask_index],
+            price_impact,
+        ),
+    }
+}
+
+/// Returns the return and commission amounts after refunding `discount` of the
+/// commission to the trader
+pub fn apply_fee_discount(
+    return_amount: Uint128,
+    commission_amount: Uint128,
+    discount: Decimal,
+) -> (Uint128, Uint128) {
+    let rebate = commission_amount.mul_floor(discount);
+    (return_amount + rebate, commission_amount - rebate)
+}
+
+/// Converts a fee tier in basis points into the commission rate used by the swap math
+pub fn commission_rate(commission_bps: u16) -> Decimal256 {
+    Decimal256::bps(commission_bps.into())
+}
+
+/// Commission rate of a fee tier once the trader's fee discount is taken off
+pub fn discounted_commission_rate(commission_bps: u16, discount: Decimal) -> Decimal256 {
+    commission_rate(commission_bps) * (Decimal256::one() - Decimal256::from(discount))
+}
 
 pub fn compute_swap(
     offer_pool: Uint128,
@@ -725,6 +3933,7 @@ pub fn compute_swap(
     offer_amount: Uint128,
     offer_dec: u8,
     ask_dec: u8,
+    commission_rate: Decimal256,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let target_dec = offer_dec.max(ask_dec);
     let pow10 = |d: u8| Uint256::from(10u128.pow(d as u32));
@@ -740,6 +3949,7 @@ pub fn compute_swap(
         offer_pool_u.try_into()?,   
         ask_pool_u.try_into()?,     
         offer_amount_u.try_into()?, 
+        commission_rate,
     )?;
 
     // 3. down-scale helper
@@ -759,13 +3969,12 @@ fn compute_swap_raw(
     offer_pool: Uint128,
     ask_pool: Uint128,
     offer_amount: Uint128,
+    commission_rate: Decimal256,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let offer_amount: Uint256 = offer_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
-
     // offer => ask
     // ask_amount = (ask_pool - cp / (offer_pool + offer_amount)) * (1 - commission_rate)
     let return_amount: Uint256 = (ask_pool * offer_amount) / (offer_pool + offer_amount);
@@ -786,33 +3995,70 @@ fn compute_swap_raw(
     ))
 }
 
+/// Reverse of `compute_swap` on the same upscaled reserves. The offer amount is
+/// rounded up to offer decimals, spread and commission down to ask decimals.
+pub fn compute_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = stableswap::upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = stableswap::upscale(ask_pool, ask_dec, target_dec);
+    let ask_amount = stableswap::upscale(ask_amount, ask_dec, target_dec);
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount_raw(
+        offer_pool.try_into()?,
+        ask_pool.try_into()?,
+        ask_amount.try_into()?,
+        commission_rate,
+    )?;
+
+    Ok((
+        stableswap::downscale(offer_amount.into(), offer_dec, target_dec, true)?,
+        stableswap::downscale(spread_amount.into(), ask_dec, target_dec, false)?,
+        stableswap::downscale(commission_amount.into(), ask_dec, target_dec, false)?,
+    ))
+}
 
-fn compute_offer_amount(
+fn compute_offer_amount_raw(
     offer_pool: Uint128,
     ask_pool: Uint128,
     ask_amount: Uint128,
+    commission_rate: Decimal256,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let ask_amount: Uint256 = ask_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
-
     // ask => offer
     // offer_amount = cp / (ask_pool - ask_amount / (1 - commission_rate)) - offer_pool
     let cp: Uint256 = offer_pool * ask_pool;
 
-    let one_minus_commission = Decimal256::one() - commission_rate;
-    let inv_one_minus_commission = Decimal256::one() / one_minus_commission;
-    let mut before_commission_deduction: Uint256 = ask_amount.mul_floor(inv_one_minus_commission);
-    if before_commission_deduction.mul_floor(one_minus_commission) != ask_amount {
-        before_commission_deduction += Uint256::from(1u8);
-    }
+    // (ask_amount + 1) / (1 - commission_rate), rounded up. The extra unit
+    // covers `compute_swap_raw` charging one unit over the exact commission.
+    let one = Decimal256::one().atomics();
+    let one_minus_commission = (Decimal256::one() - commission_rate).atomics();
+    let ask_amount_with_margin = if commission_rate.is_zero() {
+        ask_amount
+    } else {
+        ask_amount.checked_add(Uint256::one())?
+    };
+    let before_commission_deduction = ask_amount_with_margin
+        .checked_mul(one)?
+        .checked_add(one_minus_commission - Uint256::one())?
+        .checked_div(one_minus_commission)?;
 
+    if before_commission_deduction >= ask_pool {
+        return Err(StdError::generic_err("ask amount exceeds the pool"));
+    }
     let after_ask_pool = ask_pool - before_commission_deduction;
     let mut after_offer_pool = Uint256::from(1u8).multiply_ratio(cp, after_ask_pool);
 
-    if after_offer_pool * (ask_pool - before_commission_deduction) != cp {
+    if after_offer_pool * after_ask_pool != cp {
         after_offer_pool += Uint256::from(1u8);
     }
 
@@ -835,6 +4081,32 @@ fn compute_offer_amount(
     ))
 }
 
+/// Spot price of the offer asset quoted in the ask asset, on the reserves scaled
+/// to the larger of the two asset decimals like `compute_swap`
+pub fn compute_spot_price(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+) -> Decimal256 {
+    let target_dec = offer_dec.max(ask_dec);
+    Decimal256::from_ratio(
+        stableswap::upscale(ask_pool, ask_dec, target_dec),
+        stableswap::upscale(offer_pool, offer_dec, target_dec),
+    )
+}
+
+/// Offer amount, before commission, that lowers the spot price of the offer
+/// asset by `price_impact`. The price falls with the square of
+/// `offer_pool / new_offer_pool`, so it is `offer_pool * (1 / sqrt(1 - price_impact) - 1)`.
+pub fn compute_depth(offer_pool: Uint128, price_impact: Decimal256) -> StdResult<Uint128> {
+    let growth = Decimal256::one() / (Decimal256::one() - price_impact).sqrt();
+
+    Ok(Uint256::from(offer_pool)
+        .mul_ceil(growth.checked_sub(Decimal256::one())?)
+        .try_into()?)
+}
+
 /// If `belief_price` and `max_spread` both are given,
 /// we compute new spread else we just use choice
 /// spread to check `max_spread`
@@ -956,9 +4228,25 @@ const TARGET_CONTRACT_VERSION: &str = "1.1.2";
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<InjectiveQueryWrapper>,
-    _env: Env,
-    _msg: MigrateMsg,
+    env: Env,
+    msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
+    // pairs from before the factory address was stored get it from the factory
+    if FACTORY.may_load(deps.storage)?.is_none() {
+        let factory = msg.factory.ok_or_else(|| {
+            StdError::generic_err("the factory address is required to migrate this pair")
+        })?;
+        FACTORY.save(deps.storage, &deps.api.addr_canonicalize(&factory)?)?;
+    }
+
+    // pairs from before reserve accounting adopt their balances
+    if RESERVES.may_load(deps.storage)?.is_none() {
+        let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+        let balances: [Asset; 2] =
+            pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
+        RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+    }
+
     migrate_version(
         deps,
         TARGET_CONTRACT_VERSION,
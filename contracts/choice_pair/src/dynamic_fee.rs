@@ -0,0 +1,111 @@
+// Volatility driven commission. Every swap compares the decimal adjusted spot
+// price of the curve at the reserves it trades against with the price seen by
+// the previous swap.
+// Relative moves add up in an accumulator that shrinks linearly with the time
+// between swaps, and the commission grows with it from the floor to the ceiling.
+
+use crate::state::{Volatility, DYNAMIC_FEE, VOLATILITY};
+use crate::twap;
+use choice::pair::DynamicFee;
+use cosmwasm_std::{Decimal256, StdResult, Storage, Uint128, Uint256};
+use std::convert::TryInto;
+
+/// Commission in bps of a swap at `now` against `pools`, `commission_bps` when
+/// the pair has no dynamic fee
+pub fn commission_bps(
+    storage: &dyn Storage,
+    now: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+    commission_bps: u16,
+) -> StdResult<u16> {
+    match DYNAMIC_FEE.may_load(storage)? {
+        Some(config) => {
+            let (price, _) = twap::spot_prices(storage, now, pools, decimals)?;
+            let volatility = observe(&config, VOLATILITY.may_load(storage)?, now, price)?;
+            fee_bps(&config, &volatility)
+        }
+        None => Ok(commission_bps),
+    }
+}
+
+/// Same as `commission_bps`, and records the price for the next swap. `pools`
+/// must be the reserves before the swap changes them.
+pub fn record_swap(
+    storage: &mut dyn Storage,
+    now: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+    commission_bps: u16,
+) -> StdResult<u16> {
+    let config = match DYNAMIC_FEE.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok(commission_bps),
+    };
+
+    let (price, _) = twap::spot_prices(storage, now, pools, decimals)?;
+    let volatility = observe(&config, VOLATILITY.may_load(storage)?, now, price)?;
+    VOLATILITY.save(storage, &volatility)?;
+
+    fee_bps(&config, &volatility)
+}
+
+/// Volatility after a swap at `now`, the accumulator is capped where the fee
+/// reaches its ceiling so a spike is forgotten within one decay period
+fn observe(
+    config: &DynamicFee,
+    previous: Option<Volatility>,
+    now: u64,
+    price: Decimal256,
+) -> StdResult<Volatility> {
+    let previous = match previous {
+        Some(previous) if !previous.price.is_zero() && !price.is_zero() => previous,
+        _ => {
+            return Ok(Volatility {
+                price,
+                accumulator: Decimal256::zero(),
+                timestamp: now,
+            })
+        }
+    };
+
+    let elapsed = now
+        .saturating_sub(previous.timestamp)
+        .min(config.decay_period);
+    let remaining = previous.accumulator.checked_mul(Decimal256::from_ratio(
+        config.decay_period - elapsed,
+        config.decay_period,
+    ))?;
+
+    let price_move_bps = Decimal256::from_ratio(
+        price
+            .abs_diff(previous.price)
+            .atomics()
+            .checked_mul(Uint256::from(10_000u128))?,
+        previous.price.atomics(),
+    );
+    let mut accumulator = remaining.checked_add(price_move_bps)?;
+
+    let multiplier = Decimal256::from(config.volatility_multiplier);
+    if !multiplier.is_zero() {
+        let ceiling = Decimal256::from_ratio(config.max_fee_bps - config.min_fee_bps, 1u8);
+        accumulator = accumulator.min(ceiling / multiplier);
+    }
+
+    Ok(Volatility {
+        price,
+        accumulator,
+        timestamp: now,
+    })
+}
+
+fn fee_bps(config: &DynamicFee, volatility: &Volatility) -> StdResult<u16> {
+    let extra = Decimal256::from(config.volatility_multiplier)
+        .checked_mul(volatility.accumulator)?
+        .to_uint_floor()
+        .min(Uint256::from(config.max_fee_bps - config.min_fee_bps));
+    let extra: Uint128 = extra.try_into()?;
+
+    // bounded by the fee range above
+    Ok(config.min_fee_bps + extra.u128() as u16)
+}
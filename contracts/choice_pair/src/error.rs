@@ -30,6 +30,9 @@ pub enum ContractError {
     #[error("Expired deadline")]
     ExpiredDeadline {},
 
+    #[error("Max offer assertion ({offer} > {max_offer})")]
+    MaxOfferAssertion { max_offer: String, offer: String },
+
     #[error("Max slippage assertion")]
     MaxSlippageAssertion {},
 
@@ -43,5 +46,125 @@ pub enum ContractError {
     },
 
     #[error("LP supply overflow")]
-    LpSupplyOverflow {}
+    LpSupplyOverflow {},
+
+    #[error("Pair is not a stable swap pair")]
+    NotStableSwap {},
+
+    #[error("Amp must be between 1 and {max_amp}")]
+    InvalidAmp { max_amp: u64 },
+
+    #[error("Amp can change by at most a factor of {max_change} per ramp")]
+    MaxAmpChangeAssertion { max_change: u64 },
+
+    #[error("Amp ramp must last at least {min_ramp_time} seconds")]
+    MinAmpRampTimeAssertion { min_ramp_time: u64 },
+
+    #[error("Pair is not a weighted pair")]
+    NotWeighted {},
+
+    #[error("TWAP window must be greater than zero")]
+    InvalidTwapWindow {},
+
+    #[error("TWAP window exceeds the recorded price history ({max_window} seconds)")]
+    TwapWindowTooLong { max_window: u64 },
+
+    #[error("A flash swap is in progress")]
+    FlashSwapInProgress {},
+
+    #[error("Flash swap amount must be lower than the reserve")]
+    FlashSwapExceedsReserve {},
+
+    #[error("Flash swap was not repaid")]
+    FlashSwapNotRepaid {},
+
+    #[error("Zap in requires a pool with liquidity")]
+    ZapInEmptyPool {},
+
+    #[error("Donations require a pool with liquidity")]
+    DonateEmptyPool {},
+
+    #[error("Min LP assertion ({min_lp} > {share})")]
+    MinLpAssertion { min_lp: String, share: String },
+
+    #[error("Swaps are paused on this pair")]
+    SwapsPaused {},
+
+    #[error("Pair only accepts withdrawals")]
+    WithdrawOnly {},
+
+    #[error("Dynamic fee requires 0 < min_fee_bps <= max_fee_bps <= {max_fee_bps}")]
+    InvalidDynamicFee { max_fee_bps: u16 },
+
+    #[error("Dynamic fee decay period must be greater than zero")]
+    InvalidDecayPeriod {},
+
+    #[error("Pool has no liquidity")]
+    EmptyPool {},
+
+    #[error("Price impacts must be greater than 0 and lower than 1")]
+    InvalidPriceImpact {},
+
+    #[error("At most {max} price impacts can be queried at once")]
+    TooManyPriceImpacts { max: u32 },
+
+    #[error("No pending deposit")]
+    NoPendingDeposit {},
+
+    #[error("A pair can have at most {max} swap hooks")]
+    TooManySwapHooks { max: u32 },
+
+    #[error("Batch auctions require a constant product pair")]
+    BatchAuctionUnsupported {},
+
+    #[error("Batch auctions need an interval of at least one block and minimum offers above zero")]
+    InvalidBatchAuction {},
+
+    #[error("Only swaps can trade against a pair in batch auction mode")]
+    BatchAuctionActive {},
+
+    #[error("A batch can queue at most {max} swaps")]
+    BatchFull { max: u32 },
+
+    #[error("An address can queue at most {max} swaps per batch")]
+    BatchSenderFull { max: u32 },
+
+    #[error("Batch orders must offer at least {min_offer}")]
+    BatchOrderTooSmall { min_offer: String },
+
+    #[error("Swaps through the router cannot be queued, swap with the pair directly")]
+    BatchAuctionRouted {},
+
+    #[error("No swaps are queued")]
+    NoBatch {},
+
+    #[error("Batch can be settled from block {settle_height}")]
+    BatchNotClosed { settle_height: u64 },
+
+    #[error("Trading starts at {trading_start}")]
+    TradingNotStarted { trading_start: u64 },
+
+    #[error("Swap exceeds the launch limit ({offer} > {max_offer})")]
+    LaunchSwapTooLarge { max_offer: String, offer: String },
+
+    #[error("Sender can swap again at {next_swap}")]
+    SwapCooldown { next_swap: u64 },
+
+    #[error("Circuit breaker window must be greater than zero and its thresholds greater than 0 and at most 1")]
+    InvalidCircuitBreaker {},
+
+    #[error("Trade moves the price or reserves past the circuit breaker limits of the window ending at {window_end}")]
+    CircuitBreakerExceeded { window_end: u64 },
+
+    #[error("Holding period must be greater than zero and its penalty between 0 and 1")]
+    InvalidHoldingPeriod {},
+
+    #[error("LP can be withdrawn from {matures_at}")]
+    HoldingPeriodNotOver { matures_at: u64 },
+
+    #[error("Pair has no allowlist")]
+    NotPermissioned {},
+
+    #[error("{address} is not on the allowlist")]
+    NotAllowlisted { address: String },
 }
@@ -0,0 +1,175 @@
+// Minimum LP holding period. While a pair has one, every LP mint moves the
+// deposit time of its receiver to the average weighted by amount. LP withdrawn
+// before its deposit is `seconds` old is rejected or pays a penalty that stays
+// in the reserves.
+//
+// LP is a bank denom and moves between addresses without the pair seeing it,
+// so LP withdrawn beyond the sender's recorded deposit is taken as deposited
+// when it is withdrawn. Its holder records it with `record_lp_deposit` to let
+// it mature.
+//
+// Removing the holding period starts a new epoch. Deposits of earlier epochs
+// are ignored and overwritten when their address records a new one.
+
+use crate::error::ContractError;
+use crate::state::{LpDeposit, HOLDING_PERIOD, HOLDING_PERIOD_EPOCH, LP_DEPOSITS};
+use choice::pair::HoldingPeriod;
+use cosmwasm_std::{Addr, Decimal, StdResult, Storage, Uint128};
+
+pub fn validate(holding_period: &HoldingPeriod) -> Result<(), ContractError> {
+    let invalid_penalty = holding_period
+        .early_withdrawal_penalty
+        .map(|penalty| penalty.is_zero() || penalty > Decimal::one())
+        .unwrap_or(false);
+    if holding_period.seconds == 0 || invalid_penalty {
+        return Err(ContractError::InvalidHoldingPeriod {});
+    }
+
+    Ok(())
+}
+
+/// Records `amount` of LP minted to `receiver` at `now`
+pub fn record_mint(
+    storage: &mut dyn Storage,
+    now: u64,
+    receiver: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    if HOLDING_PERIOD.may_load(storage)?.is_none() {
+        return Ok(());
+    }
+
+    let deposit = add(storage, load_deposit(storage, receiver)?, now, amount)?;
+    LP_DEPOSITS.save(storage, receiver, &deposit)?;
+
+    Ok(())
+}
+
+/// Records the part of the LP `balance` of `sender` beyond its recorded
+/// deposit as deposited at `now`, and returns that amount
+pub fn record_balance(
+    storage: &mut dyn Storage,
+    now: u64,
+    sender: &Addr,
+    balance: Uint128,
+) -> StdResult<Uint128> {
+    if HOLDING_PERIOD.may_load(storage)?.is_none() {
+        return Ok(Uint128::zero());
+    }
+
+    let deposit = load_deposit(storage, sender)?;
+    let recorded = deposit
+        .as_ref()
+        .map(|deposit| deposit.amount)
+        .unwrap_or_default();
+    let unrecorded = balance.saturating_sub(recorded);
+    if !unrecorded.is_zero() {
+        let deposit = add(storage, deposit, now, unrecorded)?;
+        LP_DEPOSITS.save(storage, sender, &deposit)?;
+    }
+
+    Ok(unrecorded)
+}
+
+/// Records `amount` of LP burnt by `sender` at `now`, and returns the share of
+/// the refund withheld as early withdrawal penalty
+pub fn record_burn(
+    storage: &mut dyn Storage,
+    now: u64,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<Decimal, ContractError> {
+    let holding_period = match HOLDING_PERIOD.may_load(storage)? {
+        Some(holding_period) => holding_period,
+        None => return Ok(Decimal::zero()),
+    };
+    let deposit = load_deposit(storage, sender)?;
+
+    // the recorded deposit is withdrawn first, the rest is as young as it gets
+    let recorded = deposit
+        .as_ref()
+        .map(|deposit| deposit.amount.min(amount))
+        .unwrap_or_default();
+    let mut early = Uint128::zero();
+    let mut matures_at = 0;
+    let withdrawals = [
+        (
+            recorded,
+            deposit.as_ref().map(|deposit| deposit.time).unwrap_or(0),
+        ),
+        (amount - recorded, now),
+    ];
+    for (withdrawn, time) in withdrawals.iter() {
+        let deposit_matures_at = time.saturating_add(holding_period.seconds);
+        if !withdrawn.is_zero() && now < deposit_matures_at {
+            early += *withdrawn;
+            matures_at = matures_at.max(deposit_matures_at);
+        }
+    }
+
+    let penalty = match holding_period.early_withdrawal_penalty {
+        _ if early.is_zero() => Decimal::zero(),
+        Some(penalty) => Decimal::from_ratio(early, amount) * penalty,
+        None => return Err(ContractError::HoldingPeriodNotOver { matures_at }),
+    };
+
+    if let Some(mut deposit) = deposit {
+        deposit.amount -= recorded;
+        if deposit.amount.is_zero() {
+            LP_DEPOSITS.remove(storage, sender);
+        } else {
+            LP_DEPOSITS.save(storage, sender, &deposit)?;
+        }
+    }
+
+    Ok(penalty)
+}
+
+/// Drops the deposits recorded so far by starting a new epoch
+pub fn reset(storage: &mut dyn Storage) -> StdResult<()> {
+    let epoch = HOLDING_PERIOD_EPOCH.may_load(storage)?.unwrap_or_default();
+    HOLDING_PERIOD_EPOCH.save(storage, &(epoch + 1))
+}
+
+/// Deposit of `address` recorded in the current epoch
+pub fn load_deposit(storage: &dyn Storage, address: &Addr) -> StdResult<Option<LpDeposit>> {
+    let epoch = HOLDING_PERIOD_EPOCH.may_load(storage)?.unwrap_or_default();
+
+    Ok(LP_DEPOSITS
+        .may_load(storage, address)?
+        .filter(|deposit| deposit.epoch == epoch))
+}
+
+pub fn matures_at(holding_period: &HoldingPeriod, deposit: &LpDeposit) -> u64 {
+    deposit.time.saturating_add(holding_period.seconds)
+}
+
+/// `deposit` after adding `amount` at `now`
+fn add(
+    storage: &dyn Storage,
+    deposit: Option<LpDeposit>,
+    now: u64,
+    amount: Uint128,
+) -> StdResult<LpDeposit> {
+    let epoch = HOLDING_PERIOD_EPOCH.may_load(storage)?.unwrap_or_default();
+    let deposit = match deposit {
+        Some(deposit) if !deposit.amount.is_zero() => deposit,
+        _ => {
+            return Ok(LpDeposit {
+                amount,
+                time: now,
+                epoch,
+            })
+        }
+    };
+
+    let total = deposit.amount.checked_add(amount)?;
+    let shift = Uint128::from(now.saturating_sub(deposit.time)).multiply_ratio(amount, total);
+
+    Ok(LpDeposit {
+        amount: total,
+        // at most `now`
+        time: deposit.time + shift.u128() as u64,
+        epoch,
+    })
+}
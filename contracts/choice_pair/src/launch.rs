@@ -0,0 +1,84 @@
+// Launch protection. A pair created with launch parameters rejects swaps
+// before its trading start. For `duration` seconds after it, each swap can
+// offer at most a fraction of the offer reserve and an address has to wait
+// `cooldown` seconds between swaps, after which the pair trades freely.
+
+use crate::error::ContractError;
+use crate::state::{LAST_SWAPS, LAUNCH_PROTECTION};
+use choice::pair::LaunchProtection;
+use cosmwasm_std::{Addr, Decimal, StdError, StdResult, Storage, Uint128};
+
+/// Furthest a trading start may be after the creation of the pair, as pairs
+/// are unique per asset pair and fee tier
+pub const MAX_LAUNCH_DELAY: u64 = 7 * 86_400;
+
+/// Longest the swap size limit and cooldown may apply
+pub const MAX_LAUNCH_DURATION: u64 = 86_400;
+
+pub fn validate(launch: &LaunchProtection, now: u64) -> StdResult<()> {
+    if launch.trading_start > now.saturating_add(MAX_LAUNCH_DELAY) {
+        return Err(StdError::generic_err(format!(
+            "trading_start can be at most {} seconds away",
+            MAX_LAUNCH_DELAY
+        )));
+    }
+    if launch.duration > MAX_LAUNCH_DURATION {
+        return Err(StdError::generic_err(format!(
+            "launch protection can last at most {} seconds",
+            MAX_LAUNCH_DURATION
+        )));
+    }
+    if launch.max_swap_ratio.is_zero() || launch.max_swap_ratio > Decimal::one() {
+        return Err(StdError::generic_err(
+            "max_swap_ratio must be greater than 0 and at most 1",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn is_active(launch: &LaunchProtection, now: u64) -> bool {
+    now < launch.trading_start.saturating_add(launch.duration)
+}
+
+/// Checks a swap by `sender` offering `offer_amount` against `offer_pool`, and
+/// starts the sender's cooldown while the protection applies
+pub fn assert_swap(
+    storage: &mut dyn Storage,
+    now: u64,
+    sender: &Addr,
+    offer_amount: Uint128,
+    offer_pool: Uint128,
+) -> Result<(), ContractError> {
+    let launch = match LAUNCH_PROTECTION.may_load(storage)? {
+        Some(launch) => launch,
+        None => return Ok(()),
+    };
+
+    if now < launch.trading_start {
+        return Err(ContractError::TradingNotStarted {
+            trading_start: launch.trading_start,
+        });
+    }
+    if !is_active(&launch, now) {
+        return Ok(());
+    }
+
+    let max_offer = offer_pool.mul_floor(launch.max_swap_ratio);
+    if offer_amount > max_offer {
+        return Err(ContractError::LaunchSwapTooLarge {
+            max_offer: max_offer.to_string(),
+            offer: offer_amount.to_string(),
+        });
+    }
+
+    if let Some(last_swap) = LAST_SWAPS.may_load(storage, sender)? {
+        let next_swap = last_swap.saturating_add(launch.cooldown);
+        if now < next_swap {
+            return Err(ContractError::SwapCooldown { next_swap });
+        }
+    }
+    LAST_SWAPS.save(storage, sender, &now)?;
+
+    Ok(())
+}
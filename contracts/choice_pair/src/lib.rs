@@ -1,5 +1,15 @@
+pub mod allowlist;
+pub mod batch;
+pub mod circuit_breaker;
 pub mod contract;
+pub mod dynamic_fee;
+pub mod holding;
+pub mod launch;
+pub mod stableswap;
 pub mod state;
+pub mod stats;
+pub mod twap;
+pub mod weighted;
 
 mod error;
 mod response;
@@ -0,0 +1,270 @@
+// StableSwap (Curve) invariant for two assets. Balances are scaled to the larger
+// of the two asset decimals before solving, like `contract::compute_swap`.
+
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256};
+use std::convert::TryInto;
+
+/// Highest amplification coefficient a pair may use
+pub const MAX_AMP: u64 = 1_000_000;
+/// A single ramp may multiply or divide the coefficient by at most this factor
+pub const MAX_AMP_CHANGE: u64 = 10;
+/// Shortest duration of an amplification ramp, in seconds
+pub const MIN_AMP_RAMP_TIME: u64 = 86_400;
+
+const N_COINS: u8 = 2;
+const ITERATIONS: u8 = 64;
+
+/// Returns (return_amount, spread_amount, commission_amount) in ask decimals
+pub fn compute_swap(
+    amp: u64,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+    let offer_amount = upscale(offer_amount, offer_dec, target_dec);
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    let new_ask_pool = compute_y(amp, offer_pool.checked_add(offer_amount)?, d)?;
+
+    // keep one unit in the pool to cover rounding in the newton iterations
+    let return_amount = ask_pool
+        .saturating_sub(new_ask_pool)
+        .saturating_sub(Uint256::one());
+
+    // the invariant prices both assets 1:1 when balanced
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+    let commission_amount = return_amount.mul_ceil(commission_rate);
+    let return_amount = return_amount - commission_amount;
+
+    Ok((
+        downscale(return_amount, ask_dec, target_dec, false)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Returns (offer_amount, spread_amount, commission_amount), the offer amount
+/// in offer decimals and the others in ask decimals
+pub fn compute_offer_amount(
+    amp: u64,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+    let ask_amount = upscale(ask_amount, ask_dec, target_dec);
+
+    // ask_amount / (1 - commission_rate), rounded up
+    let one = Decimal256::one().atomics();
+    let one_minus_commission = (Decimal256::one() - commission_rate).atomics();
+    let before_commission_deduction = ask_amount
+        .checked_mul(one)?
+        .checked_add(one_minus_commission - Uint256::one())?
+        .checked_div(one_minus_commission)?;
+
+    if before_commission_deduction >= ask_pool {
+        return Err(StdError::generic_err("ask amount exceeds the pool"));
+    }
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    let new_offer_pool = compute_y(amp, ask_pool - before_commission_deduction, d)?;
+
+    let offer_amount = new_offer_pool
+        .saturating_sub(offer_pool)
+        .checked_add(Uint256::one())?;
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+    let commission_amount = before_commission_deduction - ask_amount;
+
+    Ok((
+        downscale(offer_amount, offer_dec, target_dec, true)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Invariant `D` of the pools, scaled to the larger of the two asset decimals
+pub fn compute_invariant(amp: u64, pools: [Uint128; 2], decimals: [u8; 2]) -> StdResult<Uint256> {
+    let target_dec = decimals[0].max(decimals[1]);
+    compute_d(
+        amp,
+        upscale(pools[0], decimals[0], target_dec),
+        upscale(pools[1], decimals[1], target_dec),
+    )
+}
+
+/// Marginal price of the offer asset quoted in the ask asset, with both pools
+/// scaled to the larger of the two asset decimals
+pub fn spot_price(
+    amp: u64,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+) -> StdResult<Decimal256> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    marginal_price(amp, offer_pool, ask_pool, d)
+}
+
+/// Offer amount, in offer decimals and before commission, that lowers the spot
+/// price of the offer asset by `price_impact`. There is no closed form on the
+/// invariant, so the new offer pool is bisected down to one scaled unit.
+pub fn compute_depth(
+    amp: u64,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    price_impact: Decimal256,
+) -> StdResult<Uint128> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    let target_price =
+        marginal_price(amp, offer_pool, ask_pool, d)? * (Decimal256::one() - price_impact);
+    let price_at = |x: Uint256| -> StdResult<Decimal256> {
+        let y = compute_y(amp, x, d)?;
+        if y.is_zero() {
+            return Ok(Decimal256::zero());
+        }
+        marginal_price(amp, x, y, d)
+    };
+
+    // the price falls as the offer pool grows
+    let two = Uint256::from(2u8);
+    let mut low = offer_pool;
+    let mut high = offer_pool.checked_mul(two)?;
+    while price_at(high)? > target_price {
+        low = high;
+        high = high.checked_mul(two)?;
+    }
+    while high - low > Uint256::one() {
+        let mid = low + (high - low) / two;
+        if price_at(mid)? > target_price {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    downscale(high - offer_pool, offer_dec, target_dec, true)
+}
+
+/// Solves the invariant D for two balances with newton's method
+fn compute_d(amp: u64, x: Uint256, y: Uint256) -> StdResult<Uint256> {
+    let n = Uint256::from(N_COINS);
+    let sum = x.checked_add(y)?;
+    if sum.is_zero() {
+        return Ok(Uint256::zero());
+    }
+
+    let ann = Uint256::from(amp).checked_mul(n)?;
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        // d_p = d^(n+1) / (n^n * x * y)
+        let d_p = d
+            .checked_mul(d)?
+            .checked_div(x.checked_mul(n)?)?
+            .checked_mul(d)?
+            .checked_div(y.checked_mul(n)?)?;
+        let d_prev = d;
+        d = ann
+            .checked_mul(sum)?
+            .checked_add(d_p.checked_mul(n)?)?
+            .checked_mul(d)?
+            .checked_div(
+                (ann - Uint256::one())
+                    .checked_mul(d)?
+                    .checked_add((n + Uint256::one()).checked_mul(d_p)?)?,
+            )?;
+
+        if d.abs_diff(d_prev) <= Uint256::one() {
+            return Ok(d);
+        }
+    }
+
+    Err(StdError::generic_err(
+        "stable swap invariant did not converge",
+    ))
+}
+
+/// Balance of the other asset that keeps the invariant at `d` when one asset's balance is `x`
+fn compute_y(amp: u64, x: Uint256, d: Uint256) -> StdResult<Uint256> {
+    let n = Uint256::from(N_COINS);
+    let ann = Uint256::from(amp).checked_mul(n)?;
+
+    // c = d^(n+1) / (n^n * x * ann), b = x + d / ann
+    let c = d
+        .checked_mul(d)?
+        .checked_div(x.checked_mul(n)?)?
+        .checked_mul(d)?
+        .checked_div(ann.checked_mul(n)?)?;
+    let b = x.checked_add(d.checked_div(ann)?)?;
+
+    let mut y = d;
+    for _ in 0..ITERATIONS {
+        let y_prev = y;
+        y = y
+            .checked_mul(y)?
+            .checked_add(c)?
+            .checked_div(y.checked_mul(n)?.checked_add(b)?.checked_sub(d)?)?;
+
+        if y.abs_diff(y_prev) <= Uint256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(StdError::generic_err(
+        "stable swap invariant did not converge",
+    ))
+}
+
+/// `-dy/dx` on the invariant at balances `x`, `y`, from its partial derivatives
+/// `ann + D^3 / (4 * x^2 * y)` and `ann + D^3 / (4 * x * y^2)`
+fn marginal_price(amp: u64, x: Uint256, y: Uint256, d: Uint256) -> StdResult<Decimal256> {
+    let ann = Decimal256::from_ratio(Uint256::from(amp).checked_mul(Uint256::from(N_COINS))?, 1u8);
+    let n_pow_n = Decimal256::from_ratio(4u8, 1u8);
+    let d_x = Decimal256::from_ratio(d, x);
+    let d_y = Decimal256::from_ratio(d, y);
+
+    let offer_derivative = ann.checked_add(d_x.checked_mul(d_x)?.checked_mul(d_y)? / n_pow_n)?;
+    let ask_derivative = ann.checked_add(d_x.checked_mul(d_y)?.checked_mul(d_y)? / n_pow_n)?;
+
+    Ok(offer_derivative / ask_derivative)
+}
+
+pub(crate) fn upscale(amount: Uint128, dec: u8, target_dec: u8) -> Uint256 {
+    Uint256::from(amount) * Uint256::from(10u128.pow((target_dec - dec) as u32))
+}
+
+pub(crate) fn downscale(
+    amount: Uint256,
+    dec: u8,
+    target_dec: u8,
+    round_up: bool,
+) -> StdResult<Uint128> {
+    let factor = Uint256::from(10u128.pow((target_dec - dec) as u32));
+    let amount = if round_up {
+        amount.checked_add(factor - Uint256::one())? / factor
+    } else {
+        amount / factor
+    };
+
+    Ok(amount.try_into()?)
+}
@@ -1,4 +1,213 @@
-use choice::asset::PairInfoRaw;
-use cw_storage_plus::Item;
+use choice::asset::{Asset, PairInfoRaw};
+use choice::pair::{
+    BatchAuction, CircuitBreaker, DynamicFee, HoldingPeriod, LaunchProtection, PairStatus,
+    TradingStats,
+};
+use cosmwasm_std::{Addr, CanonicalAddr, Decimal, Decimal256, Empty, Uint128, Uint256};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 pub const PAIR_INFO: Item<PairInfoRaw> = Item::new("pair_info");
+
+/// Pool balances owned by the liquidity providers, in the pair's asset order.
+/// Balances above them are surplus that anyone can skim.
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
+
+/// Assets staged by each sender for a CW20 `provide_liquidity` hook, in the
+/// pair's asset order. They are held by the pair outside the reserves.
+pub const DEPOSITS: Map<&Addr, [Uint128; 2]> = Map::new("deposits");
+
+/// Sum of the pending `DEPOSITS`
+pub const DEPOSITS_TOTAL: Item<[Uint128; 2]> = Item::new("deposits_total");
+
+/// Factory that instantiated the pair, queried for the commission split
+pub const FACTORY: Item<CanonicalAddr> = Item::new("factory");
+
+/// Contracts notified after each swap, set by the factory
+pub const SWAP_HOOKS: Item<Vec<Addr>> = Item::new("swap_hooks");
+
+/// Trading status pushed by the factory, pairs without one are active
+pub const STATUS: Item<PairStatus> = Item::new("status");
+
+/// Amplification coefficient of a stable swap pair, ramped linearly
+/// from `init_amp` at `init_amp_time` to `next_amp` at `next_amp_time`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AmpConfig {
+    pub init_amp: u64,
+    pub init_amp_time: u64,
+    pub next_amp: u64,
+    pub next_amp_time: u64,
+}
+
+impl AmpConfig {
+    pub fn current(&self, time: u64) -> u64 {
+        if time >= self.next_amp_time {
+            return self.next_amp;
+        }
+
+        let elapsed = (time - self.init_amp_time) as u128;
+        let duration = (self.next_amp_time - self.init_amp_time) as u128;
+        let (init_amp, next_amp) = (self.init_amp as u128, self.next_amp as u128);
+
+        if next_amp > init_amp {
+            (init_amp + (next_amp - init_amp) * elapsed / duration) as u64
+        } else {
+            (init_amp - (init_amp - next_amp) * elapsed / duration) as u64
+        }
+    }
+}
+
+pub const AMP: Item<AmpConfig> = Item::new("amp");
+
+/// Asset weights of a weighted pair, in the pair's asset order
+pub const WEIGHTS: Item<[Decimal; 2]> = Item::new("weights");
+
+/// Volatility driven commission set by the factory, pairs without one charge
+/// their fee tier
+pub const DYNAMIC_FEE: Item<DynamicFee> = Item::new("dynamic_fee");
+
+/// Price movement recorded by the swaps of a pair with a dynamic fee
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Volatility {
+    /// Decimal adjusted price of asset 0 before the latest swap
+    pub price: Decimal256,
+    /// Relative price moves in bps, decayed up to `timestamp`
+    pub accumulator: Decimal256,
+    pub timestamp: u64,
+}
+
+pub const VOLATILITY: Item<Volatility> = Item::new("volatility");
+
+/// Batch auction mode set by the factory, pairs without one execute swaps
+/// immediately
+pub const BATCH_AUCTION: Item<BatchAuction> = Item::new("batch_auction");
+
+/// Batch of queued swaps, opened by its first order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Batch {
+    /// First block the batch can be settled at
+    pub settle_height: u64,
+    pub order_count: u64,
+    /// Queued offers in the pair's asset order, held by the pair outside the
+    /// reserves
+    pub offer_totals: [Uint128; 2],
+}
+
+pub const BATCH: Item<Batch> = Item::new("batch");
+
+/// Swap queued in the current batch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct QueuedOrder {
+    pub sender: Addr,
+    pub receiver: Addr,
+    pub offer_asset: Asset,
+    pub belief_price: Option<Decimal>,
+    pub max_spread: Option<Decimal>,
+    pub deadline: Option<u64>,
+}
+
+/// Orders of the current batch by id, ids restart with every batch
+pub const BATCH_ORDERS: Map<u64, QueuedOrder> = Map::new("batch_orders");
+
+/// Launch limits the pair was created with
+pub const LAUNCH_PROTECTION: Item<LaunchProtection> = Item::new("launch_protection");
+
+/// Block time of each address' latest swap during the launch protection
+pub const LAST_SWAPS: Map<&Addr, u64> = Map::new("last_swaps");
+
+/// Circuit breaker thresholds set by the factory
+pub const CIRCUIT_BREAKER: Item<CircuitBreaker> = Item::new("circuit_breaker");
+
+/// Window the circuit breaker measures swaps over, opened by the first swap
+/// after the previous window ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BreakerWindow {
+    pub start: u64,
+    /// Decimal adjusted spot price of each asset quoted in the other one when
+    /// the window opened
+    pub start_prices: [Decimal256; 2],
+    pub start_reserves: [Uint128; 2],
+    /// Amounts swapped into and out of the reserves since the window opened
+    pub inflows: [Uint128; 2],
+    pub outflows: [Uint128; 2],
+}
+
+pub const BREAKER_WINDOW: Item<BreakerWindow> = Item::new("breaker_window");
+
+/// Minimum LP holding period set by the factory
+pub const HOLDING_PERIOD: Item<HoldingPeriod> = Item::new("holding_period");
+
+/// LP minted under the holding period and its average deposit time, weighted
+/// by amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LpDeposit {
+    pub amount: Uint128,
+    pub time: u64,
+    /// `HOLDING_PERIOD_EPOCH` the deposit was recorded in
+    pub epoch: u64,
+}
+
+/// Deposit of each LP receiver, only those of the current epoch count
+pub const LP_DEPOSITS: Map<&Addr, LpDeposit> = Map::new("lp_deposits");
+
+/// Bumped whenever the holding period is removed, which drops every recorded
+/// deposit without iterating over them
+pub const HOLDING_PERIOD_EPOCH: Item<u64> = Item::new("holding_period_epoch");
+
+/// Allowlist settings of a permissioned pair, absent for public pairs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AllowlistConfig {
+    /// Manages the allowlist along with the factory
+    pub manager: Option<Addr>,
+    pub restrict_swaps: bool,
+}
+
+pub const ALLOWLIST_CONFIG: Item<AllowlistConfig> = Item::new("allowlist_config");
+
+/// Addresses allowed to provide liquidity, and to swap with `restrict_swaps`
+pub const ALLOWLIST: Map<&Addr, Empty> = Map::new("allowlist");
+
+/// Trading totals since instantiation
+pub const STATS: Item<TradingStats> = Item::new("stats");
+
+/// Trading totals of each epoch with swaps, by `STATS_EPOCH_SECONDS` epoch
+pub const EPOCH_STATS: Map<u64, TradingStats> = Map::new("epoch_stats");
+
+/// Cumulative prices at `timestamp`, each the sum of the decimal adjusted
+/// price (in atomics) multiplied by the seconds it was in effect
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price0_cumulative: Uint256,
+    pub price1_cumulative: Uint256,
+}
+
+/// Position of the newest observation in the ring buffer and the number of
+/// slots written so far
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ObservationIndex {
+    pub latest: u32,
+    pub count: u32,
+}
+
+pub const OBSERVATIONS: Map<u32, Observation> = Map::new("observations");
+pub const OBSERVATION_INDEX: Item<ObservationIndex> = Item::new("observation_index");
+
+/// Flash swap awaiting repayment, also guards the pair against reentrancy
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FlashSwapState {
+    pub borrower: Addr,
+    /// Reserves before the assets were lent out
+    pub pools: [Uint128; 2],
+    /// Balances before the assets were lent out, repayments are measured
+    /// against them
+    pub balances: [Uint128; 2],
+    pub amounts_out: [Uint128; 2],
+    /// Commission rate of the loan in bps, charged again on settlement
+    pub commission_bps: u16,
+    /// Fee discount of the initiator, taken off the commission rate
+    pub discount: Decimal,
+}
+
+pub const FLASH_SWAP: Item<FlashSwapState> = Item::new("flash_swap");
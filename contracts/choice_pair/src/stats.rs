@@ -0,0 +1,86 @@
+// Running trading totals. Every swap adds its volume, commission and protocol
+// fees to the totals since instantiation and to the bucket of the epoch its
+// block falls in, so volume and revenue can be read without replaying events.
+
+use crate::state::{EPOCH_STATS, STATS};
+use choice::pair::{EpochStats, TradingStats, STATS_EPOCH_SECONDS};
+use cosmwasm_std::{Order, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Stats of a swap of `offer_amount` returning `return_amount` of the other
+/// asset, the commission is charged in the returned asset
+pub fn swap(
+    offer_index: usize,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    commission_amount: Uint128,
+    burn_amount: Uint128,
+    fee_wallet_amount: Uint128,
+) -> TradingStats {
+    let ask_index = 1 - offer_index;
+    let mut stats = TradingStats {
+        swap_count: 1,
+        ..TradingStats::default()
+    };
+    stats.volume[offer_index] = offer_amount;
+    stats.volume[ask_index] = return_amount;
+    stats.commission_amount[ask_index] = commission_amount;
+    stats.burn_amount[ask_index] = burn_amount;
+    stats.fee_wallet_amount[ask_index] = fee_wallet_amount;
+
+    stats
+}
+
+/// Adds `trade` to the totals and to the epoch of `now`
+pub fn record(storage: &mut dyn Storage, now: u64, trade: &TradingStats) -> StdResult<()> {
+    let mut total = STATS.may_load(storage)?.unwrap_or_default();
+    add(&mut total, trade);
+    STATS.save(storage, &total)?;
+
+    let epoch = now / STATS_EPOCH_SECONDS;
+    let mut stats = EPOCH_STATS.may_load(storage, epoch)?.unwrap_or_default();
+    add(&mut stats, trade);
+    EPOCH_STATS.save(storage, epoch, &stats)
+}
+
+pub fn read_total(storage: &dyn Storage) -> StdResult<TradingStats> {
+    Ok(STATS.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn read_epochs(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EpochStats>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    EPOCH_STATS
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (epoch, stats) = item?;
+            Ok(EpochStats { epoch, stats })
+        })
+        .collect()
+}
+
+// saturating, running totals must never make a swap fail
+fn add(stats: &mut TradingStats, trade: &TradingStats) {
+    stats.swap_count = stats.swap_count.saturating_add(trade.swap_count);
+    for i in 0..2 {
+        stats.volume[i] = stats.volume[i].saturating_add(trade.volume[i]);
+        stats.commission_amount[i] =
+            stats.commission_amount[i].saturating_add(trade.commission_amount[i]);
+        stats.burn_amount[i] = stats.burn_amount[i].saturating_add(trade.burn_amount[i]);
+        stats.fee_wallet_amount[i] =
+            stats.fee_wallet_amount[i].saturating_add(trade.fee_wallet_amount[i]);
+    }
+}
@@ -1,25 +1,46 @@
 use crate::contract::{
-    assert_deadline, assert_max_spread, assert_minimum_assets, compute_swap, execute, instantiate, query_pair_info, query_pool, query_reverse_simulation, query_simulation
+    assert_deadline, assert_max_spread, assert_minimum_assets, commission_rate, compute_depth, compute_offer_amount, compute_spot_price, compute_swap, execute, instantiate, migrate, query_allowlist, query_amp, query_batch_auction, query_batch_orders, query_circuit_breaker, query_depth, query_dynamic_fee, query_holding_period, query_launch_protection, query_lp_deposit, query_pair_info, query_pending_deposit, query_pool, query_reverse_simulation, query_simulate_provide, query_simulate_withdraw, query_simulation, query_spot_price, query_stats, query_swap_hooks, query_twap, query_weights, reply
 };
+use crate::circuit_breaker;
+use crate::dynamic_fee;
 use crate::error::ContractError;
+use crate::state::{FACTORY, FLASH_SWAP, RESERVES};
+use crate::stableswap;
+use crate::twap::{self, MAX_OBSERVATIONS};
+use crate::weighted;
 use std::str::FromStr;
 
 use choice::asset::{Asset, AssetInfo, PairInfo};
-use choice::mock_querier::mock_dependencies;
+use choice::factory::FeeSplit;
+use choice::pair::{Allowlist, AllowlistResponse, AmpResponse, BatchAuction, BatchAuctionResponse, CircuitBreaker, CircuitBreakerResponse, DepthLevel, DepthResponse, DynamicFee, DynamicFeeResponse, EpochStats, FlashSwapCallbackMsg, HoldingPeriod, HoldingPeriodResponse, LaunchProtection, LaunchProtectionResponse, LpDepositResponse, PairStatus, PairType, SimulateProvideResponse, SimulateWithdrawResponse, SpotPriceResponse, SwapHookMsg, SwapHooksResponse, TradingStats, TwapResponse, WeightsResponse, STATS_EPOCH_SECONDS};
+use choice::mock_querier::{mock_dependencies, WasmMockQuerier};
 use choice::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, ReverseSimulationResponse,
     SimulationResponse,
 };
-use cosmwasm_std::testing::{message_info, mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{message_info, mock_env, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, coins, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Decimal256, ReplyOn, Response, StdError, SubMsg, Uint128, Uint256, WasmMsg
+    attr, coins, from_json, to_json_binary, Reply, SubMsgResponse, SubMsgResult, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Decimal256, OwnedDeps, ReplyOn, Response, StdError, SubMsg, Timestamp, Uint128, Uint256, WasmMsg
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use injective_cosmwasm::msg::{create_new_denom_msg, create_set_token_metadata_msg};
-use injective_cosmwasm::InjectiveMsgWrapper;
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
 use injective_cosmwasm::{create_burn_tokens_msg, create_mint_tokens_msg};
 use std::convert::TryInto;
 
+/// Seeds the reserves the pair trades against, balances above them are surplus
+fn set_reserves(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+    reserves: [u128; 2],
+) {
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::from(reserves[0]), Uint128::from(reserves[1])],
+        )
+        .unwrap();
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
@@ -36,6 +57,12 @@ fn proper_initialization() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -131,6 +158,12 @@ fn provide_liquidity() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
@@ -278,6 +311,7 @@ fn provide_liquidity() {
         &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
         Uint128::from(1_100u128),
     )]);
+    set_reserves(&mut deps, [200, 200]);
 
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: [
@@ -342,6 +376,7 @@ fn provide_liquidity() {
         &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
         Uint128::from(100u128),
     )]);
+    set_reserves(&mut deps, [200, 200]);
 
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: [
@@ -462,6 +497,7 @@ fn provide_liquidity() {
         &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
         Uint128::from(100u128),
     )]);
+    set_reserves(&mut deps, [100, 100]);
 
     // successfully provide liquidity, and refund remain asset
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -565,12 +601,19 @@ fn withdraw_liquidity() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    set_reserves(&mut deps, [100, 100]);
 
     // failed to withdraw liquidity, did not pass funds
     let msg = ExecuteMsg::WithdrawLiquidity {
@@ -662,6 +705,7 @@ fn withdraw_liquidity() {
         )
     );
 
+    set_reserves(&mut deps, [100, 100]);
     // withdraw liquidity with assert min_assets
     let msg = ExecuteMsg::WithdrawLiquidity {
         min_assets: Some([
@@ -759,12 +803,22 @@ fn try_native_to_token() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    set_reserves(
+        &mut deps,
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     // normal swap
     let msg = ExecuteMsg::Swap {
@@ -778,6 +832,7 @@ fn try_native_to_token() {
         max_spread: None,
         to: None,
         deadline: None,
+        trader: None,
     };
     let env = mock_env();
     let info = message_info(
@@ -818,15 +873,21 @@ fn try_native_to_token() {
             amount: collateral_pool_amount, /* user deposit must be pre-applied */
         }],
     )]);
+    set_reserves(
+        &mut deps,
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
             amount: offer_amount,
         },
+        None,
     )
     .unwrap();
     assert_eq!(expected_return_amount, simulation_res.return_amount);
@@ -836,12 +897,14 @@ fn try_native_to_token() {
     // check reverse simulation res
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
             amount: expected_return_amount,
         },
+        None,
     )
     .unwrap();
 
@@ -951,12 +1014,22 @@ fn try_token_to_native() {
         asset_decimals: [8u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    set_reserves(
+        &mut deps,
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     // unauthorized access; can not execute swap directly for token swap
     let msg = ExecuteMsg::Swap {
@@ -970,6 +1043,7 @@ fn try_token_to_native() {
         max_spread: None,
         to: None,
         deadline: None,
+        trader: None,
     };
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -989,6 +1063,7 @@ fn try_token_to_native() {
             max_spread: None,
             to: None,
             deadline: None,
+            trader: None,
         })
         .unwrap(),
     });
@@ -1039,6 +1114,10 @@ fn try_token_to_native() {
         &deps.api.addr_make("asset0000").to_string(),
         &[(&MOCK_CONTRACT_ADDR.to_string(), &(asset_pool_amount))],
     )]);
+    set_reserves(
+        &mut deps,
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     deps.querier.with_token_factory_denom_supply(&[(
         &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
@@ -1047,12 +1126,14 @@ fn try_token_to_native() {
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: offer_amount,
             info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
         },
+        None,
     )
     .unwrap();
     assert_eq!(expected_return_amount, simulation_res.return_amount);
@@ -1062,12 +1143,14 @@ fn try_token_to_native() {
     // check reverse simulation res
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: expected_return_amount,
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
         },
+        None,
     )
     .unwrap();
     assert!(
@@ -1133,6 +1216,7 @@ fn try_token_to_native() {
             max_spread: None,
             to: None,
             deadline: None,
+            trader: None,
         })
         .unwrap(),
     });
@@ -1362,12 +1446,19 @@ fn test_query_pool() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    set_reserves(&mut deps, [asset_0_amount.u128(), asset_1_amount.u128()]);
 
     let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
 
@@ -1736,6 +1827,12 @@ fn test_initial_liquidity_provide() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -1859,6 +1956,12 @@ fn test_create_pair_simulated() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
     let env = mock_env();
     let creator = deps.api.addr_make("creator");
@@ -1994,6 +2097,12 @@ fn simulate_token_to_native_underflow() {
         asset_decimals: [6u8, 18u8], 
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
     };
     instantiate(
         deps.as_mut(),
@@ -2002,15 +2111,18 @@ fn simulate_token_to_native_underflow() {
         init_msg,
     )
     .unwrap();
+    set_reserves(&mut deps, [ask_pool_native.u128(), offer_pool_token.u128()]);
 
     let _ = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("cw20token").to_string(),
             },
             amount: offer_amount,
         },
+        None,
     )
     .unwrap();
 }
@@ -2052,9 +2164,16 @@ fn provide_liquidity_lp_overflow() {
             asset_decimals: [6, 6],
             burn_address:   burn.to_string(),
             fee_wallet_address: fees.to_string(),
+            commission_bps: 30,
+            pair_type: PairType::ConstantProduct,
+            amp: None,
+            weights: None,
+            launch_protection: None,
+            allowlist: None,
         },
     )
     .unwrap();
+    set_reserves(&mut deps, [999, 999]);
 
     let exec_msg = ExecuteMsg::ProvideLiquidity {
         assets: [
@@ -2095,7 +2214,7 @@ fn test_compute_swap_with_huge_pool_variance() {
     let ask_pool = Uint128::from(317u128);
 
     assert_eq!(
-        compute_swap(offer_pool, ask_pool, Uint128::from(1u128), 6, 6)
+        compute_swap(offer_pool, ask_pool, Uint128::from(1u128), 6, 6, commission_rate(30))
             .unwrap()
             .0,
         Uint128::zero()
@@ -2109,7 +2228,8 @@ fn swap_6_vs_18_does_not_panic() {
     let offer_pool= Uint128::new(1_000_000_000_000_000_000u128); // 1.0 (18-dec)
     let offer_amt = Uint128::new(500_000_000_000_000_000u128);   // 0.5 (18-dec)
 
-    let (ret, _, _) = compute_swap(offer_pool, ask_pool, offer_amt, 18, 6).unwrap();
+    let (ret, _, _) =
+        compute_swap(offer_pool, ask_pool, offer_amt, 18, 6, commission_rate(30)).unwrap();
     assert!(ret > Uint128::zero());
 }
 
@@ -2124,7 +2244,7 @@ fn test_compute_swap_max_whole_tokens() {
 
     // Should compute without panic/overflow
     let (return_amount, spread_amount, commission_amount) =
-        compute_swap(pool_size, pool_size, offer_amount, 18, 18).unwrap();
+        compute_swap(pool_size, pool_size, offer_amount, 18, 18, commission_rate(30)).unwrap();
 
     // Invariants for the max‐whole scenario:
     // 1. return_amount never exceeds the ask pool
@@ -2149,3 +2269,5467 @@ fn test_compute_swap_max_whole_tokens() {
         offer_amount
     );
 }
+
+#[test]
+fn instantiate_with_zero_commission_fails() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 0,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        StdError::generic_err("commission_bps must be greater than zero")
+    );
+}
+
+#[test]
+fn compute_swap_uses_fee_tier() {
+    let pool = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+
+    let (return_30, _, commission_30) =
+        compute_swap(pool, pool, offer_amount, 6, 6, commission_rate(30)).unwrap();
+    let (return_5, _, commission_5) =
+        compute_swap(pool, pool, offer_amount, 6, 6, commission_rate(5)).unwrap();
+
+    // 0.3% vs 0.05% of the ~999_999 return, rounded up
+    assert_eq!(commission_30, Uint128::from(3_000u128));
+    assert_eq!(commission_5, Uint128::from(500u128));
+    assert_eq!(return_30 + commission_30, return_5 + commission_5);
+}
+
+#[test]
+fn swap_with_pair_fee_split() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + offer_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        total_share,
+    )]);
+
+    // no burn, half of the commission to the fee wallet
+    let fee_split = FeeSplit {
+        burn: Decimal::zero(),
+        fee_wallet: Decimal::percent(50),
+    };
+    deps.querier
+        .with_pair_fee_split(MOCK_CONTRACT_ADDR, fee_split.clone());
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    set_reserves(
+        &mut deps,
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.fee_split, fee_split);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 952.380952 = 20000 - 20000 * 30000 / (30000 + 1500)
+    let expected_ret_amount = Uint128::from(952_380_952u128);
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(3u128, 1000u128) + Uint128::from(1u8); // 0.3%, round up
+    let expected_fee_wallet_amount = expected_commission_amount.multiply_ratio(1u128, 2u128);
+    let expected_return_amount = expected_ret_amount - expected_commission_amount;
+
+    let attribute = |key: &str| {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attribute("burn_amount"), "0");
+    assert_eq!(
+        attribute("fee_wallet_amount"),
+        expected_fee_wallet_amount.to_string()
+    );
+    assert_eq!(
+        attribute("pool_amount"),
+        (expected_commission_amount - expected_fee_wallet_amount).to_string()
+    );
+
+    // return to the trader and the fee wallet share, nothing is sent to the burn handler
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("addr0000").to_string(),
+                    amount: expected_return_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("feeaddr0000").to_string(),
+                    amount: expected_fee_wallet_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+}
+
+#[test]
+fn stableswap_compute_swap_balanced_pool() {
+    let pool = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000_000u128);
+
+    let (return_amount, spread_amount, commission_amount) =
+        stableswap::compute_swap(100, pool, pool, offer_amount, 6, 6, commission_rate(5)).unwrap();
+    assert_eq!(return_amount, Uint128::from(999_490_103u128));
+    assert_eq!(spread_amount, Uint128::from(9_901u128));
+    assert_eq!(commission_amount, Uint128::from(499_996u128));
+
+    // far less slippage than the constant product curve at the same fee
+    let (cp_return_amount, _, _) =
+        compute_swap(pool, pool, offer_amount, 6, 6, commission_rate(5)).unwrap();
+    assert_eq!(cp_return_amount, Uint128::from(998_501_498u128));
+
+    // reverse simulation of the same trade asks for the original offer
+    let (reverse_offer_amount, reverse_spread_amount, reverse_commission_amount) =
+        stableswap::compute_offer_amount(100, pool, pool, return_amount, 6, 6, commission_rate(5))
+            .unwrap();
+    assert_eq!(reverse_offer_amount, offer_amount);
+    assert_eq!(reverse_spread_amount, spread_amount);
+    assert_eq!(reverse_commission_amount, commission_amount);
+}
+
+#[test]
+fn stableswap_compute_swap_with_diff_decimal() {
+    // 1,000,000 of a 6 decimal asset against 1,000,000 of an 18 decimal asset
+    let pool_6 = Uint128::from(1_000_000_000_000u128);
+    let pool_18 = Uint128::from(1_000_000_000_000_000_000_000_000u128);
+
+    let (return_amount, spread_amount, commission_amount) = stableswap::compute_swap(
+        100,
+        pool_6,
+        pool_18,
+        Uint128::from(1_000_000_000u128),
+        6,
+        18,
+        commission_rate(5),
+    )
+    .unwrap();
+    assert_eq!(
+        return_amount,
+        Uint128::from(999_490_104_048_674_889_848u128)
+    );
+    assert_eq!(spread_amount, Uint128::from(9_900_901_775_998_151u128));
+    assert_eq!(
+        commission_amount,
+        Uint128::from(499_995_049_549_112_001u128)
+    );
+
+    // 999 of the 6 decimal asset costs a little over 999 of the 18 decimal one
+    let (offer_amount, _, commission_amount) = stableswap::compute_offer_amount(
+        100,
+        pool_18,
+        pool_6,
+        Uint128::from(999_000_000u128),
+        18,
+        6,
+        commission_rate(5),
+    )
+    .unwrap();
+    assert_eq!(offer_amount, Uint128::from(999_509_641_069_141_453_443u128));
+    assert_eq!(commission_amount, Uint128::from(499_749u128));
+
+    // asking for the whole pool fails instead of panicking
+    assert_eq!(
+        stableswap::compute_offer_amount(100, pool_18, pool_6, pool_6, 18, 6, commission_rate(5))
+            .unwrap_err(),
+        StdError::generic_err("ask amount exceeds the pool")
+    );
+}
+
+fn stable_instantiate_msg(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+    amp: Option<u64>,
+) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 5,
+        pair_type: PairType::StableSwap,
+        amp,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    }
+}
+
+#[test]
+fn stableswap_instantiate_requires_amp() {
+    let mut deps = mock_dependencies(&[]);
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+
+    let msg = stable_instantiate_msg(&deps, None);
+    assert_eq!(
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+        StdError::generic_err("stable swap pairs require an amp between 1 and 1000000")
+    );
+
+    let msg = stable_instantiate_msg(&deps, Some(1_000_001));
+    assert_eq!(
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+        StdError::generic_err("stable swap pairs require an amp between 1 and 1000000")
+    );
+
+    let mut msg = stable_instantiate_msg(&deps, Some(100));
+    msg.pair_type = PairType::ConstantProduct;
+    assert_eq!(
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        StdError::generic_err("amp is only supported by stable swap pairs")
+    );
+}
+
+#[test]
+fn stableswap_ramp_amp() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+
+    let msg = stable_instantiate_msg(&deps, Some(100));
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    instantiate(deps.as_mut(), env.clone(), message_info(&factory, &[]), msg).unwrap();
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::StableSwap);
+    assert_eq!(
+        query_amp(deps.as_ref(), env.clone()).unwrap(),
+        AmpResponse {
+            amp: 100,
+            future_amp: 100,
+            future_time: now,
+        }
+    );
+
+    // only the factory may ramp
+    let ramp = |future_amp: u64, future_time: u64| ExecuteMsg::RampAmp {
+        future_amp,
+        future_time,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ramp(200, now + 2 * 86_400),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&factory, &[]),
+        ramp(200, now + 1_000),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::MinAmpRampTimeAssertion {
+            min_ramp_time: 86_400
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&factory, &[]),
+        ramp(1_001, now + 2 * 86_400),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::MaxAmpChangeAssertion { max_change: 10 }
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&factory, &[]),
+        ramp(200, now + 2 * 86_400),
+    )
+    .unwrap();
+
+    // halfway through the ramp
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86_400);
+    assert_eq!(
+        query_amp(deps.as_ref(), env.clone()).unwrap(),
+        AmpResponse {
+            amp: 150,
+            future_amp: 200,
+            future_time: now + 2 * 86_400,
+        }
+    );
+
+    // stopping freezes the coefficient where it is
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::StopRampAmp {});
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&factory, &[]),
+        ExecuteMsg::StopRampAmp {},
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(2 * 86_400);
+    assert_eq!(query_amp(deps.as_ref(), env).unwrap().amp, 150);
+}
+
+#[test]
+fn ramp_amp_on_constant_product_pair_fails() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+
+    let mut msg = stable_instantiate_msg(&deps, None);
+    msg.pair_type = PairType::ConstantProduct;
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::RampAmp {
+            future_amp: 200,
+            future_time: mock_env().block.time.seconds() + 2 * 86_400,
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NotStableSwap {});
+    assert_eq!(
+        query_amp(deps.as_ref(), mock_env()).unwrap_err(),
+        ContractError::NotStableSwap {}
+    );
+}
+
+#[test]
+fn stableswap_swap() {
+    let pool_amount = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + offer_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &pool_amount)],
+    )]);
+
+    let msg = stable_instantiate_msg(&deps, Some(100));
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+    let msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        belief_price: None,
+        max_spread: Some(Decimal::percent(1)),
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // same trade as in stableswap_compute_swap_balanced_pool
+    let expected_return_amount = Uint128::from(999_490_103u128);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("addr0000").to_string(),
+                amount: expected_return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        }))
+    );
+
+    // simulation against the pre-swap reserves agrees
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let simulation_res = query_simulation(deps.as_ref(), mock_env(), offer_asset, None).unwrap();
+    assert_eq!(simulation_res.return_amount, expected_return_amount);
+}
+
+#[test]
+fn twap_average_price() {
+    let mut storage = MockStorage::new();
+    let decimals = [6u8, 18u8];
+    let pools = [
+        Uint128::from(1_000_000_000u128),
+        Uint128::from(2_000_000_000_000_000_000_000u128),
+    ];
+    let pools_after_swap = [
+        Uint128::from(1_250_000_000u128),
+        Uint128::from(1_600_000_000_000_000_000_000u128),
+    ];
+
+    // nothing is recorded before the first action
+    assert_eq!(
+        twap::average_price(&storage, 1_000, pools, decimals, 0, 10).unwrap_err(),
+        ContractError::TwapWindowTooLong { max_window: 0 }
+    );
+
+    // first provide on empty reserves, then two swaps
+    twap::accumulate_prices(&mut storage, 1_000, [Uint128::zero(); 2], decimals).unwrap();
+    twap::accumulate_prices(&mut storage, 1_100, pools, decimals).unwrap();
+    twap::accumulate_prices(&mut storage, 1_200, pools_after_swap, decimals).unwrap();
+    // a second action in the same block does not add an observation
+    twap::accumulate_prices(&mut storage, 1_200, pools, decimals).unwrap();
+
+    let average = |window: u64, asset_index: usize| {
+        twap::average_price(
+            &storage,
+            1_300,
+            pools_after_swap,
+            decimals,
+            asset_index,
+            window,
+        )
+    };
+
+    // 1.28 since the swap at 1_100
+    assert_eq!(
+        average(200, 0).unwrap(),
+        Decimal256::from_str("1.28").unwrap()
+    );
+    assert_eq!(
+        average(200, 1).unwrap(),
+        Decimal256::from_str("0.78125").unwrap()
+    );
+    // 2 for the first 100 seconds
+    assert_eq!(
+        average(300, 0).unwrap(),
+        Decimal256::from_str("1.52").unwrap()
+    );
+    // window starting between two observations
+    assert_eq!(
+        average(250, 0).unwrap(),
+        Decimal256::from_str("1.424").unwrap()
+    );
+    // window starting after the latest observation
+    assert_eq!(
+        average(50, 0).unwrap(),
+        Decimal256::from_str("1.28").unwrap()
+    );
+
+    assert_eq!(
+        average(301, 0).unwrap_err(),
+        ContractError::TwapWindowTooLong { max_window: 300 }
+    );
+    assert_eq!(
+        average(0, 0).unwrap_err(),
+        ContractError::InvalidTwapWindow {}
+    );
+}
+
+#[test]
+fn twap_follows_stableswap_curve() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = stable_instantiate_msg(&deps, Some(100));
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // a 60/40 pool trades near 1:1 while its reserve ratio is 1.5
+    let reserves = [
+        Uint128::from(600_000_000_000u128),
+        Uint128::from(400_000_000_000u128),
+    ];
+    set_reserves(&mut deps, [reserves[0].u128(), reserves[1].u128()]);
+    let start = mock_env().block.time;
+    twap::accumulate_prices(deps.as_mut().storage, start.seconds(), reserves, [6, 6]).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(600);
+    for asset in [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+    ] {
+        let twap = query_twap(deps.as_ref(), env.clone(), asset.clone(), 600).unwrap();
+        let spot = query_spot_price(deps.as_ref(), env.clone(), asset).unwrap();
+        assert_eq!(twap.price, spot.price);
+        assert!(twap.price > Decimal256::percent(90) && twap.price < Decimal256::percent(110));
+    }
+}
+
+#[test]
+fn twap_ring_buffer_overwrites_oldest() {
+    let mut storage = MockStorage::new();
+    let pools = [Uint128::from(1_000u128), Uint128::from(3_000u128)];
+
+    let last = 1_000 + MAX_OBSERVATIONS as u64 + 10;
+    for time in 1_000..=last {
+        twap::accumulate_prices(&mut storage, time, pools, [6, 6]).unwrap();
+    }
+
+    let max_window = MAX_OBSERVATIONS as u64 - 1;
+    assert_eq!(
+        twap::average_price(&storage, last, pools, [6, 6], 0, max_window).unwrap(),
+        Decimal256::from_str("3").unwrap()
+    );
+    assert_eq!(
+        twap::average_price(&storage, last, pools, [6, 6], 0, max_window + 1).unwrap_err(),
+        ContractError::TwapWindowTooLong { max_window }
+    );
+}
+
+#[test]
+fn twap_query_after_swap() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + offer_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &(pool_amount * Uint128::from(4u8)),
+        )],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128() * 4]);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let env = mock_env();
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // reserves are put back as they were before the swap, so the price is constant
+    let mut later_env = env.clone();
+    later_env.block.time = env.block.time.plus_seconds(600);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128() * 4]);
+
+    let res: TwapResponse =
+        query_twap(deps.as_ref(), later_env.clone(), uusd.clone(), 600).unwrap();
+    assert_eq!(
+        res,
+        TwapResponse {
+            asset: uusd,
+            price: Decimal256::from_str("4").unwrap(),
+            start_time: env.block.time.seconds(),
+            end_time: later_env.block.time.seconds(),
+        }
+    );
+
+    let res = query_twap(
+        deps.as_ref(),
+        later_env,
+        AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+        600,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AssetMismatch {});
+}
+
+fn flash_swap_deps(
+    pool_amount: Uint128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &pool_amount)],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    deps
+}
+
+#[allow(deprecated)]
+fn flash_swap_reply() -> Reply {
+    Reply {
+        id: 1,
+        payload: Binary::default(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![],
+        }),
+    }
+}
+
+#[test]
+fn flash_swap_repaid() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let borrow_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+
+    let token = deps.api.addr_make("asset0000").to_string();
+    let borrower = deps.api.addr_make("borrower0000");
+    let asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: token.clone(),
+        },
+        amount: borrow_amount,
+    };
+    let msg = ExecuteMsg::FlashSwap {
+        asset: asset.clone(),
+        to: None,
+        msg: Binary::from(b"liquidate".to_vec()),
+    };
+    let info = message_info(&borrower, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 1_003_009 would leave 999_999 after its 3_010 commission
+    let repay_amount = Uint128::from(1_003_010u128);
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: borrower.to_string(),
+                    amount: borrow_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: borrower.to_string(),
+                    msg: to_json_binary(&FlashSwapCallbackMsg::FlashSwapCallback {
+                        initiator: borrower.to_string(),
+                        asset,
+                        repay_amount,
+                        msg: Binary::from(b"liquidate".to_vec()),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                1,
+            ),
+        ]
+    );
+
+    // the pair cannot be used until the loan is settled
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(100u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(&borrower, &coins(100u128, "uusd"));
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err(),
+        ContractError::FlashSwapInProgress {}
+    );
+
+    // borrower transferred the repayment back during the callback
+    deps.querier.with_token_balances(&[(
+        &token,
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &(pool_amount - borrow_amount + repay_amount),
+        )],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), flash_swap_reply()).unwrap();
+
+    // default split, 1/6 of the 3_010 commission each to the burn handler and the fee wallet
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: deps.api.addr_make("burnaddr0000").to_string(),
+                    amount: Uint128::from(501u128),
+                    msg: Binary::default(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("feeaddr0000").to_string(),
+                    amount: Uint128::from(501u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_flash_swap"),
+            attr("borrower", borrower.as_str()),
+            attr("commission_amounts", format!("0uusd, 3010{}", token)),
+            attr("burn_amounts", format!("0uusd, 501{}", token)),
+            attr("fee_wallet_amounts", format!("0uusd, 501{}", token)),
+        ]
+    );
+
+    // settled, the pair accepts messages again
+    let info = message_info(&borrower, &coins(100u128, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+#[test]
+fn flash_swap_not_repaid() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let borrow_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+
+    let asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: pool_amount,
+    };
+    let msg = ExecuteMsg::FlashSwap {
+        asset: asset.clone(),
+        to: None,
+        msg: Binary::default(),
+    };
+    let info = message_info(&deps.api.addr_make("borrower0000"), &[]);
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        ContractError::FlashSwapExceedsReserve {}
+    );
+
+    let msg = ExecuteMsg::FlashSwap {
+        asset: Asset {
+            amount: borrow_amount,
+            ..asset
+        },
+        to: Some(deps.api.addr_make("receiver0000").to_string()),
+        msg: Binary::default(),
+    };
+    let info = message_info(&deps.api.addr_make("borrower0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // only the borrowed amount came back, the commission is missing
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    assert_eq!(
+        reply(deps.as_mut(), mock_env(), flash_swap_reply()).unwrap_err(),
+        ContractError::FlashSwapNotRepaid {}
+    );
+}
+
+fn zap_in_deps(
+    uusd_balance: Uint128,
+    token_balance: Uint128,
+    total_share: Uint128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: uusd_balance,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &token_balance)],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        total_share,
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+#[test]
+fn zap_in_native() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let total_share = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(100_000_000u128);
+    let mut deps = zap_in_deps(pool_amount + offer_amount, pool_amount, total_share);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let msg = ExecuteMsg::ZapIn {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        min_lp: Some(Uint128::from(48_000_000u128)),
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(100_000_000u128, "uusd"),
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+
+    // 51_115_440 uusd and 46_466_415 token are deposited, both 4.8733% of the
+    // post-swap pools (1_048_884_560 and 953_486_979)
+    let sender = deps.api.addr_make("addr0000").to_string();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "zap_in"),
+            attr("sender", sender.clone()),
+            attr("receiver", sender.clone()),
+            attr("offer_asset", "100000000uusd"),
+            attr("swap_amount", "48884560"),
+            attr("return_amount", "46466415"),
+            attr("commission_amount", "139819"),
+            attr("burn_amount", "23303"),
+            attr("fee_wallet_amount", "23303"),
+            attr("share", "48733140"),
+        ]
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            Coin {
+                denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                amount: Uint128::from(48_733_140u128),
+            },
+            sender.clone(),
+        ))
+    );
+
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let msg = match msg {
+        ExecuteMsg::ZapIn {
+            offer_asset,
+            receiver,
+            deadline,
+            ..
+        } => ExecuteMsg::ZapIn {
+            offer_asset,
+            min_lp: Some(Uint128::from(48_733_141u128)),
+            receiver,
+            deadline,
+        },
+        _ => panic!("DO NOT ENTER HERE"),
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(100_000_000u128, "uusd"),
+    );
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        ContractError::MinLpAssertion {
+            min_lp: "48733141".to_string(),
+            share: "48733140".to_string(),
+        }
+    );
+}
+
+#[test]
+fn zap_in_token() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let total_share = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(100_000_000u128);
+    let mut deps = zap_in_deps(pool_amount, pool_amount + offer_amount, total_share);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let receiver = deps.api.addr_make("receiver0000").to_string();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: offer_amount,
+        msg: to_json_binary(&Cw20HookMsg::ZapIn {
+            min_lp: None,
+            receiver: Some(receiver.clone()),
+            deadline: None,
+        })
+        .unwrap(),
+    });
+
+    // only the pair's tokens can zap in
+    let info = message_info(&deps.api.addr_make("asset0001"), &[]);
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+
+    // mirror of zap_in_native, the protocol fees are paid in uusd
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("burnaddr0000").to_string(),
+                msg: to_json_binary(&choice::send_to_auction::ExecuteMsg::SendNative {
+                    asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::from(23_303u128),
+                    },
+                })
+                .unwrap(),
+                funds: coins(23_303u128, "uusd"),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: deps.api.addr_make("feeaddr0000").to_string(),
+                amount: coins(23_303u128, "uusd"),
+            }),
+            SubMsg::new(create_mint_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                Coin {
+                    denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                    amount: Uint128::from(48_733_140u128),
+                },
+                receiver,
+            )),
+        ]
+    );
+}
+
+#[test]
+fn zap_in_empty_pool() {
+    let offer_amount = Uint128::from(100_000_000u128);
+    let mut deps = zap_in_deps(offer_amount, Uint128::zero(), Uint128::zero());
+
+    let msg = ExecuteMsg::ZapIn {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        min_lp: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(100_000_000u128, "uusd"),
+    );
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        ContractError::ZapInEmptyPool {}
+    );
+}
+
+#[test]
+fn zap_out() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let total_share = Uint128::from(1_000_000_000u128);
+    let mut deps = zap_in_deps(pool_amount, pool_amount, total_share);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let sender = deps.api.addr_make("addr0000");
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let zap_out_msg = |target_asset: AssetInfo, min_return: u128| ExecuteMsg::ZapOut {
+        amount: Uint128::from(100_000_000u128),
+        target_asset,
+        min_return: Some(Uint128::from(min_return)),
+        deadline: None,
+    };
+
+    // 10% of the pool is withdrawn, the 100_000_000 token are sold into the
+    // remaining 900_000_000 / 900_000_000 for 89_729_999 uusd after commission
+    let info = message_info(&sender, &coins(100_000_000u128, lp_denom.clone()));
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            zap_out_msg(uusd.clone(), 189_730_000)
+        )
+        .unwrap_err(),
+        ContractError::MinAmountAssertion {
+            min_asset: "189730000uusd".to_string(),
+            asset: "189729999uusd".to_string(),
+        }
+    );
+
+    let info = message_info(&sender, &coins(100_000_000u128, lp_denom.clone()));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        zap_out_msg(uusd.clone(), 189_729_999),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: coins(189_729_999u128, "uusd"),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("burnaddr0000").to_string(),
+                msg: to_json_binary(&choice::send_to_auction::ExecuteMsg::SendNative {
+                    asset: Asset {
+                        info: uusd.clone(),
+                        amount: Uint128::from(45_000u128),
+                    },
+                })
+                .unwrap(),
+                funds: coins(45_000u128, "uusd"),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: deps.api.addr_make("feeaddr0000").to_string(),
+                amount: coins(45_000u128, "uusd"),
+            }),
+            SubMsg::new(create_burn_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                Coin {
+                    denom: lp_denom.clone(),
+                    amount: Uint128::from(100_000_000u128),
+                },
+            )),
+        ]
+    );
+
+    // same attributes as withdraw_liquidity, followed by the swap
+    let token = deps.api.addr_make("asset0000").to_string();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_liquidity"),
+            attr("sender", sender.as_str()),
+            attr("withdrawn_share", "100000000"),
+            attr("refund_assets", format!("189729999uusd, 0{}", token)),
+            attr("target_asset", "uusd"),
+            attr("swap_amount", "100000000"),
+            attr("return_amount", "89729999"),
+            attr("commission_amount", "270001"),
+            attr("burn_amount", "45000"),
+            attr("fee_wallet_amount", "45000"),
+        ]
+    );
+
+    let other = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let info = message_info(&sender, &coins(100_000_000u128, lp_denom));
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, zap_out_msg(other, 0)).unwrap_err(),
+        ContractError::AssetMismatch {}
+    );
+}
+
+#[test]
+fn weighted_compute_swap() {
+    let pool = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000_000u128);
+    let weights = [Decimal::percent(80), Decimal::percent(20)];
+
+    // the heavier asset is worth four times the lighter one at equal balances
+    let (return_amount, spread_amount, commission_amount) = weighted::compute_swap(
+        pool,
+        pool,
+        offer_amount,
+        6,
+        6,
+        weights[0],
+        weights[1],
+        commission_rate(30),
+    )
+    .unwrap();
+    assert_eq!(return_amount, Uint128::from(3_978_049_905u128));
+    assert_eq!(spread_amount, Uint128::from(9_980_035u128));
+    assert_eq!(commission_amount, Uint128::from(11_970_060u128));
+
+    let (return_amount_reverse, _, _) = weighted::compute_swap(
+        pool,
+        pool,
+        offer_amount,
+        6,
+        6,
+        weights[1],
+        weights[0],
+        commission_rate(30),
+    )
+    .unwrap();
+    assert_eq!(return_amount_reverse, Uint128::from(249_094_335u128));
+
+    // reverse simulation of the same trade asks for the original offer, rounded up
+    let (offer_amount_reverse, _, commission_amount_reverse) = weighted::compute_offer_amount(
+        pool,
+        pool,
+        return_amount,
+        6,
+        6,
+        weights[0],
+        weights[1],
+        commission_rate(30),
+    )
+    .unwrap();
+    assert_eq!(offer_amount_reverse, offer_amount + Uint128::one());
+    assert_eq!(commission_amount_reverse, commission_amount);
+
+    // a 50/50 pool prices like the constant product curve
+    let half = Decimal::percent(50);
+    assert_eq!(
+        weighted::compute_swap(
+            pool,
+            pool,
+            offer_amount,
+            6,
+            6,
+            half,
+            half,
+            commission_rate(30)
+        )
+        .unwrap(),
+        compute_swap(pool, pool, offer_amount, 6, 6, commission_rate(30)).unwrap()
+    );
+
+    // decimals are scaled before the weighted math
+    let pool_18 = Uint128::from(1_000_000_000_000_000_000_000u128);
+    let (return_amount_18, _, _) = weighted::compute_swap(
+        pool_18,
+        pool,
+        Uint128::from(1_000_000_000_000_000_000u128),
+        18,
+        6,
+        weights[0],
+        weights[1],
+        commission_rate(30),
+    )
+    .unwrap();
+    assert_eq!(return_amount_18, return_amount);
+
+    // swaps that would move the pool too far are rejected
+    assert_eq!(
+        weighted::compute_swap(
+            pool,
+            pool,
+            pool,
+            6,
+            6,
+            weights[0],
+            weights[1],
+            commission_rate(30)
+        )
+        .unwrap_err(),
+        StdError::generic_err("offer amount exceeds the weighted pool limit")
+    );
+    assert_eq!(
+        weighted::compute_offer_amount(
+            pool,
+            pool,
+            pool / Uint128::from(2u8),
+            6,
+            6,
+            weights[0],
+            weights[1],
+            commission_rate(30)
+        )
+        .unwrap_err(),
+        StdError::generic_err("ask amount exceeds the weighted pool limit")
+    );
+
+    // the commission grows the invariant
+    let ratio = weighted::invariant_ratio(
+        [pool, pool],
+        [pool + offer_amount, pool - return_amount],
+        weights,
+    )
+    .unwrap();
+    assert!(ratio > Decimal256::one());
+}
+
+#[test]
+fn weighted_pow() {
+    let pow = |base: &str, exponent: &str| {
+        weighted::pow(
+            Decimal256::from_str(base).unwrap(),
+            Decimal256::from_str(exponent).unwrap(),
+        )
+        .unwrap()
+        .to_string()
+    };
+
+    assert_eq!(pow("0.99999999", "4"), "0.999999960000000599");
+    assert_eq!(pow("2", "0.5"), "1.414213562373095048");
+    assert_eq!(pow("0.9", "1.5"), "0.853814968245462424");
+    assert_eq!(pow("1000000", "0.2"), "15.848931924611134592");
+    assert_eq!(pow("0.000001", "0.3"), "0.015848931924611135");
+    assert_eq!(pow("0", "0.5"), "0");
+    assert_eq!(pow("0", "0"), "1");
+}
+
+fn weighted_instantiate_msg(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+    weights: Option<[Decimal; 2]>,
+) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::Weighted,
+        amp: None,
+        weights,
+        launch_protection: None,
+        allowlist: None,
+    }
+}
+
+#[test]
+fn weighted_instantiate_requires_weights() {
+    let mut deps = mock_dependencies(&[]);
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let invalid_weights =
+        StdError::generic_err("weighted pairs require two weights of at least 0.01 that sum to 1");
+
+    for weights in [
+        None,
+        Some([Decimal::percent(80), Decimal::percent(30)]),
+        Some([Decimal::permille(5), Decimal::permille(995)]),
+    ] {
+        let msg = weighted_instantiate_msg(&deps, weights);
+        assert_eq!(
+            instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+            invalid_weights
+        );
+    }
+
+    let mut msg = weighted_instantiate_msg(&deps, Some([Decimal::percent(50); 2]));
+    msg.pair_type = PairType::ConstantProduct;
+    assert_eq!(
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+        StdError::generic_err("weights are only supported by weighted pairs")
+    );
+
+    let mut msg = weighted_instantiate_msg(&deps, Some([Decimal::percent(50); 2]));
+    msg.amp = Some(100);
+    assert_eq!(
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+        StdError::generic_err("amp is only supported by stable swap pairs")
+    );
+
+    assert_eq!(
+        query_weights(deps.as_ref()).unwrap_err(),
+        ContractError::NotWeighted {}
+    );
+
+    let weights = [Decimal::percent(80), Decimal::percent(20)];
+    let msg = weighted_instantiate_msg(&deps, Some(weights));
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        query_weights(deps.as_ref()).unwrap(),
+        WeightsResponse { weights }
+    );
+}
+
+#[test]
+fn weighted_provide_initial_liquidity() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(4_000_000_000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        Uint128::zero(),
+    )]);
+
+    let msg = weighted_instantiate_msg(&deps, Some([Decimal::percent(80), Decimal::percent(20)]));
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(4_000_000_000u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(1_000_000_000u128),
+            },
+        ],
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(4_000_000_000u128, "uusd"),
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 4_000_000_000^0.8 * 1_000_000_000^0.2 minus the locked minimum liquidity
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(create_mint_tokens_msg(
+            deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(),
+            Coin {
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
+                amount: Uint128::from(3_031_432_133u128),
+            },
+            deps.api.addr_make("addr0000").to_string(),
+        ))
+    );
+}
+
+#[test]
+fn weighted_swap() {
+    let pool_amount = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + offer_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &pool_amount)],
+    )]);
+
+    let msg = weighted_instantiate_msg(&deps, Some([Decimal::percent(80), Decimal::percent(20)]));
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+    let msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        belief_price: Some(Decimal::from_ratio(1u128, 4u128)),
+        max_spread: Some(Decimal::percent(1)),
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(offer_amount.u128(), "uusd"),
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // same trade as in weighted_compute_swap
+    let expected_return_amount = Uint128::from(3_978_049_905u128);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("addr0000").to_string(),
+                amount: expected_return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        }))
+    );
+
+    // simulations against the pre-swap reserves agree
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let simulation_res = query_simulation(deps.as_ref(), mock_env(), offer_asset, None).unwrap();
+    assert_eq!(simulation_res.return_amount, expected_return_amount);
+
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        amount: expected_return_amount,
+    };
+    let reverse_simulation_res =
+        query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset, None).unwrap();
+    assert_eq!(
+        reverse_simulation_res.offer_amount,
+        offer_amount + Uint128::one()
+    );
+}
+
+#[test]
+fn pair_status() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    let token = deps.api.addr_make("asset0000");
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    deps.querier.with_balance(&[
+        (&user.to_string(), coins(100u128, lp_denom.clone())),
+        (&MOCK_CONTRACT_ADDR.to_string(), coins(110u128, "uusd")),
+    ]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(100u128))],
+    )]);
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::from(100u128))]);
+
+    let mut msg = stable_instantiate_msg(&deps, None);
+    msg.pair_type = PairType::ConstantProduct;
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    set_reserves(&mut deps, [100, 100]);
+
+    let status_msg = |status: PairStatus| ExecuteMsg::UpdateStatus { status };
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let provide_msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(10u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(10u128),
+            },
+        ],
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+
+    // only the factory sets the status
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        status_msg(PairStatus::SwapsPaused),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        status_msg(PairStatus::SwapsPaused),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "update_status"), attr("status", "swaps_paused")]
+    );
+
+    // swaps, including token swaps, are rejected while liquidity can still be provided
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        swap_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::SwapsPaused {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&token, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: user.to_string(),
+            amount: Uint128::from(10u128),
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                trader: None,
+            })
+            .unwrap(),
+        }),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::SwapsPaused {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        provide_msg.clone(),
+    )
+    .unwrap();
+
+    // deprecated pairs only accept withdrawals
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        status_msg(PairStatus::WithdrawOnly),
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        provide_msg,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::WithdrawOnly {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        swap_msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::WithdrawOnly {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        ExecuteMsg::ZapIn {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(10u128),
+            },
+            min_lp: None,
+            receiver: None,
+            deadline: None,
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::WithdrawOnly {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(100u128, lp_denom)),
+        ExecuteMsg::WithdrawLiquidity {
+            amount: Uint128::from(100u128),
+            min_assets: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.status, PairStatus::WithdrawOnly);
+
+    // the mocked lp supply does not follow the withdrawal, refill the pool for the swap below
+    set_reserves(&mut deps, [100, 100]);
+
+    // reactivating the pair resumes swaps
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        status_msg(PairStatus::Active),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10u128, "uusd")),
+        swap_msg,
+    )
+    .unwrap();
+}
+
+#[test]
+fn dynamic_fee() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let moved_pool_amount = Uint128::from(1_100_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let offer_asset = Asset {
+        info: uusd.clone(),
+        amount: offer_amount,
+    };
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let dynamic_fee = DynamicFee {
+        min_fee_bps: 10,
+        max_fee_bps: 100,
+        volatility_multiplier: Decimal::percent(50),
+        decay_period: 600,
+    };
+
+    // only the factory sets a valid dynamic fee
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        ExecuteMsg::UpdateDynamicFee {
+            dynamic_fee: Some(dynamic_fee.clone()),
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    for invalid in [
+        DynamicFee {
+            min_fee_bps: 0,
+            ..dynamic_fee.clone()
+        },
+        DynamicFee {
+            min_fee_bps: 200,
+            ..dynamic_fee.clone()
+        },
+        DynamicFee {
+            max_fee_bps: 1_001,
+            ..dynamic_fee.clone()
+        },
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&factory, &[]),
+            ExecuteMsg::UpdateDynamicFee {
+                dynamic_fee: Some(invalid),
+            },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InvalidDynamicFee { max_fee_bps: 1_000 }
+        );
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateDynamicFee {
+            dynamic_fee: Some(DynamicFee {
+                decay_period: 0,
+                ..dynamic_fee.clone()
+            }),
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidDecayPeriod {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateDynamicFee {
+            dynamic_fee: Some(dynamic_fee.clone()),
+        },
+    )
+    .unwrap();
+
+    // a calm market pays the floor
+    let res = query_simulation(deps.as_ref(), mock_env(), offer_asset.clone(), None).unwrap();
+    assert_eq!(res.commission_bps, 10);
+
+    let set_uusd_reserve = |deps: &mut OwnedDeps<_, _, _, _>, amount: Uint128| {
+        set_reserves(deps, [amount.u128(), pool_amount.u128()])
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(offer_amount.u128(), "uusd")),
+        swap_msg.clone(),
+    )
+    .unwrap();
+
+    // the price moved by ~9% since the last swap, the fee is capped at the ceiling
+    set_uusd_reserve(&mut deps, moved_pool_amount);
+    let simulation =
+        query_simulation(deps.as_ref(), mock_env(), offer_asset.clone(), None).unwrap();
+    assert_eq!(simulation.commission_bps, 100);
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+        None,
+    )
+    .unwrap();
+    assert_eq!(reverse_simulation.commission_bps, 100);
+
+    // the swap pays the quoted fee
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(offer_amount.u128(), "uusd")),
+        swap_msg,
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("return_amount", simulation.return_amount.to_string())));
+    assert!(res.attributes.contains(&attr(
+        "commission_amount",
+        simulation.commission_amount.to_string()
+    )));
+
+    // without further moves the volatility decays back to the floor
+    set_uusd_reserve(&mut deps, moved_pool_amount);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(300);
+    assert_eq!(
+        query_dynamic_fee(deps.as_ref(), env.clone()).unwrap(),
+        DynamicFeeResponse {
+            dynamic_fee: Some(dynamic_fee),
+            commission_bps: 55,
+        }
+    );
+
+    env.block.time = env.block.time.plus_seconds(300);
+    let res = query_simulation(deps.as_ref(), env, offer_asset.clone(), None).unwrap();
+    assert_eq!(res.commission_bps, 10);
+
+    // removing the dynamic fee restores the fee tier
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateDynamicFee { dynamic_fee: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_dynamic_fee"),
+            attr("dynamic_fee", "none")
+        ]
+    );
+
+    let res = query_simulation(deps.as_ref(), mock_env(), offer_asset, None).unwrap();
+    assert_eq!(res.commission_bps, 30);
+}
+
+#[test]
+fn dynamic_fee_stableswap() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+    let msg = stable_instantiate_msg(&deps, Some(100));
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    let dynamic_fee = DynamicFee {
+        min_fee_bps: 5,
+        max_fee_bps: 100,
+        volatility_multiplier: Decimal::percent(50),
+        decay_period: 600,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateDynamicFee {
+            dynamic_fee: Some(dynamic_fee.clone()),
+        },
+    )
+    .unwrap();
+
+    let pool_amount = 1_000_000_000_000u128;
+    dynamic_fee::record_swap(
+        deps.as_mut().storage,
+        mock_env().block.time.seconds(),
+        [Uint128::from(pool_amount); 2],
+        [6, 6],
+        5,
+    )
+    .unwrap();
+
+    // a 10% shift of the reserves moves the stable curve's price by less than
+    // 0.1%, where the reserve ratio would put the fee at its ceiling
+    set_reserves(&mut deps, [pool_amount * 11 / 10, pool_amount]);
+    assert_eq!(
+        query_dynamic_fee(deps.as_ref(), mock_env()).unwrap(),
+        DynamicFeeResponse {
+            dynamic_fee: Some(dynamic_fee),
+            commission_bps: 9,
+        }
+    );
+}
+
+#[test]
+fn swap_exact_out() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let ask_amount = Uint128::from(1_000_000u128);
+    let max_offer_amount = Uint128::from(1_100_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    let token = deps.api.addr_make("asset0000");
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: token.to_string(),
+    };
+    let swap_msg = |ask_info: &AssetInfo, offer_info: &AssetInfo, max_offer_amount: Uint128| {
+        ExecuteMsg::SwapExactOut {
+            ask_asset: Asset {
+                info: ask_info.clone(),
+                amount: ask_amount,
+            },
+            max_offer: Asset {
+                info: offer_info.clone(),
+                amount: max_offer_amount,
+            },
+            to: None,
+            deadline: None,
+        }
+    };
+
+    let quote = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: token_info.clone(),
+            amount: ask_amount,
+        },
+        None,
+    )
+    .unwrap();
+
+    // a native offer sends the maximum and gets the surplus back
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(max_offer_amount.u128(), "uusd")),
+        swap_msg(&token_info, &uusd, max_offer_amount),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0..2],
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: coins((max_offer_amount - quote.offer_amount).u128(), "uusd"),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: user.to_string(),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("offer_amount", quote.offer_amount.to_string())));
+    assert!(res.attributes.contains(&attr(
+        "commission_amount",
+        quote.commission_amount.to_string()
+    )));
+
+    let too_low = quote.offer_amount - Uint128::one();
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(too_low.u128(), "uusd")),
+        swap_msg(&token_info, &uusd, too_low),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::MaxOfferAssertion {
+            max_offer: too_low.to_string(),
+            offer: quote.offer_amount.to_string(),
+        }
+    );
+
+    // a token offer only transfers the required amount from the allowance
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let quote = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: uusd.clone(),
+            amount: ask_amount,
+        },
+        None,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        swap_msg(&uusd, &token_info, max_offer_amount),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0..2],
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: user.to_string(),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: quote.offer_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: coins(ask_amount.u128(), "uusd"),
+            })),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(max_offer_amount.u128(), "uusd")),
+        swap_msg(&uusd, &uusd, max_offer_amount),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AssetMismatch {});
+}
+
+#[test]
+fn compute_offer_amount_round_trip_with_diff_decimal() {
+    // 1,000,000 INJ (18 decimals) against 20,000,000 USDT (6 decimals)
+    let pool_inj = Uint128::from(1_000_000_000_000_000_000_000_000u128);
+    let pool_usdt = Uint128::from(20_000_000_000_000u128);
+
+    for commission_bps in [5u16, 30, 100] {
+        let rate = commission_rate(commission_bps);
+        for ask_amount in [1u128, 999, 1_000_000, 123_456_789, 100_000_000_000] {
+            // buying USDT with INJ
+            let ask_amount = Uint128::from(ask_amount);
+            let (offer_amount, _, _) =
+                compute_offer_amount(pool_inj, pool_usdt, ask_amount, 18, 6, rate).unwrap();
+            let (return_amount, _, _) =
+                compute_swap(pool_inj, pool_usdt, offer_amount, 18, 6, rate).unwrap();
+            assert!(return_amount >= ask_amount);
+
+            // buying INJ with USDT
+            let ask_amount = ask_amount * Uint128::from(1_000_000_000_000u128);
+            let (offer_amount, _, _) =
+                compute_offer_amount(pool_usdt, pool_inj, ask_amount, 6, 18, rate).unwrap();
+            let (return_amount, _, _) =
+                compute_swap(pool_usdt, pool_inj, offer_amount, 6, 18, rate).unwrap();
+            assert!(return_amount >= ask_amount);
+            // one unit less of the coarser offer asset falls short
+            let (return_amount, _, _) = compute_swap(
+                pool_usdt,
+                pool_inj,
+                offer_amount - Uint128::one(),
+                6,
+                18,
+                rate,
+            )
+            .unwrap();
+            assert!(return_amount < ask_amount);
+        }
+
+        // the quote for a swap's return stays within rounding of the swap
+        // itself
+        for offer_amount in [1_000_000u128, 777_777_777, 5_000_000_000_000] {
+            let offer_amount = Uint128::from(offer_amount);
+            let (return_amount, spread_amount, commission_amount) =
+                compute_swap(pool_usdt, pool_inj, offer_amount, 6, 18, rate).unwrap();
+            let (reverse_offer_amount, reverse_spread_amount, reverse_commission_amount) =
+                compute_offer_amount(pool_usdt, pool_inj, return_amount, 6, 18, rate).unwrap();
+            assert!(reverse_offer_amount <= offer_amount + Uint128::one());
+            assert!(commission_amount.abs_diff(reverse_commission_amount) <= Uint128::from(2u8));
+            assert!(
+                spread_amount.abs_diff(reverse_spread_amount)
+                    <= Uint128::from(1_000_000_000_000u128)
+            );
+        }
+    }
+
+    // asking for the whole pool fails instead of panicking
+    assert!(
+        compute_offer_amount(pool_inj, pool_usdt, pool_usdt, 18, 6, commission_rate(30)).is_err()
+    );
+}
+
+#[test]
+fn trading_stats() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    let token = deps.api.addr_make("asset0000");
+
+    let amount = |res: &Response<InjectiveMsgWrapper>, key: &str| {
+        let attribute = res.attributes.iter().find(|attr| attr.key == key).unwrap();
+        Uint128::from_str(&attribute.value).unwrap()
+    };
+
+    // uusd -> token in the first epoch
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        coins((pool_amount + offer_amount).u128(), "uusd"),
+    )]);
+    let buy = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(offer_amount.u128(), "uusd")),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            trader: None,
+        },
+    )
+    .unwrap();
+
+    // token -> uusd a day later
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        coins(pool_amount.u128(), "uusd"),
+    )]);
+    deps.querier.with_token_balances(&[(
+        &token.to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &(pool_amount + offer_amount),
+        )],
+    )]);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(STATS_EPOCH_SECONDS);
+    let sell = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&token, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: user.to_string(),
+            amount: offer_amount,
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                trader: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+
+    let buy_stats = TradingStats {
+        swap_count: 1,
+        volume: [offer_amount, amount(&buy, "return_amount")],
+        commission_amount: [Uint128::zero(), amount(&buy, "commission_amount")],
+        burn_amount: [Uint128::zero(), amount(&buy, "burn_amount")],
+        fee_wallet_amount: [Uint128::zero(), amount(&buy, "fee_wallet_amount")],
+    };
+    let sell_stats = TradingStats {
+        swap_count: 1,
+        volume: [amount(&sell, "return_amount"), offer_amount],
+        commission_amount: [amount(&sell, "commission_amount"), Uint128::zero()],
+        burn_amount: [amount(&sell, "burn_amount"), Uint128::zero()],
+        fee_wallet_amount: [amount(&sell, "fee_wallet_amount"), Uint128::zero()],
+    };
+    assert!(!buy_stats.burn_amount[1].is_zero());
+    assert!(!sell_stats.fee_wallet_amount[0].is_zero());
+
+    let first_epoch = mock_env().block.time.seconds() / STATS_EPOCH_SECONDS;
+    let res = query_stats(deps.as_ref(), None, None).unwrap();
+    assert_eq!(
+        res.asset_infos,
+        [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: token.to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        res.total,
+        TradingStats {
+            swap_count: 2,
+            volume: [
+                buy_stats.volume[0] + sell_stats.volume[0],
+                buy_stats.volume[1] + sell_stats.volume[1],
+            ],
+            commission_amount: [
+                sell_stats.commission_amount[0],
+                buy_stats.commission_amount[1]
+            ],
+            burn_amount: [sell_stats.burn_amount[0], buy_stats.burn_amount[1]],
+            fee_wallet_amount: [
+                sell_stats.fee_wallet_amount[0],
+                buy_stats.fee_wallet_amount[1]
+            ],
+        }
+    );
+    assert_eq!(
+        res.epochs,
+        vec![
+            EpochStats {
+                epoch: first_epoch,
+                stats: buy_stats,
+            },
+            EpochStats {
+                epoch: first_epoch + 1,
+                stats: sell_stats.clone(),
+            },
+        ]
+    );
+
+    // epochs are paginated, the totals are not
+    let res = query_stats(deps.as_ref(), Some(first_epoch), None).unwrap();
+    assert_eq!(res.total.swap_count, 2);
+    assert_eq!(
+        res.epochs,
+        vec![EpochStats {
+            epoch: first_epoch + 1,
+            stats: sell_stats,
+        }]
+    );
+    let res = query_stats(deps.as_ref(), None, Some(0)).unwrap();
+    assert!(res.epochs.is_empty());
+}
+
+#[test]
+fn reserves_skim_sync_donate() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    let receiver = deps.api.addr_make("receiver0000");
+    let token = deps.api.addr_make("asset0000");
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, pool_amount)]);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: token.to_string(),
+    };
+    let offer_asset = Asset {
+        info: uusd.clone(),
+        amount: Uint128::from(1_000_000u128),
+    };
+    let set_balances =
+        |deps: &mut OwnedDeps<_, _, WasmMockQuerier, _>, uusd: u128, amount: u128| {
+            deps.querier
+                .with_balance(&[(&MOCK_CONTRACT_ADDR.to_string(), coins(uusd, "uusd"))]);
+            deps.querier.with_token_balances(&[(
+                &token.to_string(),
+                &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(amount))],
+            )]);
+        };
+    let reserves = |deps: &OwnedDeps<_, _, WasmMockQuerier, _>| {
+        let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+        [res.assets[0].amount.u128(), res.assets[1].amount.u128()]
+    };
+
+    // tokens sent to the pair outside of its messages do not move the price
+    let simulation =
+        query_simulation(deps.as_ref(), mock_env(), offer_asset.clone(), None).unwrap();
+    set_balances(
+        &mut deps,
+        pool_amount.u128() + 500,
+        pool_amount.u128() + 300,
+    );
+    assert_eq!(reserves(&deps), [pool_amount.u128(), pool_amount.u128()]);
+    assert_eq!(
+        query_simulation(deps.as_ref(), mock_env(), offer_asset, None).unwrap(),
+        simulation
+    );
+
+    // anyone can skim the surplus
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        ExecuteMsg::Skim {
+            to: Some(receiver.to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: receiver.to_string(),
+                amount: coins(500u128, "uusd"),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: receiver.to_string(),
+                    amount: Uint128::from(300u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "skim"),
+            attr("sender", user.as_str()),
+            attr("receiver", receiver.as_str()),
+            attr("assets", format!("500uusd, 300{}", token)),
+        ]
+    );
+
+    // only the factory adopts the balances as reserves
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        ExecuteMsg::Sync {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr("reserves", format!("1000000500uusd, 1000000300{}", token)),
+        ]
+    );
+    assert_eq!(
+        reserves(&deps),
+        [pool_amount.u128() + 500, pool_amount.u128() + 300]
+    );
+
+    // donations need someone to own them
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::zero())]);
+    let donate_msg = |asset: AssetInfo| ExecuteMsg::Donate {
+        asset: Asset {
+            info: asset,
+            amount: Uint128::from(1_000u128),
+        },
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_000u128, "uusd")),
+        donate_msg(uusd.clone()),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::DonateEmptyPool {});
+
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, pool_amount)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        donate_msg(uusd.clone()),
+    );
+    match res.unwrap_err() {
+        ContractError::Std(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Native token balance mismatch between the argument and the transferred"
+        ),
+        _ => panic!("Must return generic error"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_000u128, "uluna")),
+        donate_msg(AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        }),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AssetMismatch {});
+
+    // tokens are donated through the cw20 hook
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        donate_msg(token_info),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_000u128, "uusd")),
+        donate_msg(uusd),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "donate"),
+            attr("sender", user.as_str()),
+            attr("asset", "1000uusd"),
+        ]
+    );
+
+    let donate_hook = |amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: user.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_json_binary(&Cw20HookMsg::Donate {}).unwrap(),
+        })
+    };
+    let other_token = deps.api.addr_make("other0000");
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&other_token, &[]),
+        donate_hook(2_000),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&token, &[]),
+        donate_hook(2_000),
+    )
+    .unwrap();
+    assert_eq!(
+        reserves(&deps),
+        [pool_amount.u128() + 1_500, pool_amount.u128() + 2_300]
+    );
+
+    // pairs migrated from before reserve accounting adopt their balances
+    RESERVES.remove(deps.as_mut().storage);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { factory: None }).unwrap();
+    assert_eq!(
+        reserves(&deps),
+        [pool_amount.u128() + 500, pool_amount.u128() + 300]
+    );
+}
+
+#[test]
+fn spot_price_and_depth_with_diff_decimal() {
+    // 1,000,000 INJ (18 decimals) against 20,000,000 USDT (6 decimals)
+    let pool_inj = Uint128::from(1_000_000_000_000_000_000_000_000u128);
+    let pool_usdt = Uint128::from(20_000_000_000_000u128);
+    let price_impact = Decimal256::percent(1);
+    let within = |price: Decimal256, expected: Decimal256| {
+        price.abs_diff(expected) <= expected * Decimal256::from_str("0.000001").unwrap()
+    };
+
+    // constant product, the marginal price is the reserve ratio
+    let price = compute_spot_price(pool_inj, pool_usdt, 18, 6);
+    assert_eq!(price, Decimal256::from_ratio(20u8, 1u8));
+    assert_eq!(
+        compute_spot_price(pool_usdt, pool_inj, 6, 18),
+        Decimal256::from_ratio(1u8, 20u8)
+    );
+
+    let offer_amount = compute_depth(pool_inj, price_impact).unwrap();
+    let moved_price = compute_spot_price(
+        pool_inj + offer_amount,
+        pool_usdt.multiply_ratio(pool_inj, pool_inj + offer_amount),
+        18,
+        6,
+    );
+    assert!(within(moved_price, price * Decimal256::percent(99)));
+
+    // stable swap, balanced pools trade 1:1 at the margin
+    let pool_usdc = Uint128::from(1_000_000_000_000u128);
+    let pool_usdt_18 = Uint128::from(1_000_000_000_000_000_000_000_000u128);
+    let price = stableswap::spot_price(100, pool_usdc, pool_usdt_18, 6, 18).unwrap();
+    assert!(within(price, Decimal256::one()));
+
+    let offer_amount =
+        stableswap::compute_depth(100, pool_usdc, pool_usdt_18, 6, 18, price_impact).unwrap();
+    let (return_amount, _, _) = stableswap::compute_swap(
+        100,
+        pool_usdc,
+        pool_usdt_18,
+        offer_amount,
+        6,
+        18,
+        Decimal256::zero(),
+    )
+    .unwrap();
+    let moved_price = stableswap::spot_price(
+        100,
+        pool_usdc + offer_amount,
+        pool_usdt_18 - return_amount,
+        6,
+        18,
+    )
+    .unwrap();
+    assert!(within(moved_price, price * Decimal256::percent(99)));
+    // the flat curve absorbs far more than a constant product pool of the same size
+    assert!(offer_amount > compute_depth(pool_usdc, price_impact).unwrap() * Uint128::from(10u8));
+
+    // weighted 80/20, the heavy asset is priced at 4 times the reserve ratio
+    let weights = [Decimal::percent(80), Decimal::percent(20)];
+    let price = weighted::spot_price(pool_inj, pool_usdt, 18, 6, weights[0], weights[1]);
+    assert_eq!(price, Decimal256::from_ratio(80u8, 1u8));
+
+    let offer_amount =
+        weighted::compute_depth(pool_inj, weights[0], weights[1], price_impact).unwrap();
+    let (return_amount, _, _) = weighted::compute_swap(
+        pool_inj,
+        pool_usdt,
+        offer_amount,
+        18,
+        6,
+        weights[0],
+        weights[1],
+        Decimal256::zero(),
+    )
+    .unwrap();
+    let moved_price = weighted::spot_price(
+        pool_inj + offer_amount,
+        pool_usdt - return_amount,
+        18,
+        6,
+        weights[0],
+        weights[1],
+    );
+    assert!(within(moved_price, price * Decimal256::percent(99)));
+}
+
+#[test]
+fn query_spot_price_and_depth() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128() * 4]);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let token = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+
+    assert_eq!(
+        query_spot_price(deps.as_ref(), mock_env(), uusd.clone()).unwrap(),
+        SpotPriceResponse {
+            asset: uusd.clone(),
+            price: Decimal256::from_ratio(4u8, 1u8),
+        }
+    );
+    assert_eq!(
+        query_spot_price(deps.as_ref(), mock_env(), token.clone())
+            .unwrap()
+            .price,
+        Decimal256::from_ratio(1u8, 4u8)
+    );
+    assert_eq!(
+        query_spot_price(deps.as_ref(), mock_env(), uluna.clone()).unwrap_err(),
+        ContractError::AssetMismatch {}
+    );
+
+    // 1_000_000_000 * (1 / sqrt(0.99) - 1) and 1_000_000_000 * (1 / sqrt(0.75) - 1), rounded up
+    let res = query_depth(
+        deps.as_ref(),
+        mock_env(),
+        uusd.clone(),
+        vec![Decimal::percent(1), Decimal::percent(25)],
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        DepthResponse {
+            asset: uusd.clone(),
+            levels: vec![
+                DepthLevel {
+                    price_impact: Decimal::percent(1),
+                    offer_amount: Uint128::from(5_037_816u128),
+                },
+                DepthLevel {
+                    price_impact: Decimal::percent(25),
+                    offer_amount: Uint128::from(154_700_539u128),
+                },
+            ],
+        }
+    );
+
+    for invalid in [Decimal::zero(), Decimal::one()] {
+        assert_eq!(
+            query_depth(deps.as_ref(), mock_env(), uusd.clone(), vec![invalid]).unwrap_err(),
+            ContractError::InvalidPriceImpact {}
+        );
+    }
+    assert_eq!(
+        query_depth(
+            deps.as_ref(),
+            mock_env(),
+            uusd.clone(),
+            vec![Decimal::percent(1); 11]
+        )
+        .unwrap_err(),
+        ContractError::TooManyPriceImpacts { max: 10 }
+    );
+    assert_eq!(
+        query_depth(deps.as_ref(), mock_env(), uluna, vec![Decimal::percent(1)]).unwrap_err(),
+        ContractError::AssetMismatch {}
+    );
+
+    set_reserves(&mut deps, [0, 0]);
+    assert_eq!(
+        query_spot_price(deps.as_ref(), mock_env(), uusd.clone()).unwrap_err(),
+        ContractError::EmptyPool {}
+    );
+    assert_eq!(
+        query_depth(deps.as_ref(), mock_env(), uusd, vec![Decimal::percent(1)]).unwrap_err(),
+        ContractError::EmptyPool {}
+    );
+}
+
+#[test]
+fn simulate_provide_and_withdraw() {
+    let mut deps = mock_dependencies(&[]);
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::zero())]);
+
+    let token = deps.api.addr_make("asset0000").to_string();
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: token.clone(),
+            },
+        ],
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let assets = |uusd: u128, token_amount: u128| {
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(uusd),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: token.clone(),
+                },
+                amount: Uint128::from(token_amount),
+            },
+        ]
+    };
+    let provide =
+        |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         uusd: u128,
+         token_amount: u128,
+         slippage_tolerance: Option<Decimal>| {
+            let msg = ExecuteMsg::ProvideLiquidity {
+                assets: assets(uusd, token_amount),
+                receiver: None,
+                deadline: None,
+                slippage_tolerance,
+            };
+            let info = message_info(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(uusd),
+                }],
+            );
+            execute(deps.as_mut(), mock_env(), info, msg)
+        };
+
+    // the first provision locks the minimum liquidity like provide_liquidity
+    assert_eq!(
+        query_simulate_provide(deps.as_ref(), assets(1, 1)).unwrap_err(),
+        provide(&mut deps, 1, 1, None).unwrap_err()
+    );
+
+    let simulated = query_simulate_provide(deps.as_ref(), assets(1_100, 1_100)).unwrap();
+    assert_eq!(
+        simulated,
+        SimulateProvideResponse {
+            share: Uint128::from(100u128),
+            refund_assets: assets(0, 0),
+            slippage: Decimal::zero(),
+        }
+    );
+    let res = provide(&mut deps, 1_100, 1_100, None).unwrap();
+    assert_eq!(
+        res.attributes[4],
+        attr("share", simulated.share.to_string())
+    );
+
+    // the pool takes 100 uusd for 100 of the token and refunds the other half
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::from(1_100u128))]);
+    let simulated = query_simulate_provide(deps.as_ref(), assets(200, 100)).unwrap();
+    assert_eq!(
+        simulated,
+        SimulateProvideResponse {
+            share: Uint128::from(100u128),
+            refund_assets: assets(100, 0),
+            slippage: Decimal::percent(50),
+        }
+    );
+    assert_eq!(
+        provide(&mut deps, 200, 100, Some(Decimal::percent(49))).unwrap_err(),
+        ContractError::MaxSlippageAssertion {}
+    );
+    let res = provide(&mut deps, 200, 100, Some(simulated.slippage)).unwrap();
+    assert_eq!(
+        res.attributes[4],
+        attr("share", simulated.share.to_string())
+    );
+    assert_eq!(
+        res.attributes[5],
+        attr(
+            "refund_assets",
+            format!(
+                "{}, {}",
+                simulated.refund_assets[0], simulated.refund_assets[1]
+            )
+        )
+    );
+
+    let mut wrong_assets = assets(100, 100);
+    wrong_assets[0].info = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    assert_eq!(
+        query_simulate_provide(deps.as_ref(), wrong_assets).unwrap_err(),
+        ContractError::AssetMismatch {}
+    );
+
+    // reserves are 1_200 of each asset for 1_200 lp
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::from(1_200u128))]);
+    let simulated = query_simulate_withdraw(deps.as_ref(), Uint128::from(300u128)).unwrap();
+    assert_eq!(
+        simulated,
+        SimulateWithdrawResponse {
+            refund_assets: assets(300, 300),
+        }
+    );
+
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(300u128),
+        min_assets: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: lp_denom.clone(),
+            amount: Uint128::from(300u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "refund_assets",
+            format!(
+                "{}, {}",
+                simulated.refund_assets[0], simulated.refund_assets[1]
+            )
+        )
+    );
+
+    // more than the lp supply cannot be withdrawn
+    assert_eq!(
+        query_simulate_withdraw(deps.as_ref(), Uint128::from(1_201u128)).unwrap_err(),
+        ContractError::Std(StdError::generic_err(
+            "withdraw amount exceeds the lp supply"
+        ))
+    );
+
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::zero())]);
+    assert_eq!(
+        query_simulate_withdraw(deps.as_ref(), Uint128::from(1u128)).unwrap_err(),
+        ContractError::EmptyPool {}
+    );
+}
+
+#[test]
+fn provide_liquidity_with_deposit() {
+    let pool_amount = Uint128::from(1_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    let token = deps.api.addr_make("asset0000");
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, pool_amount)]);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: token.to_string(),
+    };
+    let pending_deposit = |deps: &OwnedDeps<_, _, WasmMockQuerier, _>| {
+        let res = query_pending_deposit(deps.as_ref(), user.to_string()).unwrap();
+        [res.assets[0].amount.u128(), res.assets[1].amount.u128()]
+    };
+    let send = |token: &Addr, amount: u128, msg: &Cw20HookMsg| {
+        (
+            message_info(token, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user.to_string(),
+                amount: Uint128::from(amount),
+                msg: to_json_binary(msg).unwrap(),
+            }),
+        )
+    };
+    let provide = Cw20HookMsg::ProvideLiquidity {
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &[]),
+            ExecuteMsg::Deposit {},
+        )
+        .unwrap_err(),
+        ContractError::InvalidZeroAmount {}
+    );
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &coins(300, "uluna")),
+            ExecuteMsg::Deposit {},
+        )
+        .unwrap_err(),
+        ContractError::AssetMismatch {}
+    );
+
+    // the native leg is staged outside the reserves
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(300, "uusd")),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit"),
+            attr("sender", user.as_str()),
+            attr("pending_deposit", format!("300uusd, 0{}", token)),
+        ]
+    );
+    assert_eq!(pending_deposit(&deps), [300, 0]);
+
+    deps.querier
+        .with_balance(&[(&MOCK_CONTRACT_ADDR.to_string(), coins(1_300, "uusd"))]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        ExecuteMsg::Skim { to: None },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    let (info, msg) = send(&deps.api.addr_make("other0000"), 200, &provide);
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+
+    // the sent token completes the provision, the unused native part is refunded
+    let (info, msg) = send(&token, 200, &provide);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: coins(100, "uusd"),
+            })),
+            SubMsg::new(create_mint_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                Coin {
+                    denom: lp_denom.clone(),
+                    amount: Uint128::from(200u128),
+                },
+                user.to_string(),
+            )),
+        ]
+    );
+    assert_eq!(
+        res.attributes[3],
+        attr("assets", format!("300uusd, 200{}", token))
+    );
+    assert_eq!(pending_deposit(&deps), [0, 0]);
+    assert_eq!(
+        RESERVES.load(&deps.storage).unwrap(),
+        [Uint128::from(1_200u128), Uint128::from(1_200u128)]
+    );
+
+    let (info, msg) = send(&token, 200, &provide);
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err(),
+        ContractError::NoPendingDeposit {}
+    );
+
+    // the unused part of the sent token is transferred back
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::from(1_200u128))]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(100, "uusd")),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    let (info, msg) = send(&token, 300, &provide);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(
+            Asset {
+                info: token_info,
+                amount: Uint128::from(200u128),
+            }
+            .into_msg(user.clone())
+            .unwrap()
+        )
+    );
+    assert_eq!(res.attributes[4], attr("share", "100"));
+
+    // a pending deposit can be taken back
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(50, "uusd")),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &[]),
+        ExecuteMsg::RefundDeposit {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(
+            Asset {
+                info: uusd,
+                amount: Uint128::from(50u128),
+            }
+            .into_msg(user.clone())
+            .unwrap()
+        )]
+    );
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &[]),
+            ExecuteMsg::RefundDeposit {},
+        )
+        .unwrap_err(),
+        ContractError::NoPendingDeposit {}
+    );
+}
+
+#[test]
+fn swap_hooks() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    let hooks = vec![
+        deps.api.addr_make("hook0000").to_string(),
+        deps.api.addr_make("hook0001").to_string(),
+    ];
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: Uint128::from(1_000_000u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let update_hooks = |hooks: Vec<String>| ExecuteMsg::UpdateSwapHooks { hooks };
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &[]),
+            update_hooks(hooks.clone()),
+        )
+        .unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&factory, &[]),
+            update_hooks(vec![hooks[0].clone(); 6]),
+        )
+        .unwrap_err(),
+        ContractError::TooManySwapHooks { max: 5 }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        update_hooks(hooks.clone()),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_swap_hooks"),
+            attr("hooks", hooks.join(",")),
+        ]
+    );
+    assert_eq!(
+        query_swap_hooks(deps.as_ref()).unwrap(),
+        SwapHooksResponse {
+            hooks: hooks.clone()
+        }
+    );
+
+    // every hook is notified after the swap, without blocking it on failure
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_000_000, "uusd")),
+        swap_msg.clone(),
+    )
+    .unwrap();
+    let amount = |index: usize| Uint128::from_str(&res.attributes[index].value).unwrap();
+    let hook_msg = to_json_binary(&SwapHookMsg::SwapHook {
+        sender: user.to_string(),
+        receiver: user.to_string(),
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: Uint128::from(1_000_000u128),
+        },
+        return_asset: Asset {
+            info: token_info.clone(),
+            amount: amount(6),
+        },
+        spread_amount: amount(7),
+        commission_amount: amount(8),
+        burn_amount: amount(9),
+        fee_wallet_amount: amount(10),
+    })
+    .unwrap();
+    let hook_messages: Vec<SubMsg<InjectiveMsgWrapper>> = res
+        .messages
+        .iter()
+        .filter(|msg| msg.reply_on == ReplyOn::Error)
+        .cloned()
+        .collect();
+    assert_eq!(
+        hook_messages,
+        hooks
+            .iter()
+            .map(|hook| SubMsg {
+                id: 2,
+                payload: Binary::from(hook.as_bytes()),
+                msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: hook.clone(),
+                    msg: hook_msg.clone(),
+                    funds: vec![],
+                }),
+                gas_limit: Some(300_000),
+                reply_on: ReplyOn::Error,
+            })
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(res.messages[res.messages.len() - 2..], hook_messages[..]);
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 2,
+            payload: Binary::from(hooks[1].as_bytes()),
+            gas_used: 300_000,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap_hook_failed"),
+            attr("hook", hooks[1].clone()),
+            attr("error", "out of gas"),
+        ]
+    );
+
+    // exact output swaps notify the hooks too
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_100_000, "uusd")),
+        ExecuteMsg::SwapExactOut {
+            ask_asset: Asset {
+                info: token_info,
+                amount: Uint128::from(1_000_000u128),
+            },
+            max_offer: Asset {
+                info: uusd,
+                amount: Uint128::from(1_100_000u128),
+            },
+            to: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .iter()
+            .filter(|msg| msg.reply_on == ReplyOn::Error)
+            .count(),
+        2
+    );
+
+    // an empty list removes the hooks
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        update_hooks(vec![]),
+    )
+    .unwrap();
+    assert!(query_swap_hooks(deps.as_ref()).unwrap().hooks.is_empty());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1_000_000, "uusd")),
+        swap_msg,
+    )
+    .unwrap();
+    assert!(res
+        .messages
+        .iter()
+        .all(|msg| msg.reply_on == ReplyOn::Never));
+}
+#[test]
+fn batch_auction() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let token_addr = deps.api.addr_make("asset0000");
+    let users: Vec<Addr> = (0..4)
+        .map(|i| deps.api.addr_make(&format!("addr000{}", i)))
+        .collect();
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: token_addr.to_string(),
+    };
+    let min_offer_amounts = [Uint128::from(100_000u128); 2];
+    let update_batch_auction = |interval_blocks: Option<u64>| ExecuteMsg::UpdateBatchAuction {
+        batch_auction: interval_blocks.map(|interval_blocks| BatchAuction {
+            interval_blocks,
+            min_offer_amounts,
+        }),
+    };
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&users[0], &[]),
+            update_batch_auction(Some(2)),
+        )
+        .unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&factory, &[]),
+            update_batch_auction(Some(0)),
+        )
+        .unwrap_err(),
+        ContractError::InvalidBatchAuction {}
+    );
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&factory, &[]),
+            ExecuteMsg::UpdateBatchAuction {
+                batch_auction: Some(BatchAuction {
+                    interval_blocks: 2,
+                    min_offer_amounts: [Uint128::from(100_000u128), Uint128::zero()],
+                }),
+            },
+        )
+        .unwrap_err(),
+        ContractError::InvalidBatchAuction {}
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        update_batch_auction(Some(2)),
+    )
+    .unwrap();
+
+    // swaps are queued, other trades are rejected
+    let env = mock_env();
+    let native_swap = |amount: u128, deadline: Option<u64>| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: Uint128::from(amount),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline,
+        trader: None,
+    };
+    let token_swap = |sender: &Addr, amount: u128, belief_price: Option<Decimal>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                belief_price,
+                max_spread: belief_price.map(|_| Decimal::percent(1)),
+                to: None,
+                deadline: None,
+                trader: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&users[0], &coins(3_000_000, "uusd")),
+        native_swap(3_000_000, None),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "queue_swap"),
+            attr("sender", users[0].as_str()),
+            attr("receiver", users[0].as_str()),
+            attr("offer_asset", "uusd"),
+            attr("offer_amount", "3000000"),
+            attr("order_id", "0"),
+            attr("settle_height", (env.block.height + 2).to_string()),
+        ]
+    );
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&token_addr, &[]),
+        token_swap(&users[1], 1_000_000, None),
+    )
+    .unwrap();
+    // asks for twice the pool price, refunded by its spread limit
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&token_addr, &[]),
+        token_swap(&users[2], 1_000_000, Some(Decimal::percent(50))),
+    )
+    .unwrap();
+    // refunded by its deadline
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&users[3], &coins(500_000, "uusd")),
+        native_swap(500_000, Some(env.block.time.seconds() + 5)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&users[0], &coins(1_000_000, "uusd")),
+            ExecuteMsg::ZapIn {
+                offer_asset: Asset {
+                    info: uusd.clone(),
+                    amount: Uint128::from(1_000_000u128),
+                },
+                min_lp: None,
+                receiver: None,
+                deadline: None,
+            },
+        )
+        .unwrap_err(),
+        ContractError::BatchAuctionActive {}
+    );
+
+    assert_eq!(
+        query_batch_auction(deps.as_ref()).unwrap(),
+        BatchAuctionResponse {
+            batch_auction: Some(BatchAuction {
+                interval_blocks: 2,
+                min_offer_amounts,
+            }),
+            settle_height: Some(env.block.height + 2),
+            order_count: 4,
+            offer_assets: [
+                Asset {
+                    info: uusd.clone(),
+                    amount: Uint128::from(3_500_000u128),
+                },
+                Asset {
+                    info: token_info.clone(),
+                    amount: Uint128::from(2_000_000u128),
+                },
+            ],
+        }
+    );
+    let orders = query_batch_orders(deps.as_ref(), Some(1), None)
+        .unwrap()
+        .orders;
+    assert_eq!(
+        orders.iter().map(|order| order.id).collect::<Vec<u64>>(),
+        vec![2, 3]
+    );
+    assert_eq!(orders[0].sender, users[2].to_string());
+    assert_eq!(orders[0].belief_price, Some(Decimal::percent(50)));
+
+    let mut settle_env = mock_env();
+    settle_env.block.height += 1;
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            settle_env.clone(),
+            message_info(&users[1], &[]),
+            ExecuteMsg::SettleBatch {},
+        )
+        .unwrap_err(),
+        ContractError::BatchNotClosed {
+            settle_height: env.block.height + 2
+        }
+    );
+
+    // 3_000_000 uusd and 1_000_000 tokens clear at (1e9 + 1e6) / (1e9 + 3e6)
+    // tokens per uusd, the 30 bps commission is charged on the returns
+    settle_env.block.height += 1;
+    settle_env.block.time = settle_env.block.time.plus_seconds(10);
+    let res = execute(
+        deps.as_mut(),
+        settle_env.clone(),
+        message_info(&users[1], &[]),
+        ExecuteMsg::SettleBatch {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_batch"),
+            attr("filled_orders", "2"),
+            attr("refunded_orders", "2"),
+            attr("clearing_price", "0.998005982053838484"),
+            attr(
+                "reserves",
+                format!("1002000008uusd, 998011972{}", token_addr),
+            ),
+        ]
+    );
+    let transfer = |recipient: &Addr, amount: u128| -> CosmosMsg<InjectiveMsgWrapper> {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: Uint128::from(amount),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    };
+    let send = |recipient: &Addr, amount: u128| -> CosmosMsg<InjectiveMsgWrapper> {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(amount, "uusd"),
+        })
+    };
+    let msgs: Vec<CosmosMsg<InjectiveMsgWrapper>> =
+        res.messages.into_iter().map(|msg| msg.msg).collect();
+    assert_eq!(msgs.len(), 8);
+    assert_eq!(msgs[0], transfer(&users[0], 2_985_034));
+    assert_eq!(msgs[1], send(&users[1], 998_992));
+    assert_eq!(msgs[6], send(&users[3], 500_000));
+    assert_eq!(msgs[7], transfer(&users[2], 1_000_000));
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            Uint128::from(1_002_000_008u128),
+            Uint128::from(998_011_972u128)
+        ]
+    );
+    assert_eq!(
+        query_stats(deps.as_ref(), None, None)
+            .unwrap()
+            .total
+            .swap_count,
+        2
+    );
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            settle_env.clone(),
+            message_info(&users[1], &[]),
+            ExecuteMsg::SettleBatch {},
+        )
+        .unwrap_err(),
+        ContractError::NoBatch {}
+    );
+    assert!(query_batch_orders(deps.as_ref(), None, None)
+        .unwrap()
+        .orders
+        .is_empty());
+
+    // swaps execute immediately again once the mode is turned off
+    execute(
+        deps.as_mut(),
+        settle_env.clone(),
+        message_info(&factory, &[]),
+        update_batch_auction(None),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        settle_env,
+        message_info(&users[0], &coins(1_000_000, "uusd")),
+        native_swap(1_000_000, None),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "swap"));
+}
+
+fn launch_swap(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+    time: u64,
+    sender: &Addr,
+    amount: u128,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(time);
+    execute(
+        deps.as_mut(),
+        env,
+        message_info(sender, &coins(amount, "uusd")),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(amount),
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            trader: None,
+        },
+    )
+}
+
+#[test]
+fn launch_protection() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+    let users = [
+        deps.api.addr_make("addr0000"),
+        deps.api.addr_make("addr0001"),
+    ];
+    let env = mock_env();
+    let trading_start = env.block.time.seconds() + 600;
+    let launch = LaunchProtection {
+        trading_start,
+        duration: 1_800,
+        max_swap_ratio: Decimal::percent(1),
+        cooldown: 60,
+    };
+    let base_msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: None,
+    };
+    let instantiate_msg = |launch_protection: LaunchProtection| InstantiateMsg {
+        launch_protection: Some(launch_protection),
+        ..base_msg.clone()
+    };
+
+    let invalid = [
+        LaunchProtection {
+            max_swap_ratio: Decimal::zero(),
+            ..launch.clone()
+        },
+        LaunchProtection {
+            duration: 86_401,
+            ..launch.clone()
+        },
+        LaunchProtection {
+            trading_start: env.block.time.seconds() + 7 * 86_400 + 1,
+            ..launch.clone()
+        },
+    ];
+    for launch_protection in invalid {
+        let msg = instantiate_msg(launch_protection);
+        instantiate(deps.as_mut(), env.clone(), message_info(&factory, &[]), msg).unwrap_err();
+    }
+    let msg = instantiate_msg(launch.clone());
+    instantiate(deps.as_mut(), env.clone(), message_info(&factory, &[]), msg).unwrap();
+    set_reserves(&mut deps, [1_000_000_000, 1_000_000_000]);
+
+    assert_eq!(
+        launch_swap(&mut deps, trading_start - 1, &users[0], 1_000_000).unwrap_err(),
+        ContractError::TradingNotStarted { trading_start }
+    );
+
+    // at most 1% of the offer reserve per swap
+    assert_eq!(
+        launch_swap(&mut deps, trading_start, &users[0], 10_000_001).unwrap_err(),
+        ContractError::LaunchSwapTooLarge {
+            max_offer: "10000000".to_string(),
+            offer: "10000001".to_string(),
+        }
+    );
+    launch_swap(&mut deps, trading_start, &users[0], 10_000_000).unwrap();
+
+    // one swap per address and minute
+    assert_eq!(
+        launch_swap(&mut deps, trading_start + 30, &users[0], 1_000_000).unwrap_err(),
+        ContractError::SwapCooldown {
+            next_swap: trading_start + 60
+        }
+    );
+    launch_swap(&mut deps, trading_start + 30, &users[1], 1_000_000).unwrap();
+    launch_swap(&mut deps, trading_start + 60, &users[0], 1_000_000).unwrap();
+
+    // swaps through the router wait for the cooldown of the trader it forwards
+    let router = deps.api.addr_make("router0000");
+    deps.querier.with_router(router.as_str());
+    let routed_swap =
+        |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         time: u64,
+         trader: &Addr| {
+            let mut env = mock_env();
+            env.block.time = Timestamp::from_seconds(time);
+            execute(
+                deps.as_mut(),
+                env,
+                message_info(&router, &coins(1_000_000, "uusd")),
+                ExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::from(1_000_000u128),
+                    },
+                    belief_price: None,
+                    max_spread: None,
+                    to: Some(trader.to_string()),
+                    deadline: None,
+                    trader: Some(trader.to_string()),
+                },
+            )
+        };
+    routed_swap(&mut deps, trading_start + 90, &users[1]).unwrap();
+    assert_eq!(
+        routed_swap(&mut deps, trading_start + 90, &users[0]).unwrap_err(),
+        ContractError::SwapCooldown {
+            next_swap: trading_start + 120
+        }
+    );
+    let user = deps.api.addr_make("addr0002");
+    routed_swap(&mut deps, trading_start + 90, &user).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(trading_start + 1_799);
+    assert_eq!(
+        query_launch_protection(deps.as_ref(), env.clone()).unwrap(),
+        LaunchProtectionResponse {
+            launch_protection: Some(launch),
+            active: true,
+        }
+    );
+
+    // the pair trades freely once the protection is over
+    env.block.time = Timestamp::from_seconds(trading_start + 1_800);
+    assert!(!query_launch_protection(deps.as_ref(), env).unwrap().active);
+    launch_swap(&mut deps, trading_start + 1_800, &users[0], 100_000_000).unwrap();
+    launch_swap(&mut deps, trading_start + 1_801, &users[0], 100_000_000).unwrap();
+}
+#[test]
+fn circuit_breaker() {
+    let mut deps = flash_swap_deps(Uint128::from(1_000_000_000u128));
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    let start = mock_env().block.time.seconds();
+    let breaker = CircuitBreaker {
+        window: 3_600,
+        max_price_drop: Decimal::percent(10),
+        max_outflow: Decimal::percent(5),
+    };
+    let update = |circuit_breaker: Option<CircuitBreaker>| ExecuteMsg::UpdateCircuitBreaker {
+        circuit_breaker,
+    };
+    let query_at =
+        |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         time: u64| {
+            let mut env = mock_env();
+            env.block.time = Timestamp::from_seconds(time);
+            query_circuit_breaker(deps.as_ref(), env).unwrap()
+        };
+
+    let info = message_info(&user, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        update(Some(breaker.clone())),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let invalid = [
+        CircuitBreaker {
+            window: 0,
+            ..breaker.clone()
+        },
+        CircuitBreaker {
+            max_price_drop: Decimal::zero(),
+            ..breaker.clone()
+        },
+        CircuitBreaker {
+            max_outflow: Decimal::percent(101),
+            ..breaker.clone()
+        },
+    ];
+    for circuit_breaker in invalid {
+        let info = message_info(&factory, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            update(Some(circuit_breaker)),
+        );
+        assert_eq!(res.unwrap_err(), ContractError::InvalidCircuitBreaker {});
+    }
+
+    let info = message_info(&factory, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        update(Some(breaker.clone())),
+    )
+    .unwrap();
+
+    // 2% in, then 4% more in the same direction would drain over 5% of the
+    // token, the trade is rejected before it executes
+    launch_swap(&mut deps, start, &user, 20_000_000).unwrap();
+    let reserves = RESERVES.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        launch_swap(&mut deps, start + 60, &user, 40_000_000).unwrap_err(),
+        ContractError::CircuitBreakerExceeded {
+            window_end: start + 3_600
+        }
+    );
+    assert_eq!(RESERVES.load(deps.as_ref().storage).unwrap(), reserves);
+
+    let window = query_at(&deps, start + 60);
+    assert_eq!(window.window_end, Some(start + 3_600));
+    assert!(window.price_drops[0] < breaker.max_price_drop);
+    assert_eq!(window.price_drops[1], Decimal::zero());
+    assert_eq!(
+        window.outflows,
+        [
+            Uint128::zero(),
+            Uint128::from(1_000_000_000u128) - reserves[1]
+        ]
+    );
+
+    // trades up to the limits go through, then only the other direction
+    launch_swap(&mut deps, start + 60, &user, 20_000_000).unwrap();
+    assert_eq!(
+        launch_swap(&mut deps, start + 120, &user, 20_000_000).unwrap_err(),
+        ContractError::CircuitBreakerExceeded {
+            window_end: start + 3_600
+        }
+    );
+    let token = deps.api.addr_make("asset0000");
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(start + 120);
+    execute(
+        deps.as_mut(),
+        env,
+        message_info(&token, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: user.to_string(),
+            amount: Uint128::from(1_000_000u128),
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                trader: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+
+    // the window rolls over an hour after it opened
+    assert_eq!(query_at(&deps, start + 3_600).window_end, None);
+    launch_swap(&mut deps, start + 3_600, &user, 20_000_000).unwrap();
+    assert_eq!(
+        query_at(&deps, start + 3_600).window_end,
+        Some(start + 7_200)
+    );
+
+    // the factory can reopen a direction early
+    launch_swap(&mut deps, start + 3_660, &user, 20_000_000).unwrap();
+    assert!(launch_swap(&mut deps, start + 3_660, &user, 20_000_000).is_err());
+    let msg = ExecuteMsg::ResetCircuitBreaker {};
+    let res = execute(deps.as_mut(), mock_env(), message_info(&user, &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    let msg = ExecuteMsg::ResetCircuitBreaker {};
+    execute(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    assert_eq!(query_at(&deps, start + 3_660).window_end, None);
+    launch_swap(&mut deps, start + 3_660, &user, 20_000_000).unwrap();
+
+    let info = message_info(&factory, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, update(None)).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_circuit_breaker"),
+            attr("circuit_breaker", "none")
+        ]
+    );
+    assert_eq!(
+        query_at(&deps, start + 3_660),
+        CircuitBreakerResponse {
+            circuit_breaker: None,
+            window_end: None,
+            price_drops: [Decimal::zero(); 2],
+            outflows: [Uint128::zero(); 2],
+        }
+    );
+}
+#[test]
+fn circuit_breaker_stableswap() {
+    let mut deps = mock_dependencies(&[]);
+    let factory = deps.api.addr_make("factory0000");
+    let msg = stable_instantiate_msg(&deps, Some(100));
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateCircuitBreaker {
+            circuit_breaker: Some(CircuitBreaker {
+                window: 3_600,
+                max_price_drop: Decimal::percent(1),
+                max_outflow: Decimal::percent(50),
+            }),
+        },
+    )
+    .unwrap();
+
+    // moving 10% of the reserves drops the reserve ratio by 18%, but the
+    // stable curve's price by less than 1%
+    let now = mock_env().block.time.seconds();
+    let reserves = [Uint128::from(1_000_000_000_000u128); 2];
+    let reserves_post = [
+        Uint128::from(1_100_000_000_000u128),
+        Uint128::from(900_000_000_000u128),
+    ];
+    circuit_breaker::record(deps.as_mut().storage, now, [6, 6], reserves, reserves_post).unwrap();
+    set_reserves(
+        &mut deps,
+        [reserves_post[0].u128(), reserves_post[1].u128()],
+    );
+    let price_drops = query_circuit_breaker(deps.as_ref(), mock_env())
+        .unwrap()
+        .price_drops;
+    assert!(price_drops[0] > Decimal::zero() && price_drops[0] < Decimal::percent(1));
+
+    // pushing on to a 30/70 split is past the limit
+    let res = circuit_breaker::record(
+        deps.as_mut().storage,
+        now,
+        [6, 6],
+        reserves_post,
+        [
+            Uint128::from(1_400_000_000_000u128),
+            Uint128::from(600_000_000_000u128),
+        ],
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CircuitBreakerExceeded {
+            window_end: now + 3_600
+        }
+    );
+}
+
+#[test]
+fn holding_period() {
+    let amount = Uint128::from(1_000_000_000u128);
+    let mut deps = zap_in_deps(amount, amount, amount);
+    set_reserves(&mut deps, [amount.u128(), amount.u128()]);
+
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let factory = deps.api.addr_make("factory0000");
+    let jit = deps.api.addr_make("addr0000");
+    let other = deps.api.addr_make("addr0001");
+    let start = mock_env().block.time.seconds();
+    let at = |time: u64| {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(time);
+        env
+    };
+    let update = |early_withdrawal_penalty: Option<Decimal>| ExecuteMsg::UpdateHoldingPeriod {
+        holding_period: Some(HoldingPeriod {
+            seconds: 86_400,
+            early_withdrawal_penalty,
+        }),
+    };
+    let withdraw =
+        |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         time: u64,
+         sender: &Addr,
+         amount: u128| {
+            let info = message_info(sender, &coins(amount, lp_denom.clone()));
+            let msg = ExecuteMsg::WithdrawLiquidity {
+                amount: Uint128::from(amount),
+                min_assets: None,
+                deadline: None,
+            };
+            execute(deps.as_mut(), at(time), info, msg)
+        };
+
+    let res = execute(
+        deps.as_mut(),
+        at(start),
+        message_info(&jit, &[]),
+        update(None),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    for msg in [
+        ExecuteMsg::UpdateHoldingPeriod {
+            holding_period: Some(HoldingPeriod {
+                seconds: 0,
+                early_withdrawal_penalty: None,
+            }),
+        },
+        update(Some(Decimal::zero())),
+        update(Some(Decimal::percent(101))),
+    ] {
+        let res = execute(deps.as_mut(), at(start), message_info(&factory, &[]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidHoldingPeriod {});
+    }
+
+    execute(
+        deps.as_mut(),
+        at(start),
+        message_info(&factory, &[]),
+        update(None),
+    )
+    .unwrap();
+    assert_eq!(
+        query_holding_period(deps.as_ref()).unwrap(),
+        HoldingPeriodResponse {
+            holding_period: Some(HoldingPeriod {
+                seconds: 86_400,
+                early_withdrawal_penalty: None,
+            }),
+        }
+    );
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount,
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount,
+            },
+        ],
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let info = message_info(&jit, &coins(amount.u128(), "uusd"));
+    execute(deps.as_mut(), at(start), info, msg).unwrap();
+    assert_eq!(
+        query_lp_deposit(deps.as_ref(), jit.to_string()).unwrap(),
+        LpDepositResponse {
+            amount,
+            matures_at: Some(start + 86_400),
+        }
+    );
+    assert_eq!(
+        withdraw(&mut deps, start + 60, &jit, 1_000).unwrap_err(),
+        ContractError::HoldingPeriodNotOver {
+            matures_at: start + 86_400
+        }
+    );
+    // LP without a recorded deposit is as young as it gets
+    assert_eq!(
+        withdraw(&mut deps, start + 60, &other, 1_000).unwrap_err(),
+        ContractError::HoldingPeriodNotOver {
+            matures_at: start + 60 + 86_400
+        }
+    );
+
+    // with a penalty early withdrawals leave 10% of their refund in the pool
+    let info = message_info(&factory, &[]);
+    execute(
+        deps.as_mut(),
+        at(start),
+        info,
+        update(Some(Decimal::percent(10))),
+    )
+    .unwrap();
+    let res = withdraw(&mut deps, start + 60, &other, 100_000_000).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_liquidity"),
+            attr("sender", other.as_str()),
+            attr("withdrawn_share", "100000000"),
+            attr(
+                "refund_assets",
+                format!(
+                    "180000000uusd, 180000000{}",
+                    deps.api.addr_make("asset0000")
+                )
+            ),
+            attr(
+                "penalty_assets",
+                format!("20000000uusd, 20000000{}", deps.api.addr_make("asset0000"))
+            ),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::from(1_820_000_000u128); 2]
+    );
+
+    // LP received by transfer matures once recorded
+    deps.querier.with_balance(&[(
+        &other.to_string(),
+        vec![Coin {
+            denom: lp_denom.clone(),
+            amount: Uint128::from(100_000_000u128),
+        }],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        at(start + 60),
+        message_info(&other, &[]),
+        ExecuteMsg::RecordLpDeposit {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "record_lp_deposit"),
+            attr("sender", other.as_str()),
+            attr("recorded_amount", "100000000"),
+        ]
+    );
+    let res = execute(
+        deps.as_mut(),
+        at(start + 120),
+        message_info(&other, &[]),
+        ExecuteMsg::RecordLpDeposit {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[2], attr("recorded_amount", "0"));
+    let res = withdraw(&mut deps, start + 60 + 86_400, &other, 100_000_000).unwrap();
+    assert_eq!(res.attributes.len(), 4);
+
+    // matured deposits withdraw in full
+    let res = withdraw(&mut deps, start + 86_400, &jit, 500_000_000).unwrap();
+    assert_eq!(res.attributes.len(), 4);
+    assert_eq!(
+        query_lp_deposit(deps.as_ref(), jit.to_string()).unwrap(),
+        LpDepositResponse {
+            amount: Uint128::from(500_000_000u128),
+            matures_at: Some(start + 86_400),
+        }
+    );
+
+    let msg = ExecuteMsg::UpdateHoldingPeriod {
+        holding_period: None,
+    };
+    execute(deps.as_mut(), at(start), message_info(&factory, &[]), msg).unwrap();
+    assert_eq!(
+        query_holding_period(deps.as_ref()).unwrap(),
+        HoldingPeriodResponse {
+            holding_period: None,
+        }
+    );
+    // deposits are cleared along with the holding period
+    assert_eq!(
+        query_lp_deposit(deps.as_ref(), jit.to_string()).unwrap(),
+        LpDepositResponse {
+            amount: Uint128::zero(),
+            matures_at: None,
+        }
+    );
+    withdraw(&mut deps, start + 60, &other, 1_000).unwrap();
+}
+
+#[test]
+fn holding_period_unrecorded_lp() {
+    let amount = Uint128::from(1_000_000_000u128);
+    let mut deps = zap_in_deps(amount, amount, amount);
+    set_reserves(&mut deps, [amount.u128(), amount.u128()]);
+
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let factory = deps.api.addr_make("factory0000");
+    let receiver = deps.api.addr_make("addr0001");
+    let start = mock_env().block.time.seconds();
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(start + 30 * 86_400);
+
+    let msg = ExecuteMsg::UpdateHoldingPeriod {
+        holding_period: Some(HoldingPeriod {
+            seconds: 86_400,
+            early_withdrawal_penalty: Some(Decimal::percent(10)),
+        }),
+    };
+    execute(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+
+    // LP transferred in long ago but never recorded is penalised as if it was
+    // deposited by the withdrawal itself
+    let info = message_info(&receiver, &coins(100_000_000, lp_denom));
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(100_000_000u128),
+        min_assets: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_liquidity"),
+            attr("sender", receiver.as_str()),
+            attr("withdrawn_share", "100000000"),
+            attr(
+                "refund_assets",
+                format!("90000000uusd, 90000000{}", deps.api.addr_make("asset0000"))
+            ),
+            attr(
+                "penalty_assets",
+                format!("10000000uusd, 10000000{}", deps.api.addr_make("asset0000"))
+            ),
+        ]
+    );
+    assert_eq!(
+        query_lp_deposit(deps.as_ref(), receiver.to_string()).unwrap(),
+        LpDepositResponse {
+            amount: Uint128::zero(),
+            matures_at: None,
+        }
+    );
+}
+
+#[test]
+fn holding_period_removal() {
+    let amount = Uint128::from(1_000_000_000u128);
+    let mut deps = zap_in_deps(amount, amount, amount);
+    set_reserves(&mut deps, [amount.u128(), amount.u128()]);
+
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let factory = deps.api.addr_make("factory0000");
+    let provider = deps.api.addr_make("addr0000");
+    let start = mock_env().block.time.seconds();
+    let at = |time: u64| {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(time);
+        env
+    };
+    let update =
+        |holding_period: Option<HoldingPeriod>| ExecuteMsg::UpdateHoldingPeriod { holding_period };
+    let withdraw =
+        |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         time: u64| {
+            let info = message_info(&provider, &coins(100_000_000, lp_denom.clone()));
+            let msg = ExecuteMsg::WithdrawLiquidity {
+                amount: Uint128::from(100_000_000u128),
+                min_assets: None,
+                deadline: None,
+            };
+            execute(deps.as_mut(), at(time), info, msg)
+        };
+
+    let holding_period = HoldingPeriod {
+        seconds: 86_400,
+        early_withdrawal_penalty: None,
+    };
+    let info = message_info(&factory, &[]);
+    execute(
+        deps.as_mut(),
+        at(start),
+        info,
+        update(Some(holding_period.clone())),
+    )
+    .unwrap();
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount,
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount,
+            },
+        ],
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let info = message_info(&provider, &coins(amount.u128(), "uusd"));
+    execute(deps.as_mut(), at(start), info, msg).unwrap();
+    assert_eq!(
+        withdraw(&mut deps, start + 60).unwrap_err(),
+        ContractError::HoldingPeriodNotOver {
+            matures_at: start + 86_400
+        }
+    );
+
+    // the same early withdrawal goes through once the holding period is removed
+    let info = message_info(&factory, &[]);
+    execute(deps.as_mut(), at(start + 60), info, update(None)).unwrap();
+    let res = withdraw(&mut deps, start + 60).unwrap();
+    assert_eq!(res.attributes.len(), 4);
+
+    // deposits recorded under the removed holding period no longer count
+    let info = message_info(&factory, &[]);
+    execute(
+        deps.as_mut(),
+        at(start + 120),
+        info,
+        update(Some(holding_period)),
+    )
+    .unwrap();
+    assert_eq!(
+        query_lp_deposit(deps.as_ref(), provider.to_string()).unwrap(),
+        LpDepositResponse {
+            amount: Uint128::zero(),
+            matures_at: None,
+        }
+    );
+    assert_eq!(
+        withdraw(&mut deps, start + 86_400).unwrap_err(),
+        ContractError::HoldingPeriodNotOver {
+            matures_at: start + 2 * 86_400
+        }
+    );
+}
+#[test]
+fn allowlist() {
+    let amount = Uint128::from(1_000_000_000u128);
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        amount,
+    )]);
+
+    let factory = deps.api.addr_make("factory0000");
+    let manager = deps.api.addr_make("manager0000");
+    let provider = deps.api.addr_make("addr0000");
+    let outsider = deps.api.addr_make("addr0001");
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: Some(Allowlist {
+            manager: Some(manager.to_string()),
+            addresses: vec![provider.to_string()],
+            restrict_swaps: false,
+        }),
+    };
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    set_reserves(&mut deps, [amount.u128(), amount.u128()]);
+    assert_eq!(
+        query_allowlist(deps.as_ref(), None, None).unwrap(),
+        AllowlistResponse {
+            manager: Some(manager.to_string()),
+            restrict_swaps: false,
+            addresses: vec![provider.to_string()],
+        }
+    );
+
+    let provide = |receiver: Option<&Addr>| ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(1_000u128),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                amount: Uint128::from(1_000u128),
+            },
+        ],
+        receiver: receiver.map(|receiver| receiver.to_string()),
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let swap = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(1_000u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let funds = [Coin::new(1_000u128, "uusd"), Coin::new(1_000u128, "uluna")];
+    let not_allowlisted = |address: &Addr| ContractError::NotAllowlisted {
+        address: address.to_string(),
+    };
+
+    // the sender and the receiver of the LP both need to be on the list
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&outsider, &funds),
+        provide(None),
+    );
+    assert_eq!(res.unwrap_err(), not_allowlisted(&outsider));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&provider, &funds),
+        provide(Some(&outsider)),
+    );
+    assert_eq!(res.unwrap_err(), not_allowlisted(&outsider));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&provider, &funds),
+        provide(None),
+    )
+    .unwrap();
+
+    // swaps are open until they are restricted
+    let info = message_info(&outsider, &funds[..1]);
+    execute(deps.as_mut(), mock_env(), info, swap.clone()).unwrap();
+
+    let update = ExecuteMsg::UpdateAllowlist {
+        add: vec![outsider.to_string()],
+        remove: vec![provider.to_string()],
+        restrict_swaps: Some(true),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&outsider, &[]),
+        update.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    for sender in [&manager, &factory] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(sender, &[]),
+            update.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_allowlist"),
+                attr("added", "1"),
+                attr("removed", "1"),
+                attr("restrict_swaps", "true"),
+            ]
+        );
+    }
+
+    let info = message_info(&provider, &funds[..1]);
+    let res = execute(deps.as_mut(), mock_env(), info, swap.clone());
+    assert_eq!(res.unwrap_err(), not_allowlisted(&provider));
+    let info = message_info(&outsider, &funds[..1]);
+    execute(deps.as_mut(), mock_env(), info, swap).unwrap();
+
+    // removed providers can still withdraw
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(100u128),
+        min_assets: None,
+        deadline: None,
+    };
+    let info = message_info(&provider, &coins(100, lp_denom));
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&manager, &[]),
+        ExecuteMsg::UpdateAllowlist {
+            add: vec![provider.to_string()],
+            remove: vec![],
+            restrict_swaps: None,
+        },
+    )
+    .unwrap();
+    let mut addresses = [provider.to_string(), outsider.to_string()];
+    addresses.sort();
+    let page = query_allowlist(deps.as_ref(), None, Some(1)).unwrap();
+    assert!(page.restrict_swaps);
+    assert_eq!(page.addresses, addresses[..1].to_vec());
+    let page = query_allowlist(deps.as_ref(), Some(addresses[0].clone()), None).unwrap();
+    assert_eq!(page.addresses, addresses[1..].to_vec());
+
+    // public pairs have no allowlist
+    let mut deps = zap_in_deps(amount, amount, amount);
+    assert_eq!(
+        query_allowlist(deps.as_ref(), None, None).unwrap_err(),
+        ContractError::NotPermissioned {}
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        update,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NotPermissioned {});
+}
+
+#[test]
+fn allowlist_routed_swap() {
+    let amount = Uint128::from(1_000_000_000u128);
+    let mut deps = mock_dependencies(&[]);
+
+    let factory = deps.api.addr_make("factory0000");
+    let router = deps.api.addr_make("router0000");
+    let trader = deps.api.addr_make("addr0000");
+    let outsider = deps.api.addr_make("addr0001");
+    deps.querier.with_router(router.as_str());
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ],
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        pair_type: PairType::ConstantProduct,
+        amp: None,
+        weights: None,
+        launch_protection: None,
+        allowlist: Some(Allowlist {
+            manager: None,
+            addresses: vec![router.to_string(), trader.to_string()],
+            restrict_swaps: true,
+        }),
+    };
+    instantiate(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    set_reserves(&mut deps, [amount.u128(), amount.u128()]);
+
+    let routed_swap = |trader: &Addr| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(1_000u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        trader: Some(trader.to_string()),
+    };
+
+    // a listed router does not open the pair to the traders it forwards
+    let info = message_info(&router, &coins(1_000, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, routed_swap(&outsider));
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::NotAllowlisted {
+            address: outsider.to_string(),
+        }
+    );
+    let info = message_info(&router, &coins(1_000, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, routed_swap(&trader)).unwrap();
+}
+#[test]
+fn fee_discount() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    let router = deps.api.addr_make("router0000");
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        coins((pool_amount + offer_amount).u128(), "uusd"),
+    )]);
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+    // the reserves are restored after every swap
+    let swap = |deps: &mut OwnedDeps<_, _, _, _>, sender: &Addr, trader: Option<String>| {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(sender, &coins(offer_amount.u128(), "uusd")),
+            ExecuteMsg::Swap {
+                offer_asset: offer_asset.clone(),
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                trader,
+            },
+        )
+        .unwrap();
+        set_reserves(deps, [pool_amount.u128(), pool_amount.u128()]);
+        let amount = |key: &str| {
+            let attribute = res.attributes.iter().find(|attr| attr.key == key).unwrap();
+            Uint128::from_str(&attribute.value).unwrap()
+        };
+        (amount("return_amount"), amount("commission_amount"))
+    };
+
+    let full = query_simulation(deps.as_ref(), mock_env(), offer_asset.clone(), None).unwrap();
+    assert_eq!(
+        swap(&mut deps, &user, None),
+        (full.return_amount, full.commission_amount)
+    );
+
+    // half of the commission goes back to the trader
+    deps.querier
+        .with_fee_discount(user.as_str(), Decimal::percent(50));
+    let rebate = full.commission_amount.mul_floor(Decimal::percent(50));
+    let discounted = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        offer_asset.clone(),
+        Some(user.to_string()),
+    )
+    .unwrap();
+    assert_eq!(discounted.return_amount, full.return_amount + rebate);
+    assert_eq!(
+        discounted.commission_amount,
+        full.commission_amount - rebate
+    );
+    assert_eq!(
+        swap(&mut deps, &user, None),
+        (discounted.return_amount, discounted.commission_amount)
+    );
+
+    // the trader's discount also applies to swaps sent on its behalf
+    assert_eq!(
+        swap(&mut deps, &router, Some(user.to_string())),
+        (discounted.return_amount, discounted.commission_amount)
+    );
+
+    // exact out swaps and flash swaps need a smaller offer
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        amount: offer_amount,
+    };
+    let full_quote =
+        query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset.clone(), None).unwrap();
+    let quote = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        ask_asset.clone(),
+        Some(user.to_string()),
+    )
+    .unwrap();
+    assert!(quote.offer_amount < full_quote.offer_amount);
+    assert!(quote.commission_amount < full_quote.commission_amount);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(full_quote.offer_amount.u128(), "uusd")),
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer: Asset {
+                info: offer_asset.info.clone(),
+                amount: full_quote.offer_amount,
+            },
+            to: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("offer_amount", quote.offer_amount.to_string())));
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+
+    let repay_amount = |deps: &mut OwnedDeps<_, _, _, _>, sender: &Addr| {
+        let msg = ExecuteMsg::FlashSwap {
+            asset: offer_asset.clone(),
+            to: None,
+            msg: Binary::from(b"arbitrage".to_vec()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), message_info(sender, &[]), msg).unwrap();
+        FLASH_SWAP.remove(deps.as_mut().storage);
+        let attribute = res
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "repay_amount")
+            .unwrap();
+        Uint128::from_str(&attribute.value).unwrap()
+    };
+    assert!(repay_amount(&mut deps, &user) < repay_amount(&mut deps, &router));
+
+    // exempt addresses swap without commission
+    deps.querier
+        .with_fee_discount(user.as_str(), Decimal::one());
+    let exempt = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        offer_asset.clone(),
+        Some(user.to_string()),
+    )
+    .unwrap();
+    assert_eq!(
+        exempt.return_amount,
+        full.return_amount + full.commission_amount
+    );
+    assert!(exempt.commission_amount.is_zero());
+    assert_eq!(
+        swap(&mut deps, &user, None),
+        (exempt.return_amount, Uint128::zero())
+    );
+}
+
+#[test]
+fn fee_discount_exact_out() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    deps.querier
+        .with_fee_discount(user.as_str(), Decimal::percent(50));
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        amount: Uint128::from(1_000_000u128),
+    };
+
+    let full_quote =
+        query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset.clone(), None).unwrap();
+    let quote = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        ask_asset.clone(),
+        Some(user.to_string()),
+    )
+    .unwrap();
+    assert!(quote.offer_amount < full_quote.offer_amount);
+    assert!(quote.commission_amount < full_quote.commission_amount);
+
+    // the discounted quote is what the swap takes, with no room to spare
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(quote.offer_amount.u128(), "uusd")),
+        ExecuteMsg::SwapExactOut {
+            ask_asset: ask_asset.clone(),
+            max_offer: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: quote.offer_amount,
+            },
+            to: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    for (key, value) in [
+        ("offer_amount", quote.offer_amount),
+        ("return_amount", ask_asset.amount),
+        ("spread_amount", quote.spread_amount),
+        ("commission_amount", quote.commission_amount),
+    ] {
+        assert!(res.attributes.contains(&attr(key, value.to_string())));
+    }
+}
+
+#[test]
+fn fee_discount_reverse_simulation() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let user = deps.api.addr_make("addr0000");
+    deps.querier
+        .with_fee_discount(user.as_str(), Decimal::percent(50));
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        amount: Uint128::from(1_000_000u128),
+    };
+    let quote = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        ask_asset.clone(),
+        Some(user.to_string()),
+    )
+    .unwrap();
+
+    // swapping the quoted offer returns the asked amount at the quoted
+    // commission, the trader's discount included
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(quote.offer_amount.u128(), "uusd")),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: quote.offer_amount,
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            trader: None,
+        },
+    )
+    .unwrap();
+    let amount = |key: &str| {
+        let attribute = res.attributes.iter().find(|attr| attr.key == key).unwrap();
+        Uint128::from_str(&attribute.value).unwrap()
+    };
+    // the quote rounds against the trader by at most a unit
+    let return_amount = amount("return_amount");
+    assert!(return_amount >= ask_asset.amount);
+    assert!(return_amount - ask_asset.amount <= Uint128::one());
+    let commission_amount = amount("commission_amount");
+    assert!(commission_amount <= quote.commission_amount);
+    assert!(quote.commission_amount - commission_amount <= Uint128::one());
+}
+#[test]
+fn migrate_stores_factory() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+
+    // a pair from before the factory address and reserves were stored
+    FACTORY.remove(deps.as_mut().storage);
+    RESERVES.remove(deps.as_mut().storage);
+    assert_eq!(
+        migrate(deps.as_mut(), mock_env(), MigrateMsg { factory: None }).unwrap_err(),
+        ContractError::Std(StdError::generic_err(
+            "the factory address is required to migrate this pair"
+        ))
+    );
+    let msg = MigrateMsg {
+        factory: Some(factory.to_string()),
+    };
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(
+        FACTORY.load(deps.as_ref().storage).unwrap(),
+        deps.api.addr_canonicalize(factory.as_str()).unwrap()
+    );
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        coins((pool_amount + offer_amount).u128(), "uusd"),
+    )]);
+    let user = deps.api.addr_make("addr0000");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(offer_amount.u128(), "uusd")),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            trader: None,
+        },
+    )
+    .unwrap();
+}
+#[test]
+fn migrate_baseline_pair_info() {
+    // pair info as stored before fee tiers and pair types
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct BaselinePairInfoRaw {
+        asset_infos: [choice::asset::AssetInfoRaw; 2],
+        contract_addr: cosmwasm_std::CanonicalAddr,
+        liquidity_token: String,
+        asset_decimals: [u8; 2],
+        burn_address: cosmwasm_std::CanonicalAddr,
+        fee_wallet_address: cosmwasm_std::CanonicalAddr,
+    }
+
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let pair_info = crate::state::PAIR_INFO.load(deps.as_ref().storage).unwrap();
+    cw_storage_plus::Item::new("pair_info")
+        .save(
+            deps.as_mut().storage,
+            &BaselinePairInfoRaw {
+                asset_infos: pair_info.asset_infos,
+                contract_addr: pair_info.contract_addr,
+                liquidity_token: pair_info.liquidity_token,
+                asset_decimals: pair_info.asset_decimals,
+                burn_address: pair_info.burn_address,
+                fee_wallet_address: pair_info.fee_wallet_address,
+            },
+        )
+        .unwrap();
+    RESERVES.remove(deps.as_mut().storage);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { factory: None }).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [pool_amount, pool_amount]
+    );
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.commission_bps, 30);
+    assert_eq!(pair_info.pair_type, PairType::ConstantProduct);
+}
+#[test]
+fn swap_hooks_every_swap_path() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = zap_in_deps(pool_amount, pool_amount, pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let hook = deps.api.addr_make("hook0000").to_string();
+    let user = deps.api.addr_make("addr0000");
+    let token = deps.api.addr_make("asset0000").to_string();
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let token_info = AssetInfo::Token {
+        contract_addr: token.clone(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateSwapHooks {
+            hooks: vec![hook.clone()],
+        },
+    )
+    .unwrap();
+
+    let hook_msg = |res: &Response<InjectiveMsgWrapper>| -> SwapHookMsg {
+        let hook_messages: Vec<&SubMsg<InjectiveMsgWrapper>> = res
+            .messages
+            .iter()
+            .filter(|msg| msg.reply_on == ReplyOn::Error)
+            .collect();
+        assert_eq!(hook_messages.len(), 1);
+        match &hook_messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) => {
+                assert_eq!(contract_addr, &hook);
+                from_json(msg).unwrap()
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    };
+    let amount = |res: &Response<InjectiveMsgWrapper>, key: &str| {
+        let attribute = res.attributes.iter().find(|attr| attr.key == key).unwrap();
+        Uint128::from_str(&attribute.value).unwrap()
+    };
+
+    // the swap leg of a zap in
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(100_000_000u128, "uusd")),
+        ExecuteMsg::ZapIn {
+            offer_asset: Asset {
+                info: uusd.clone(),
+                amount: Uint128::from(100_000_000u128),
+            },
+            min_lp: None,
+            receiver: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    let SwapHookMsg::SwapHook {
+        sender,
+        receiver,
+        offer_asset,
+        return_asset,
+        commission_amount,
+        burn_amount,
+        fee_wallet_amount,
+        ..
+    } = hook_msg(&res);
+    assert_eq!(
+        (sender.as_str(), receiver.as_str()),
+        (user.as_str(), user.as_str())
+    );
+    assert_eq!(
+        (offer_asset, return_asset),
+        (
+            Asset {
+                info: uusd.clone(),
+                amount: amount(&res, "swap_amount"),
+            },
+            Asset {
+                info: token_info.clone(),
+                amount: amount(&res, "return_amount"),
+            },
+        )
+    );
+    assert_eq!(
+        (commission_amount, burn_amount, fee_wallet_amount),
+        (
+            amount(&res, "commission_amount"),
+            amount(&res, "burn_amount"),
+            amount(&res, "fee_wallet_amount"),
+        )
+    );
+
+    // the swap leg of a zap out
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(10_000_000u128, lp_denom)),
+        ExecuteMsg::ZapOut {
+            amount: Uint128::from(10_000_000u128),
+            target_asset: uusd.clone(),
+            min_return: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    let SwapHookMsg::SwapHook {
+        sender,
+        offer_asset,
+        return_asset,
+        commission_amount,
+        ..
+    } = hook_msg(&res);
+    assert_eq!(sender, user.to_string());
+    assert_eq!(
+        (offer_asset, return_asset, commission_amount),
+        (
+            Asset {
+                info: token_info.clone(),
+                amount: amount(&res, "swap_amount"),
+            },
+            Asset {
+                info: uusd.clone(),
+                amount: amount(&res, "return_amount"),
+            },
+            amount(&res, "commission_amount"),
+        )
+    );
+
+    // a flash swap, repaid in the borrowed asset, once the loan is settled
+    set_reserves(&mut deps, [pool_amount.u128(), pool_amount.u128()]);
+    let borrower = deps.api.addr_make("borrower0000");
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&borrower, &[]),
+        ExecuteMsg::FlashSwap {
+            asset: Asset {
+                info: token_info.clone(),
+                amount: Uint128::from(1_000_000u128),
+            },
+            to: None,
+            msg: Binary::default(),
+        },
+    )
+    .unwrap();
+    assert!(res
+        .messages
+        .iter()
+        .all(|msg| msg.reply_on != ReplyOn::Error));
+    deps.querier.with_token_balances(&[(
+        &token,
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &(pool_amount + Uint128::from(3_010u128)),
+        )],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), flash_swap_reply()).unwrap();
+    assert_eq!(
+        hook_msg(&res),
+        SwapHookMsg::SwapHook {
+            sender: borrower.to_string(),
+            receiver: borrower.to_string(),
+            offer_asset: Asset {
+                info: token_info.clone(),
+                amount: Uint128::from(1_003_010u128),
+            },
+            return_asset: Asset {
+                info: token_info,
+                amount: Uint128::from(1_000_000u128),
+            },
+            spread_amount: Uint128::zero(),
+            commission_amount: Uint128::from(3_010u128),
+            burn_amount: Uint128::from(501u128),
+            fee_wallet_amount: Uint128::from(501u128),
+        }
+    );
+}
+#[test]
+fn batch_auction_order_limits() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let router = deps.api.addr_make("router0000");
+    let user = deps.api.addr_make("addr0000");
+    deps.querier.with_router(router.as_str());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&factory, &[]),
+        ExecuteMsg::UpdateBatchAuction {
+            batch_auction: Some(BatchAuction {
+                interval_blocks: 2,
+                min_offer_amounts: [Uint128::from(100_000u128), Uint128::from(200_000u128)],
+            }),
+        },
+    )
+    .unwrap();
+
+    let native_swap = |amount: u128| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(amount),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: Some(user.to_string()),
+        deadline: None,
+        trader: Some(user.to_string()),
+    };
+
+    // the router would receive the return and refunds of its users' orders
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&router, &coins(1_000_000, "uusd")),
+            native_swap(1_000_000),
+        )
+        .unwrap_err(),
+        ContractError::BatchAuctionRouted {}
+    );
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &coins(99_999, "uusd")),
+            native_swap(99_999),
+        )
+        .unwrap_err(),
+        ContractError::BatchOrderTooSmall {
+            min_offer: "100000uusd".to_string(),
+        }
+    );
+    let token = deps.api.addr_make("asset0000");
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&token, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user.to_string(),
+                amount: Uint128::from(199_999u128),
+                msg: to_json_binary(&Cw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    deadline: None,
+                    trader: None,
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap_err(),
+        ContractError::BatchOrderTooSmall {
+            min_offer: format!("200000{}", token),
+        }
+    );
+
+    // an address fills at most 5 of the 50 orders of a batch
+    for _ in 0..5 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &coins(100_000, "uusd")),
+            native_swap(100_000),
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &coins(100_000, "uusd")),
+            native_swap(100_000),
+        )
+        .unwrap_err(),
+        ContractError::BatchSenderFull { max: 5 }
+    );
+    let other = deps.api.addr_make("addr0001");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&other, &coins(100_000, "uusd")),
+        native_swap(100_000),
+    )
+    .unwrap();
+}
+
+#[test]
+fn batch_auction_fee_discount() {
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000u128);
+    let mut deps = flash_swap_deps(pool_amount);
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    let other = deps.api.addr_make("addr0001");
+    deps.querier
+        .with_fee_discount(user.as_str(), Decimal::percent(50));
+    let msg = ExecuteMsg::UpdateBatchAuction {
+        batch_auction: Some(BatchAuction {
+            interval_blocks: 1,
+            min_offer_amounts: [Uint128::one(); 2],
+        }),
+    };
+    execute(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+
+    for sender in [&user, &other] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(sender, &coins(offer_amount.u128(), "uusd")),
+            ExecuteMsg::Swap {
+                offer_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: offer_amount,
+                },
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                trader: None,
+            },
+        )
+        .unwrap();
+    }
+
+    // both orders clear at the same price, the discounted sender gets half of
+    // its commission back
+    let (return_amount, _, commission_amount) = crate::batch::compute_swap(
+        [pool_amount, pool_amount],
+        [offer_amount + offer_amount, Uint128::zero()],
+        0,
+        offer_amount,
+        commission_rate(30),
+    )
+    .unwrap();
+    let rebate = commission_amount.mul_floor(Decimal::percent(50));
+    let mut env = mock_env();
+    env.block.height += 1;
+    let res = execute(
+        deps.as_mut(),
+        env,
+        message_info(&other, &[]),
+        ExecuteMsg::SettleBatch {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("filled_orders", "2"));
+    for (receiver, amount) in [(&user, return_amount + rebate), (&other, return_amount)] {
+        assert!(res
+            .messages
+            .contains(&SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: receiver.to_string(),
+                    amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }))));
+    }
+}
+#[test]
+fn circuit_breaker_refunds_batch() {
+    let mut deps = flash_swap_deps(Uint128::from(1_000_000_000u128));
+    let factory = deps.api.addr_make("factory0000");
+    let user = deps.api.addr_make("addr0000");
+    for msg in [
+        ExecuteMsg::UpdateCircuitBreaker {
+            circuit_breaker: Some(CircuitBreaker {
+                window: 3_600,
+                max_price_drop: Decimal::percent(10),
+                max_outflow: Decimal::percent(5),
+            }),
+        },
+        ExecuteMsg::UpdateBatchAuction {
+            batch_auction: Some(BatchAuction {
+                interval_blocks: 1,
+                min_offer_amounts: [Uint128::one(); 2],
+            }),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), message_info(&factory, &[]), msg).unwrap();
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(100_000_000, "uusd")),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(100_000_000u128),
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            trader: None,
+        },
+    )
+    .unwrap();
+
+    // clearing 10% of the reserve would drain over 5% of the token, the batch
+    // is refunded and the settlement goes through
+    let mut env = mock_env();
+    env.block.height += 1;
+    let res = execute(
+        deps.as_mut(),
+        env,
+        message_info(&user, &[]),
+        ExecuteMsg::SettleBatch {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: user.to_string(),
+            amount: coins(100_000_000, "uusd"),
+        })]
+    );
+    assert_eq!(res.attributes[1], attr("filled_orders", "0"));
+    assert_eq!(res.attributes[2], attr("refunded_orders", "1"));
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::from(1_000_000_000u128); 2]
+    );
+}
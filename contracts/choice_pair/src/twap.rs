@@ -0,0 +1,216 @@
+// Uniswap V2 style price accumulators. Cumulative prices are advanced with the
+// spot prices of the curve at the reserves from before every swap, provide and
+// withdraw, and written at most once per block to a fixed-size ring buffer.
+// Reserves only change at those points, so the cumulative prices grow linearly
+// between two observations.
+
+use crate::error::ContractError;
+use crate::stableswap;
+use crate::state::{Observation, ObservationIndex, AMP, OBSERVATIONS, OBSERVATION_INDEX, WEIGHTS};
+use crate::weighted;
+use cosmwasm_std::{Decimal256, StdResult, Storage, Uint128, Uint256};
+
+/// Number of observations kept per pair, the oldest is overwritten first
+pub const MAX_OBSERVATIONS: u32 = 1_000;
+
+/// Records the cumulative prices at `now`. `pools` must be the reserves before
+/// the current action changes them.
+pub fn accumulate_prices(
+    storage: &mut dyn Storage,
+    now: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+) -> StdResult<()> {
+    let (index, observation) = match OBSERVATION_INDEX.may_load(storage)? {
+        Some(index) => {
+            let latest = OBSERVATIONS.load(storage, index.latest)?;
+            if latest.timestamp >= now {
+                return Ok(());
+            }
+
+            (
+                ObservationIndex {
+                    latest: (index.latest + 1) % MAX_OBSERVATIONS,
+                    count: (index.count + 1).min(MAX_OBSERVATIONS),
+                },
+                extrapolate(&latest, now, spot_prices(storage, now, pools, decimals)?),
+            )
+        }
+        None => (
+            ObservationIndex {
+                latest: 0,
+                count: 1,
+            },
+            Observation {
+                timestamp: now,
+                price0_cumulative: Uint256::zero(),
+                price1_cumulative: Uint256::zero(),
+            },
+        ),
+    };
+
+    OBSERVATIONS.save(storage, index.latest, &observation)?;
+    OBSERVATION_INDEX.save(storage, &index)
+}
+
+/// Average price of the asset at `asset_index` over the `window_seconds` before
+/// `now`, `pools` being the current reserves
+pub fn average_price(
+    storage: &dyn Storage,
+    now: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+    asset_index: usize,
+    window_seconds: u64,
+) -> Result<Decimal256, ContractError> {
+    if window_seconds == 0 {
+        return Err(ContractError::InvalidTwapWindow {});
+    }
+
+    let index = OBSERVATION_INDEX
+        .may_load(storage)?
+        .ok_or(ContractError::TwapWindowTooLong { max_window: 0 })?;
+    let latest = OBSERVATIONS.load(storage, index.latest)?;
+    let oldest = OBSERVATIONS.load(storage, slot(&index, 0))?;
+
+    let max_window = now.saturating_sub(oldest.timestamp);
+    if window_seconds > max_window {
+        return Err(ContractError::TwapWindowTooLong { max_window });
+    }
+
+    let prices = spot_prices(storage, now, pools, decimals)?;
+    let start_time = now - window_seconds;
+    let start = if start_time >= latest.timestamp {
+        extrapolate(&latest, start_time, prices)
+    } else {
+        interpolate(storage, &index, start_time)?
+    };
+    let end = extrapolate(&latest, now, prices);
+
+    let cumulative = |observation: &Observation| match asset_index {
+        0 => observation.price0_cumulative,
+        _ => observation.price1_cumulative,
+    };
+
+    Ok(Decimal256::new(
+        cumulative(&end).wrapping_sub(cumulative(&start)) / Uint256::from(window_seconds),
+    ))
+}
+
+/// Decimal adjusted price of each asset quoted in the other one
+pub(crate) fn prices(pools: [Uint128; 2], decimals: [u8; 2]) -> (Decimal256, Decimal256) {
+    if pools[0].is_zero() || pools[1].is_zero() {
+        return (Decimal256::zero(), Decimal256::zero());
+    }
+
+    let pool0 = Uint256::from(pools[0]) * Uint256::from(10u128.pow(decimals[1].into()));
+    let pool1 = Uint256::from(pools[1]) * Uint256::from(10u128.pow(decimals[0].into()));
+
+    (
+        Decimal256::from_ratio(pool1, pool0),
+        Decimal256::from_ratio(pool0, pool1),
+    )
+}
+
+/// Spot price of each asset quoted in the other on the pair's curve at `now`,
+/// zero while a reserve is empty. Constant product prices are the reserve ratio.
+pub(crate) fn spot_prices(
+    storage: &dyn Storage,
+    now: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+) -> StdResult<(Decimal256, Decimal256)> {
+    if pools[0].is_zero() || pools[1].is_zero() {
+        return Ok((Decimal256::zero(), Decimal256::zero()));
+    }
+
+    if let Some(amp) = AMP.may_load(storage)? {
+        let amp = amp.current(now);
+        return Ok((
+            stableswap::spot_price(amp, pools[0], pools[1], decimals[0], decimals[1])?,
+            stableswap::spot_price(amp, pools[1], pools[0], decimals[1], decimals[0])?,
+        ));
+    }
+    if let Some(weights) = WEIGHTS.may_load(storage)? {
+        return Ok((
+            weighted::spot_price(
+                pools[0],
+                pools[1],
+                decimals[0],
+                decimals[1],
+                weights[0],
+                weights[1],
+            ),
+            weighted::spot_price(
+                pools[1],
+                pools[0],
+                decimals[1],
+                decimals[0],
+                weights[1],
+                weights[0],
+            ),
+        ));
+    }
+
+    Ok(prices(pools, decimals))
+}
+
+/// Cumulative prices at `time`, assuming `prices` were the spot prices since
+/// the observation
+fn extrapolate(
+    observation: &Observation,
+    time: u64,
+    (price0, price1): (Decimal256, Decimal256),
+) -> Observation {
+    let elapsed = Uint256::from(time - observation.timestamp);
+
+    Observation {
+        timestamp: time,
+        price0_cumulative: observation
+            .price0_cumulative
+            .wrapping_add(price0.atomics().wrapping_mul(elapsed)),
+        price1_cumulative: observation
+            .price1_cumulative
+            .wrapping_add(price1.atomics().wrapping_mul(elapsed)),
+    }
+}
+
+/// Cumulative prices at `time`, which must lie between the oldest and the
+/// newest observation
+fn interpolate(
+    storage: &dyn Storage,
+    index: &ObservationIndex,
+    time: u64,
+) -> StdResult<Observation> {
+    // binary search for the two consecutive observations around `time`
+    let (mut low, mut high) = (0u32, index.count - 1);
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if OBSERVATIONS.load(storage, slot(index, mid))?.timestamp <= time {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    let before = OBSERVATIONS.load(storage, slot(index, low))?;
+    let after = OBSERVATIONS.load(storage, slot(index, high))?;
+
+    // the price was constant in between, so the per second growth is exact
+    let span = Uint256::from(after.timestamp - before.timestamp);
+    let elapsed = Uint256::from(time - before.timestamp);
+    let advance = |before: Uint256, after: Uint256| {
+        before.wrapping_add((after.wrapping_sub(before) / span).wrapping_mul(elapsed))
+    };
+
+    Ok(Observation {
+        timestamp: time,
+        price0_cumulative: advance(before.price0_cumulative, after.price0_cumulative),
+        price1_cumulative: advance(before.price1_cumulative, after.price1_cumulative),
+    })
+}
+
+/// Storage slot of the observation at `position`, counted from the oldest
+fn slot(index: &ObservationIndex, position: u32) -> u32 {
+    (index.latest + MAX_OBSERVATIONS + 1 - index.count + position) % MAX_OBSERVATIONS
+}
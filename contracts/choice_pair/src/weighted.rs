@@ -0,0 +1,251 @@
+// Balancer style weighted invariant for two assets, `b0^w0 * b1^w1 = k`.
+// Balances are scaled to the larger of the two asset decimals, like
+// `stableswap`, and fractional powers use a binomial series.
+
+use crate::stableswap::{downscale, upscale};
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128, Uint256};
+use std::convert::{TryFrom, TryInto};
+
+/// Lowest weight an asset of a weighted pair may have
+pub const MIN_WEIGHT: Decimal = Decimal::percent(1);
+
+/// A swap may add at most this share of the offer pool
+const MAX_IN_RATIO: Decimal = Decimal::percent(50);
+/// A swap may take at most this share of the ask pool
+const MAX_OUT_RATIO: Decimal256 = Decimal256::raw(333_333_333_333_333_333);
+
+const POW_ITERATIONS: u32 = 128;
+
+/// Returns (return_amount, spread_amount, commission_amount) in ask decimals
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+    let offer_amount = upscale(offer_amount, offer_dec, target_dec);
+
+    if offer_amount > offer_pool.mul_floor(Decimal256::from(MAX_IN_RATIO)) {
+        return Err(StdError::generic_err(
+            "offer amount exceeds the weighted pool limit",
+        ));
+    }
+
+    // ask_pool * (1 - (offer_pool / (offer_pool + offer_amount))^(w_offer / w_ask))
+    let exponent = Decimal256::from(offer_weight) / Decimal256::from(ask_weight);
+    let base = Decimal256::from_ratio(offer_pool, offer_pool.checked_add(offer_amount)?);
+    let return_amount = ask_pool.mul_floor(Decimal256::one().checked_sub(pow(base, exponent)?)?);
+
+    // spot price is ask_pool / w_ask per offer_pool / w_offer
+    let spot_return =
+        offer_amount.mul_floor(Decimal256::from_ratio(ask_pool, offer_pool) * exponent);
+    let spread_amount = spot_return.saturating_sub(return_amount);
+    let commission_amount = return_amount.mul_ceil(commission_rate);
+    let return_amount = return_amount - commission_amount;
+
+    Ok((
+        downscale(return_amount, ask_dec, target_dec, false)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Returns (offer_amount, spread_amount, commission_amount), the offer amount
+/// in offer decimals and the others in ask decimals
+#[allow(clippy::too_many_arguments)]
+pub fn compute_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+    let ask_amount = upscale(ask_amount, ask_dec, target_dec);
+
+    // ask_amount / (1 - commission_rate), rounded up
+    let one = Decimal256::one().atomics();
+    let one_minus_commission = (Decimal256::one() - commission_rate).atomics();
+    let before_commission_deduction = ask_amount
+        .checked_mul(one)?
+        .checked_add(one_minus_commission - Uint256::one())?
+        .checked_div(one_minus_commission)?;
+
+    if before_commission_deduction > ask_pool.mul_floor(MAX_OUT_RATIO) {
+        return Err(StdError::generic_err(
+            "ask amount exceeds the weighted pool limit",
+        ));
+    }
+
+    // offer_pool * ((ask_pool / (ask_pool - ask_amount))^(w_ask / w_offer) - 1)
+    let exponent = Decimal256::from(ask_weight) / Decimal256::from(offer_weight);
+    let base = Decimal256::from_ratio(ask_pool, ask_pool - before_commission_deduction);
+    let offer_amount = offer_pool
+        .mul_ceil(pow(base, exponent)?.checked_sub(Decimal256::one())?)
+        .checked_add(Uint256::one())?;
+
+    let spot_return =
+        offer_amount.mul_floor(Decimal256::from_ratio(ask_pool, offer_pool) / exponent);
+    let spread_amount = spot_return.saturating_sub(before_commission_deduction);
+    let commission_amount = before_commission_deduction - ask_amount;
+
+    Ok((
+        downscale(offer_amount, offer_dec, target_dec, true)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Spot price of the offer asset quoted in the ask asset,
+/// `(ask_pool / w_ask) / (offer_pool / w_offer)` on the scaled pools
+pub fn spot_price(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_dec: u8,
+    ask_dec: u8,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+) -> Decimal256 {
+    let target_dec = offer_dec.max(ask_dec);
+    let offer_pool = upscale(offer_pool, offer_dec, target_dec);
+    let ask_pool = upscale(ask_pool, ask_dec, target_dec);
+
+    Decimal256::from_ratio(ask_pool, offer_pool) * Decimal256::from(offer_weight)
+        / Decimal256::from(ask_weight)
+}
+
+/// Offer amount, before commission, that lowers the spot price of the offer
+/// asset by `price_impact`. The price moves with `(offer_pool / new_offer_pool)`
+/// raised to `(w_offer + w_ask) / w_ask`, so the new offer pool is
+/// `offer_pool / (1 - price_impact)^(w_ask / (w_offer + w_ask))`.
+pub fn compute_depth(
+    offer_pool: Uint128,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+    price_impact: Decimal256,
+) -> StdResult<Uint128> {
+    let ask_weight = Decimal256::from(ask_weight);
+    let exponent = ask_weight / (Decimal256::from(offer_weight) + ask_weight);
+    let growth = Decimal256::one() / pow(Decimal256::one() - price_impact, exponent)?;
+
+    Ok(Uint256::from(offer_pool)
+        .mul_ceil(growth.checked_sub(Decimal256::one())?)
+        .try_into()?)
+}
+
+/// Largest amount a single swap may offer to the pool
+pub fn max_offer_amount(offer_pool: Uint128) -> Uint128 {
+    offer_pool.mul_floor(MAX_IN_RATIO)
+}
+
+/// Weighted geometric mean of the deposits, the LP supply minted by the first provision
+pub fn compute_initial_share(deposits: [Uint128; 2], weights: [Decimal; 2]) -> StdResult<Uint128> {
+    let mut share = Decimal256::one();
+    for i in 0..2 {
+        let deposit = Decimal256::from_ratio(deposits[i], 1u8);
+        share = share.checked_mul(pow(deposit, Decimal256::from(weights[i]))?)?;
+    }
+
+    Ok(share.to_uint_floor().try_into()?)
+}
+
+/// Ratio of the invariant after a change of balances to the invariant before it
+pub fn invariant_ratio(
+    pools_before: [Uint128; 2],
+    pools_after: [Uint128; 2],
+    weights: [Decimal; 2],
+) -> StdResult<Decimal256> {
+    let mut ratio = Decimal256::one();
+    for i in 0..2 {
+        let balance_ratio = Decimal256::from_ratio(pools_after[i], pools_before[i]);
+        ratio = ratio.checked_mul(pow(balance_ratio, Decimal256::from(weights[i]))?)?;
+    }
+
+    Ok(ratio)
+}
+
+/// `base^exponent` for any base, splitting the exponent into an integer power
+/// and a fractional power that is approximated with a binomial series
+pub fn pow(base: Decimal256, exponent: Decimal256) -> StdResult<Decimal256> {
+    if base.is_zero() {
+        return Ok(if exponent.is_zero() {
+            Decimal256::one()
+        } else {
+            Decimal256::zero()
+        });
+    }
+
+    // the series converges quickly for bases close to one
+    if base < Decimal256::percent(50) {
+        return Ok(Decimal256::one() / pow(Decimal256::one() / base, exponent)?);
+    }
+
+    let (mut base, mut exponent) = (base, exponent);
+    while base >= Decimal256::percent(150) {
+        base = base.sqrt();
+        exponent = exponent.checked_mul(Decimal256::percent(200))?;
+    }
+
+    let whole = exponent.floor();
+    let whole_exponent: Uint128 = whole.to_uint_floor().try_into()?;
+    let whole_exponent = u32::try_from(whole_exponent.u128())
+        .map_err(|_| StdError::generic_err("weighted pool exponent overflow"))?;
+    let whole_pow = base.checked_pow(whole_exponent)?;
+    let fraction = exponent - whole;
+    if fraction.is_zero() {
+        return Ok(whole_pow);
+    }
+
+    Ok(whole_pow.checked_mul(pow_approx(base, fraction)?)?)
+}
+
+/// `(1 + x)^a = 1 + a*x + a(a-1)/2!*x^2 + ...` for 0.5 <= base < 1.5 and 0 < a < 1
+fn pow_approx(base: Decimal256, exponent: Decimal256) -> StdResult<Decimal256> {
+    let one = Decimal256::one();
+    let (x, x_negative) = if base >= one {
+        (base - one, false)
+    } else {
+        (one - base, true)
+    };
+
+    let mut term = one;
+    let mut sum_positive = one;
+    let mut sum_negative = Decimal256::zero();
+    let mut negative = false;
+    for k in 1..=POW_ITERATIONS {
+        let k = Decimal256::from_ratio(k, 1u8);
+        let previous = k - one;
+        let (c, c_negative) = if exponent >= previous {
+            (exponent - previous, false)
+        } else {
+            (previous - exponent, true)
+        };
+
+        term = term.checked_mul(c)?.checked_mul(x)? / k;
+        if term.is_zero() {
+            break;
+        }
+
+        negative ^= c_negative ^ x_negative;
+        if negative {
+            sum_negative += term;
+        } else {
+            sum_positive += term;
+        }
+    }
+
+    Ok(sum_positive.checked_sub(sum_negative)?)
+}
@@ -0,0 +1,833 @@
+use crate::error::ContractError;
+use crate::math::{self, MAX_AMP};
+use crate::state::{FACTORY, POOL_INFO};
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Decimal256, Deps,
+    DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128, Uint256, WasmMsg,
+};
+
+use choice::asset::{Asset, AssetInfo, PoolInfo, PoolInfoRaw};
+use choice::pair::{ReverseSimulationResponse, SimulationResponse};
+use choice::pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg, MAX_POOL_ASSETS,
+    MIN_POOL_ASSETS,
+};
+use choice::querier::{query_fee_split, query_token_factory_denom_total_supply};
+use choice::send_to_auction::ExecuteMsg as BurnAuctionExecuteMsg;
+use choice::util::migrate_version;
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use std::convert::TryInto;
+
+use injective_cosmwasm::msg::{
+    create_burn_tokens_msg, create_mint_tokens_msg, create_new_denom_msg,
+    create_set_token_metadata_msg,
+};
+use injective_cosmwasm::query::InjectiveQueryWrapper;
+use injective_cosmwasm::InjectiveMsgWrapper;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:choice-pool";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    if msg.commission_bps == 0 {
+        return Err(StdError::generic_err(
+            "commission_bps must be greater than zero",
+        ));
+    }
+
+    let n = msg.asset_infos.len();
+    if !(MIN_POOL_ASSETS..=MAX_POOL_ASSETS).contains(&n) || msg.asset_decimals.len() != n {
+        return Err(StdError::generic_err(format!(
+            "pools require between {} and {} assets with their decimals",
+            MIN_POOL_ASSETS, MAX_POOL_ASSETS
+        )));
+    }
+
+    for (i, asset_info) in msg.asset_infos.iter().enumerate() {
+        if msg.asset_infos[i + 1..].contains(asset_info) {
+            return Err(StdError::generic_err("same asset"));
+        }
+    }
+
+    if msg.amp == 0 || msg.amp > MAX_AMP {
+        return Err(StdError::generic_err(format!(
+            "pools require an amp between 1 and {}",
+            MAX_AMP
+        )));
+    }
+
+    let subdenom = "lp".to_string();
+    let lp_denom = format!("factory/{}/{}", env.contract.address, subdenom);
+
+    let pool_info = &PoolInfoRaw {
+        contract_addr: deps.api.addr_canonicalize(env.contract.address.as_str())?,
+        liquidity_token: lp_denom.clone(),
+        asset_infos: msg
+            .asset_infos
+            .iter()
+            .map(|asset_info| asset_info.to_raw(deps.api))
+            .collect::<StdResult<_>>()?,
+        asset_decimals: msg.asset_decimals,
+        burn_address: deps.api.addr_canonicalize(&msg.burn_address)?,
+        fee_wallet_address: deps.api.addr_canonicalize(&msg.fee_wallet_address)?,
+        commission_bps: msg.commission_bps,
+        amp: msg.amp,
+    };
+
+    POOL_INFO.save(deps.storage, pool_info)?;
+    FACTORY.save(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+    )?;
+
+    let create_msg = create_new_denom_msg(env.contract.address.to_string(), subdenom);
+
+    let metadata_msg = create_set_token_metadata_msg(
+        lp_denom.clone(),
+        "choice liquidity token".to_string(),
+        "uLP".to_string(),
+        6,
+    );
+
+    Ok(Response::new()
+        .add_messages(vec![create_msg, metadata_msg])
+        .add_attribute("lp_denom", lp_denom))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            min_lp,
+            receiver,
+            deadline,
+        } => provide_liquidity(deps, env, info, assets, min_lp, receiver, deadline),
+        ExecuteMsg::WithdrawLiquidity {
+            amount,
+            min_assets,
+            deadline,
+        } => withdraw_liquidity(deps, env, info, amount, min_assets, deadline),
+        ExecuteMsg::Swap {
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+        } => {
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(&to_addr)?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+                deadline,
+            )
+        }
+    }
+}
+
+pub fn receive_cw20(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    match from_json(&cw20_msg.msg)? {
+        Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+        } => {
+            // only the CW20 assets of the pool can execute the receive hook
+            let offer_info = AssetInfo::Token {
+                contract_addr: info.sender.to_string(),
+            };
+            let offer_info_raw = offer_info.to_raw(deps.api)?;
+            let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+            if !pool_info
+                .asset_infos
+                .iter()
+                .any(|asset_info| asset_info.equal(&offer_info_raw))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: offer_info,
+                    amount: cw20_msg.amount,
+                },
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+                deadline,
+            )
+        }
+    }
+}
+
+/// CONTRACT - should approve contract to use the amount of token
+pub fn provide_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    min_lp: Option<Uint128>,
+    receiver: Option<String>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    for asset in assets.iter() {
+        asset.assert_sent_native_token_balance(&info)?;
+    }
+
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+    let mut pools: Vec<Asset> =
+        pool_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    let mut deposits: Vec<Uint128> = vec![Uint128::zero(); pools.len()];
+    for asset in assets.iter() {
+        let index = asset_index(&pools, &asset.info)?;
+        if !deposits[index].is_zero() {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        deposits[index] = asset.amount;
+    }
+
+    for (i, pool) in pools.iter_mut().enumerate() {
+        if pool.is_native_token() {
+            // If the asset is native token, balance is already increased
+            // To calculated properly we should subtract user deposit from the pool
+            pool.amount = pool.amount.checked_sub(deposits[i])?;
+        }
+    }
+
+    let old_pools: Vec<Uint128> = pools.iter().map(|pool| pool.amount).collect();
+    let new_pools: Vec<Uint128> = old_pools
+        .iter()
+        .zip(deposits.iter())
+        .map(|(pool, deposit)| pool.checked_add(*deposit))
+        .collect::<Result<_, _>>()?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pool_info.liquidity_token.clone())?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let share: Uint128 = if total_share.is_zero() {
+        if deposits.iter().any(|deposit| deposit.is_zero()) {
+            return Err(ContractError::IncompleteInitialProvision {});
+        }
+
+        // Initial share = invariant of the deposits
+        let share: Uint128 =
+            math::compute_invariant(pool_info.amp, &new_pools, &pool_info.asset_decimals)?
+                .try_into()?;
+
+        // Mint the minimum liquidity tokens to lock forever (to protect the pool)
+        messages.push(create_mint_tokens_msg(
+            env.contract.address.clone(),
+            Coin {
+                denom: pool_info.liquidity_token.clone(),
+                amount: MINIMUM_LIQUIDITY_AMOUNT.into(),
+            },
+            env.contract.address.to_string(),
+        ));
+
+        share
+            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.into())
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {
+                min_lp_token: MINIMUM_LIQUIDITY_AMOUNT.to_string(),
+                given_lp: share.to_string(),
+            })?
+    } else {
+        let decimals = &pool_info.asset_decimals;
+        let d0 = math::compute_invariant(pool_info.amp, &old_pools, decimals)?;
+        let d1 = math::compute_invariant(pool_info.amp, &new_pools, decimals)?;
+        let adjusted_pools = math::imbalance_adjusted_pools(
+            &old_pools,
+            &new_pools,
+            d0,
+            d1,
+            imbalance_fee_rate(pool_info.commission_bps, pools.len()),
+        )?;
+        let d2 = math::compute_invariant(pool_info.amp, &adjusted_pools, decimals)?;
+
+        // the imbalance fee stays in the pool for the existing liquidity providers
+        Uint256::from(total_share)
+            .multiply_ratio(d2.saturating_sub(d0), d0)
+            .try_into()?
+    };
+
+    // prevent providing free token
+    if share.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    if let Some(min_lp) = min_lp {
+        if share < min_lp {
+            return Err(ContractError::MinLpAssertion {
+                min_lp: min_lp.to_string(),
+                share: share.to_string(),
+            });
+        }
+    }
+
+    // the total lp token cannot exceed the max value of a Uint128
+    if total_share.checked_add(share).is_err() {
+        return Err(ContractError::LpSupplyOverflow {});
+    }
+
+    for (i, pool) in pools.iter().enumerate() {
+        if let AssetInfo::Token { contract_addr, .. } = &pool.info {
+            if !deposits[i].is_zero() {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i],
+                    })?,
+                    funds: vec![],
+                }));
+            }
+        }
+    }
+
+    // mint LP token to receiver
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    messages.push(create_mint_tokens_msg(
+        env.contract.address.clone(),
+        Coin {
+            denom: pool_info.liquidity_token.clone(),
+            amount: share,
+        },
+        receiver.to_string(),
+    ));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "provide_liquidity"),
+        ("sender", info.sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("assets", &join_assets(&assets)),
+        ("share", &share.to_string()),
+    ]))
+}
+
+pub fn withdraw_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    min_assets: Option<Vec<Asset>>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+
+    // The transaction must include the LP token with the exact withdrawal amount
+    if !info
+        .funds
+        .iter()
+        .any(|coin| coin.denom == pool_info.liquidity_token && coin.amount == amount)
+    {
+        return Err(ContractError::InvalidLiquidityFunds {});
+    }
+
+    let contract_addr = env.contract.address.clone();
+    let pools: Vec<Asset> =
+        pool_info.query_pools(&deps.querier, deps.api, contract_addr.clone())?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pool_info.liquidity_token.clone())?;
+
+    let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
+    let refund_assets: Vec<Asset> = pools
+        .iter()
+        .map(|a| Asset {
+            info: a.info.clone(),
+            amount: a.amount.mul_floor(share_ratio),
+        })
+        .collect();
+
+    assert_minimum_assets(&refund_assets, min_assets)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for refund_asset in refund_assets.iter() {
+        if !refund_asset.amount.is_zero() {
+            messages.push(refund_asset.clone().into_msg(info.sender.clone())?);
+        }
+    }
+
+    // burn liquidity token
+    messages.push(create_burn_tokens_msg(
+        contract_addr,
+        Coin {
+            denom: pool_info.liquidity_token.clone(),
+            amount,
+        },
+    ));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "withdraw_liquidity"),
+        ("sender", info.sender.as_str()),
+        ("withdrawn_share", &amount.to_string()),
+        ("refund_assets", &join_assets(&refund_assets)),
+    ]))
+}
+
+// CONTRACT - a user must do token approval
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+    let mut pools: Vec<Asset> =
+        pool_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    let offer_index = asset_index(&pools, &offer_asset.info)?;
+    let ask_index = asset_index(&pools, &ask_asset_info)?;
+    if offer_index == ask_index {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // The asset balance is already increased
+    // To calculated properly we should subtract user deposit from the pool
+    pools[offer_index].amount = pools[offer_index].amount.checked_sub(offer_asset.amount)?;
+
+    let offer_amount = offer_asset.amount;
+    let (return_amount, spread_amount, commission_amount) = math::compute_swap(
+        pool_info.amp,
+        &pools.iter().map(|pool| pool.amount).collect::<Vec<_>>(),
+        &pool_info.asset_decimals,
+        offer_index,
+        ask_index,
+        offer_amount,
+        commission_rate(pool_info.commission_bps),
+    )?;
+
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+
+    // check max spread limit if exist
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        &offer_asset,
+        &return_asset,
+        spread_amount,
+        pool_info.asset_decimals[offer_index],
+        pool_info.asset_decimals[ask_index],
+    )?;
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let fee_split = query_fee_split(&deps.querier, factory_addr, env.contract.address)?;
+
+    let fee_wallet_amount = commission_amount.mul_floor(fee_split.fee_wallet);
+    let burn_amount = commission_amount.mul_floor(fee_split.burn);
+    let lp_amount = commission_amount
+        .checked_sub(fee_wallet_amount)?
+        .checked_sub(burn_amount)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    if !return_amount.is_zero() {
+        messages.push(return_asset.into_msg(receiver.clone())?);
+    }
+
+    messages.extend(fee_messages(
+        deps.api,
+        &pool_info,
+        &ask_asset_info,
+        burn_amount,
+        fee_wallet_amount,
+    )?);
+
+    // new pool amounts
+    let offer_pool_post = pools[offer_index].amount.checked_add(offer_amount)?;
+    let ask_pool_post = pools[ask_index]
+        .amount
+        .checked_sub(return_amount)?
+        .checked_sub(fee_wallet_amount)?
+        .checked_sub(burn_amount)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "swap"),
+        ("sender", sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("offer_asset", &offer_asset.info.to_string()),
+        ("ask_asset", &ask_asset_info.to_string()),
+        ("offer_amount", &offer_amount.to_string()),
+        ("return_amount", &return_amount.to_string()),
+        ("spread_amount", &spread_amount.to_string()),
+        ("commission_amount", &commission_amount.to_string()),
+        ("burn_amount", &burn_amount.to_string()),
+        ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+        ("pool_amount", &lp_amount.to_string()),
+        ("offer_pool_balance", &offer_pool_post.to_string()),
+        ("ask_pool_balance", &ask_pool_post.to_string()),
+    ]))
+}
+
+/// Messages sending the protocol share of a commission to the burn handler and the fee wallet
+fn fee_messages(
+    api: &dyn Api,
+    pool_info: &PoolInfoRaw,
+    asset_info: &AssetInfo,
+    burn_amount: Uint128,
+    fee_wallet_amount: Uint128,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+
+    if !burn_amount.is_zero() {
+        let burn_asset = Asset {
+            info: asset_info.clone(),
+            amount: burn_amount,
+        };
+        let burn_handler_address = api.addr_humanize(&pool_info.burn_address)?;
+
+        if let AssetInfo::NativeToken { denom } = &burn_asset.info {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: burn_handler_address.to_string(),
+                msg: to_json_binary(&BurnAuctionExecuteMsg::SendNative {
+                    asset: burn_asset.clone(),
+                })?,
+                funds: vec![Coin {
+                    denom: denom.clone(),
+                    amount: burn_amount,
+                }],
+            }));
+        } else if let AssetInfo::Token { contract_addr } = &burn_asset.info {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: burn_handler_address.to_string(),
+                    amount: burn_amount,
+                    msg: Binary::default(),
+                })?,
+                funds: vec![],
+            }));
+        }
+    }
+
+    if !fee_wallet_amount.is_zero() {
+        let fee_wallet_asset = Asset {
+            info: asset_info.clone(),
+            amount: fee_wallet_amount,
+        };
+        let fee_wallet_address = api.addr_humanize(&pool_info.fee_wallet_address)?;
+        messages.push(fee_wallet_asset.into_msg(fee_wallet_address)?);
+    }
+
+    Ok(messages)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(
+    deps: Deps<InjectiveQueryWrapper>,
+    _env: Env,
+    msg: QueryMsg,
+) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::PoolInfo {} => Ok(to_json_binary(&query_pool_info(deps)?)?),
+        QueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps)?)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => Ok(to_json_binary(&query_simulation(
+            deps,
+            offer_asset,
+            ask_asset_info,
+        )?)?),
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => Ok(to_json_binary(&query_reverse_simulation(
+            deps,
+            offer_asset_info,
+            ask_asset,
+        )?)?),
+    }
+}
+
+pub fn query_pool_info(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolInfo, ContractError> {
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+    let factory_addr = deps.api.addr_humanize(&FACTORY.load(deps.storage)?)?;
+    let pool_addr = deps.api.addr_humanize(&pool_info.contract_addr)?;
+    let fee_split = query_fee_split(&deps.querier, factory_addr, pool_addr)?;
+
+    Ok(pool_info.to_normal(deps.api, fee_split)?)
+}
+
+pub fn query_pool(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolResponse, ContractError> {
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+    let contract_addr = deps.api.addr_humanize(&pool_info.contract_addr)?;
+    let assets: Vec<Asset> = pool_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pool_info.liquidity_token.clone())?;
+
+    Ok(PoolResponse {
+        assets,
+        total_share,
+    })
+}
+
+pub fn query_simulation(
+    deps: Deps<InjectiveQueryWrapper>,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+) -> Result<SimulationResponse, ContractError> {
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+
+    let contract_addr = deps.api.addr_humanize(&pool_info.contract_addr)?;
+    let pools: Vec<Asset> = pool_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+
+    let offer_index = asset_index(&pools, &offer_asset.info)?;
+    let ask_index = asset_index(&pools, &ask_asset_info)?;
+    if offer_index == ask_index {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let (return_amount, spread_amount, commission_amount) = math::compute_swap(
+        pool_info.amp,
+        &pools.iter().map(|pool| pool.amount).collect::<Vec<_>>(),
+        &pool_info.asset_decimals,
+        offer_index,
+        ask_index,
+        offer_asset.amount,
+        commission_rate(pool_info.commission_bps),
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        commission_bps: pool_info.commission_bps,
+    })
+}
+
+pub fn query_reverse_simulation(
+    deps: Deps<InjectiveQueryWrapper>,
+    offer_asset_info: AssetInfo,
+    ask_asset: Asset,
+) -> Result<ReverseSimulationResponse, ContractError> {
+    let pool_info: PoolInfoRaw = POOL_INFO.load(deps.storage)?;
+
+    let contract_addr = deps.api.addr_humanize(&pool_info.contract_addr)?;
+    let pools: Vec<Asset> = pool_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+
+    let offer_index = asset_index(&pools, &offer_asset_info)?;
+    let ask_index = asset_index(&pools, &ask_asset.info)?;
+    if offer_index == ask_index {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let (offer_amount, spread_amount, commission_amount) = math::compute_offer_amount(
+        pool_info.amp,
+        &pools.iter().map(|pool| pool.amount).collect::<Vec<_>>(),
+        &pool_info.asset_decimals,
+        offer_index,
+        ask_index,
+        ask_asset.amount,
+        commission_rate(pool_info.commission_bps),
+    )?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount,
+        spread_amount,
+        commission_amount,
+        commission_bps: pool_info.commission_bps,
+    })
+}
+
+/// Converts a fee tier in basis points into the commission rate used by the swap math
+pub fn commission_rate(commission_bps: u16) -> Decimal256 {
+    Decimal256::bps(commission_bps.into())
+}
+
+/// Fee charged on the imbalanced part of a provision, `commission * n / (4 * (n - 1))`
+/// like Curve, so that providing and withdrawing can't be used to swap for free
+pub fn imbalance_fee_rate(commission_bps: u16, n_assets: usize) -> Decimal256 {
+    let n = n_assets as u128;
+    commission_rate(commission_bps) * Decimal256::from_ratio(n, 4 * (n - 1))
+}
+
+fn asset_index(pools: &[Asset], asset_info: &AssetInfo) -> Result<usize, ContractError> {
+    pools
+        .iter()
+        .position(|pool| pool.info.equal(asset_info))
+        .ok_or(ContractError::AssetMismatch {})
+}
+
+fn join_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// If `belief_price` and `max_spread` both are given,
+/// we compute new spread else we just use the pool
+/// spread to check `max_spread`
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_asset: &Asset,
+    return_asset: &Asset,
+    spread_amount: Uint128,
+    offer_decimal: u8,
+    return_decimal: u8,
+) -> Result<(), ContractError> {
+    // compare the amounts at the larger of the two decimals
+    let target_dec = offer_decimal.max(return_decimal);
+    let scale = |amount: Uint128, dec: u8| {
+        Uint256::from(amount) * Uint256::from(10u128.pow((target_dec - dec) as u32))
+    };
+    let offer_amount = scale(offer_asset.amount, offer_decimal);
+    let return_amount = scale(return_asset.amount, return_decimal);
+    let spread_amount = scale(spread_amount, return_decimal);
+
+    if let (Some(max_spread), Some(belief_price)) = (max_spread, belief_price) {
+        let expected_return =
+            offer_amount.mul_floor(Decimal256::one() / Decimal256::from(belief_price));
+        let spread_amount = expected_return.saturating_sub(return_amount);
+
+        if return_amount < expected_return
+            && Decimal256::from_ratio(spread_amount, expected_return) > Decimal256::from(max_spread)
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if let Some(max_spread) = max_spread {
+        if Decimal256::from_ratio(spread_amount, return_amount + spread_amount)
+            > Decimal256::from(max_spread)
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+pub fn assert_minimum_assets(
+    assets: &[Asset],
+    min_assets: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets.unwrap_or_default() {
+        let amount = assets
+            .iter()
+            .find(|asset| asset.info == min_asset.info)
+            .map(|asset| asset.amount)
+            .unwrap_or_default();
+
+        if amount < min_asset.amount {
+            return Err(ContractError::MinAmountAssertion {
+                min_asset: min_asset.to_string(),
+                asset: Asset {
+                    info: min_asset.info.clone(),
+                    amount,
+                }
+                .to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn assert_deadline(blocktime: u64, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if blocktime >= deadline {
+            return Err(ContractError::ExpiredDeadline {});
+        }
+    }
+
+    Ok(())
+}
+
+const TARGET_CONTRACT_VERSION: &str = "1.1.2";
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    migrate_version(
+        deps,
+        TARGET_CONTRACT_VERSION,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+    )?;
+
+    Ok(Response::default())
+}
@@ -0,0 +1,229 @@
+// StableSwap (Curve) invariant for any number of assets, the N coin form of
+// `choice_pair::stableswap`. Balances are scaled to the largest asset decimals
+// of the pool before solving.
+
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256};
+use std::convert::TryInto;
+
+/// Highest amplification coefficient a pool may use
+pub const MAX_AMP: u64 = 1_000_000;
+
+const ITERATIONS: u8 = 64;
+
+/// Returns (return_amount, spread_amount, commission_amount) in ask decimals
+pub fn compute_swap(
+    amp: u64,
+    pools: &[Uint128],
+    decimals: &[u8],
+    offer_index: usize,
+    ask_index: usize,
+    offer_amount: Uint128,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = target_decimals(decimals);
+    let mut xp = upscale_all(pools, decimals, target_dec);
+    let offer_amount = upscale(offer_amount, decimals[offer_index], target_dec);
+
+    let d = compute_d(amp, &xp)?;
+    let ask_pool = xp[ask_index];
+    xp[offer_index] = xp[offer_index].checked_add(offer_amount)?;
+    let new_ask_pool = compute_y(amp, &xp, ask_index, d)?;
+
+    // keep one unit in the pool to cover rounding in the newton iterations
+    let return_amount = ask_pool
+        .saturating_sub(new_ask_pool)
+        .saturating_sub(Uint256::one());
+
+    // the invariant prices all assets 1:1 when balanced
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+    let commission_amount = return_amount.mul_ceil(commission_rate);
+    let return_amount = return_amount - commission_amount;
+
+    let ask_dec = decimals[ask_index];
+    Ok((
+        downscale(return_amount, ask_dec, target_dec, false)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Returns (offer_amount, spread_amount, commission_amount), the offer amount
+/// in offer decimals and the others in ask decimals
+pub fn compute_offer_amount(
+    amp: u64,
+    pools: &[Uint128],
+    decimals: &[u8],
+    offer_index: usize,
+    ask_index: usize,
+    ask_amount: Uint128,
+    commission_rate: Decimal256,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let target_dec = target_decimals(decimals);
+    let mut xp = upscale_all(pools, decimals, target_dec);
+    let ask_amount = upscale(ask_amount, decimals[ask_index], target_dec);
+
+    // ask_amount / (1 - commission_rate), rounded up
+    let one = Decimal256::one().atomics();
+    let one_minus_commission = (Decimal256::one() - commission_rate).atomics();
+    let before_commission_deduction = ask_amount
+        .checked_mul(one)?
+        .checked_add(one_minus_commission - Uint256::one())?
+        .checked_div(one_minus_commission)?;
+
+    if before_commission_deduction >= xp[ask_index] {
+        return Err(StdError::generic_err("ask amount exceeds the pool"));
+    }
+
+    let d = compute_d(amp, &xp)?;
+    let offer_pool = xp[offer_index];
+    xp[ask_index] -= before_commission_deduction;
+    let new_offer_pool = compute_y(amp, &xp, offer_index, d)?;
+
+    let offer_amount = new_offer_pool
+        .saturating_sub(offer_pool)
+        .checked_add(Uint256::one())?;
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+    let commission_amount = before_commission_deduction - ask_amount;
+
+    let ask_dec = decimals[ask_index];
+    Ok((
+        downscale(offer_amount, decimals[offer_index], target_dec, true)?,
+        downscale(spread_amount, ask_dec, target_dec, false)?,
+        downscale(commission_amount, ask_dec, target_dec, false)?,
+    ))
+}
+
+/// Invariant `D` of the pools, scaled to the largest asset decimals
+pub fn compute_invariant(amp: u64, pools: &[Uint128], decimals: &[u8]) -> StdResult<Uint256> {
+    let target_dec = target_decimals(decimals);
+    compute_d(amp, &upscale_all(pools, decimals, target_dec))
+}
+
+/// Pool balances after charging the imbalance fee of a provision that moved
+/// the invariant from `d0` at `old_pools` to `d1` at `new_pools`. Each balance
+/// pays `fee_rate` on its distance from the balance a proportional deposit
+/// would have left.
+pub fn imbalance_adjusted_pools(
+    old_pools: &[Uint128],
+    new_pools: &[Uint128],
+    d0: Uint256,
+    d1: Uint256,
+    fee_rate: Decimal256,
+) -> StdResult<Vec<Uint128>> {
+    old_pools
+        .iter()
+        .zip(new_pools.iter())
+        .map(|(old, new)| {
+            let ideal = Uint256::from(*old).multiply_ratio(d1, d0);
+            let fee = ideal.abs_diff(Uint256::from(*new)).mul_ceil(fee_rate);
+            let fee: Uint128 = fee.try_into()?;
+            Ok(new.saturating_sub(fee))
+        })
+        .collect()
+}
+
+/// Solves the invariant D for the balances with newton's method
+fn compute_d(amp: u64, xp: &[Uint256]) -> StdResult<Uint256> {
+    let n = Uint256::from(xp.len() as u64);
+    let sum = xp
+        .iter()
+        .try_fold(Uint256::zero(), |sum, x| sum.checked_add(*x))?;
+    if sum.is_zero() {
+        return Ok(Uint256::zero());
+    }
+
+    let ann = Uint256::from(amp).checked_mul(n)?;
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        // d_p = d^(n+1) / (n^n * prod(xp))
+        let mut d_p = d;
+        for x in xp {
+            d_p = d_p.checked_mul(d)?.checked_div(x.checked_mul(n)?)?;
+        }
+
+        let d_prev = d;
+        d = ann
+            .checked_mul(sum)?
+            .checked_add(d_p.checked_mul(n)?)?
+            .checked_mul(d)?
+            .checked_div(
+                (ann - Uint256::one())
+                    .checked_mul(d)?
+                    .checked_add((n + Uint256::one()).checked_mul(d_p)?)?,
+            )?;
+
+        if d.abs_diff(d_prev) <= Uint256::one() {
+            return Ok(d);
+        }
+    }
+
+    Err(StdError::generic_err(
+        "stable swap invariant did not converge",
+    ))
+}
+
+/// Balance of asset `index` that keeps the invariant at `d` given the other
+/// balances of `xp`, the current balance at `index` is ignored
+fn compute_y(amp: u64, xp: &[Uint256], index: usize, d: Uint256) -> StdResult<Uint256> {
+    let n = Uint256::from(xp.len() as u64);
+    let ann = Uint256::from(amp).checked_mul(n)?;
+
+    // c = d^(n+1) / (n^n * prod(x_j) * ann), b = sum(x_j) + d / ann, for j != index
+    let mut c = d;
+    let mut sum = Uint256::zero();
+    for (j, x) in xp.iter().enumerate() {
+        if j == index {
+            continue;
+        }
+
+        sum = sum.checked_add(*x)?;
+        c = c.checked_mul(d)?.checked_div(x.checked_mul(n)?)?;
+    }
+    let c = c.checked_mul(d)?.checked_div(ann.checked_mul(n)?)?;
+    let b = sum.checked_add(d.checked_div(ann)?)?;
+
+    let mut y = d;
+    for _ in 0..ITERATIONS {
+        let y_prev = y;
+        y = y.checked_mul(y)?.checked_add(c)?.checked_div(
+            y.checked_mul(Uint256::from(2u8))?
+                .checked_add(b)?
+                .checked_sub(d)?,
+        )?;
+
+        if y.abs_diff(y_prev) <= Uint256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(StdError::generic_err(
+        "stable swap invariant did not converge",
+    ))
+}
+
+fn target_decimals(decimals: &[u8]) -> u8 {
+    decimals.iter().copied().max().unwrap_or_default()
+}
+
+fn upscale_all(pools: &[Uint128], decimals: &[u8], target_dec: u8) -> Vec<Uint256> {
+    pools
+        .iter()
+        .zip(decimals.iter())
+        .map(|(pool, dec)| upscale(*pool, *dec, target_dec))
+        .collect()
+}
+
+fn upscale(amount: Uint128, dec: u8, target_dec: u8) -> Uint256 {
+    Uint256::from(amount) * Uint256::from(10u128.pow((target_dec - dec) as u32))
+}
+
+fn downscale(amount: Uint256, dec: u8, target_dec: u8, round_up: bool) -> StdResult<Uint128> {
+    let factor = Uint256::from(10u128.pow((target_dec - dec) as u32));
+    let amount = if round_up {
+        amount.checked_add(factor - Uint256::one())? / factor
+    } else {
+        amount / factor
+    };
+
+    Ok(amount.try_into()?)
+}
@@ -0,0 +1,8 @@
+use choice::asset::PoolInfoRaw;
+use cosmwasm_std::CanonicalAddr;
+use cw_storage_plus::Item;
+
+pub const POOL_INFO: Item<PoolInfoRaw> = Item::new("pool_info");
+
+/// Factory that instantiated the pool, queried for the commission split
+pub const FACTORY: Item<CanonicalAddr> = Item::new("factory");
@@ -0,0 +1,596 @@
+use crate::contract::{
+    execute, imbalance_fee_rate, instantiate, query_pool, query_pool_info,
+    query_reverse_simulation, query_simulation,
+};
+use crate::error::ContractError;
+use crate::math;
+
+use choice::asset::{Asset, AssetInfo, PoolInfo};
+use choice::factory::FeeSplit;
+use choice::mock_querier::{mock_dependencies, WasmMockQuerier};
+use choice::pool::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse};
+use cosmwasm_std::testing::{message_info, mock_env, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coins, to_json_binary, Coin, CosmosMsg, Decimal, Decimal256, OwnedDeps, StdError, SubMsg,
+    Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use injective_cosmwasm::{create_burn_tokens_msg, create_mint_tokens_msg};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+
+fn native(denom: &str) -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    }
+}
+
+fn pool_instantiate_msg(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos: vec![
+            native("uusdt"),
+            native("uusdc"),
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("usde").to_string(),
+            },
+        ],
+        asset_decimals: vec![6u8, 6u8, 18u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        amp: 100,
+    }
+}
+
+/// Pool of uusdt, uusdc and the usde token, holding `balances` in that order
+fn pool_deps(
+    balances: [u128; 3],
+    total_share: u128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin::new(balances[0], "uusdt"),
+            Coin::new(balances[1], "uusdc"),
+        ],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("usde").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(balances[2]))],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        Uint128::from(total_share),
+    )]);
+
+    let msg = pool_instantiate_msg(&deps);
+    let info = message_info(&deps.api.addr_make("factory"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+#[test]
+fn proper_initialization() {
+    let deps = pool_deps([0, 0, 0], 0);
+
+    let pool_info: PoolInfo = query_pool_info(deps.as_ref()).unwrap();
+    assert_eq!(
+        pool_info.liquidity_token,
+        format!("factory/{}/lp", MOCK_CONTRACT_ADDR)
+    );
+    assert_eq!(pool_info.asset_infos.len(), 3);
+    assert_eq!(pool_info.asset_decimals, vec![6u8, 6u8, 18u8]);
+    assert_eq!(pool_info.amp, 100);
+    assert_eq!(pool_info.fee_split, FeeSplit::default());
+
+    let info = message_info(&deps.api.addr_make("factory"), &[]);
+
+    // two asset markets are pairs
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = pool_instantiate_msg(&deps);
+    msg.asset_infos.pop();
+    msg.asset_decimals.pop();
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "pools require between 3 and 8 assets with their decimals"
+        ))
+    );
+
+    let mut msg = pool_instantiate_msg(&deps);
+    msg.asset_infos[2] = native("uusdt");
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(res, Err(StdError::generic_err("same asset")));
+
+    let mut msg = pool_instantiate_msg(&deps);
+    msg.amp = 0;
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "pools require an amp between 1 and 1000000"
+        ))
+    );
+}
+
+#[test]
+fn compute_swap() {
+    let pools = [
+        Uint128::from(1_000_000_000_000u128),
+        Uint128::from(1_000_000_000_000u128),
+        Uint128::from(1_000_000_000_000_000_000_000_000u128),
+    ];
+    let decimals = [6u8, 6u8, 18u8];
+    let commission_rate = Decimal256::permille(3);
+
+    // balanced pools trade close to 1:1 minus the commission
+    let (return_amount, spread_amount, commission_amount) = math::compute_swap(
+        100,
+        &pools,
+        &decimals,
+        0,
+        1,
+        Uint128::from(1_000_000_000u128),
+        commission_rate,
+    )
+    .unwrap();
+    assert_eq!(return_amount, Uint128::from(996_990_128u128));
+    assert_eq!(spread_amount, Uint128::from(9_900u128));
+    assert_eq!(commission_amount, Uint128::from(2_999_970u128));
+
+    // the 18 decimals asset returns the same amount in its own decimals
+    let (return_18, _, commission_18) = math::compute_swap(
+        100,
+        &pools,
+        &decimals,
+        0,
+        2,
+        Uint128::from(1_000_000_000u128),
+        commission_rate,
+    )
+    .unwrap();
+    assert_eq!(
+        return_18 / Uint128::from(1_000_000_000_000u128),
+        return_amount
+    );
+    assert_eq!(
+        commission_18 / Uint128::from(1_000_000_000_000u128),
+        commission_amount
+    );
+
+    // the reverse simulation asks for the amount the swap returned
+    let (offer_amount, _, reverse_commission) =
+        math::compute_offer_amount(100, &pools, &decimals, 0, 1, return_amount, commission_rate)
+            .unwrap();
+    assert!(offer_amount >= Uint128::from(1_000_000_000u128));
+    assert!(offer_amount <= Uint128::from(1_000_000_002u128));
+    assert_eq!(reverse_commission, commission_amount);
+
+    // a lower amp gives a larger spread
+    let (_, low_amp_spread, _) = math::compute_swap(
+        1,
+        &pools,
+        &decimals,
+        0,
+        1,
+        Uint128::from(1_000_000_000u128),
+        commission_rate,
+    )
+    .unwrap();
+    assert!(low_amp_spread > spread_amount);
+
+    let res = math::compute_offer_amount(
+        100,
+        &pools,
+        &decimals,
+        0,
+        1,
+        Uint128::from(1_000_000_000_000u128),
+        commission_rate,
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("ask amount exceeds the pool"))
+    );
+}
+
+#[test]
+fn provide_initial_liquidity() {
+    let mut deps = pool_deps([1_000_000, 1_000_000, 0], 0);
+    let env = mock_env();
+
+    let assets = vec![
+        Asset {
+            info: native("uusdt"),
+            amount: Uint128::from(1_000_000u128),
+        },
+        Asset {
+            info: native("uusdc"),
+            amount: Uint128::from(1_000_000u128),
+        },
+    ];
+    let funds = vec![
+        Coin::new(1_000_000u128, "uusdt"),
+        Coin::new(1_000_000u128, "uusdc"),
+    ];
+
+    // the first provision must deposit every asset
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: assets.clone(),
+        min_lp: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &funds);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res, Err(ContractError::IncompleteInitialProvision {}));
+
+    let mut assets = assets;
+    assets.push(Asset {
+        info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("usde").to_string(),
+        },
+        amount: Uint128::from(1_000_000_000_000_000_000u128),
+    });
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets,
+        min_lp: None,
+        receiver: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // balanced deposits mint the sum of the upscaled balances
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_mint_tokens_msg(
+                env.contract.address.clone(),
+                Coin::new(1_000u128, lp_denom.clone()),
+                env.contract.address.to_string(),
+            )),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("usde").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: deps.api.addr_make("addr0000").to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: Uint128::from(1_000_000_000_000_000_000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(create_mint_tokens_msg(
+                env.contract.address.clone(),
+                Coin::new(2_999_999_999_999_999_000u128, lp_denom),
+                deps.api.addr_make("addr0000").to_string(),
+            )),
+        ]
+    );
+}
+
+#[test]
+fn provide_imbalanced_liquidity() {
+    let total_share = 3_000_000_000_000_000_000_000_000u128;
+    let usde = 1_000_000_000_000_000_000_000_000u128;
+
+    // balanced deposit of 3_000 units
+    let mut deps = pool_deps([1_001_000_000_000, 1_001_000_000_000, usde], total_share);
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: native("uusdt"),
+                amount: Uint128::from(1_000_000_000u128),
+            },
+            Asset {
+                info: native("uusdc"),
+                amount: Uint128::from(1_000_000_000u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("usde").to_string(),
+                },
+                amount: Uint128::from(1_000_000_000_000_000_000_000u128),
+            },
+        ],
+        min_lp: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[
+            Coin::new(1_000_000_000u128, "uusdt"),
+            Coin::new(1_000_000_000u128, "uusdc"),
+        ],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let balanced_share = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "share")
+        .map(|attr| attr.value.clone())
+        .unwrap();
+    assert_eq!(balanced_share, "3000000000000000000000");
+
+    // the same value deposited as uusdt only pays the imbalance fee
+    let mut deps = pool_deps([1_003_000_000_000, 1_000_000_000_000, usde], total_share);
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(3_000_000_000u128, "uusdt"),
+    );
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: native("uusdt"),
+            amount: Uint128::from(3_000_000_000u128),
+        }],
+        min_lp: Some(Uint128::from(3_000_000_000_000_000_000_000u128)),
+        receiver: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    let err = res.unwrap_err();
+    let share = match &err {
+        ContractError::MinLpAssertion { share, .. } => share.parse::<u128>().unwrap(),
+        _ => panic!("unexpected error {:?}", err),
+    };
+    assert!(share < 3_000_000_000_000_000_000_000u128);
+    assert!(share > 2_995_000_000_000_000_000_000u128);
+
+    // 0.3% * 3 / 8
+    assert_eq!(
+        imbalance_fee_rate(30, 3),
+        Decimal256::from_ratio(1_125u128, 1_000_000u128)
+    );
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: native("uatom"),
+            amount: Uint128::from(1u128),
+        }],
+        min_lp: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &coins(1u128, "uatom"));
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res, Err(ContractError::AssetMismatch {}));
+}
+
+#[test]
+fn swap() {
+    let usde = 1_000_000_000_000_000_000_000_000u128;
+    let mut deps = pool_deps(
+        [1_001_000_000_000, 1_000_000_000_000, usde],
+        3_000_000_000_000_000_000_000_000,
+    );
+    let env = mock_env();
+
+    let offer_asset = Asset {
+        info: native("uusdt"),
+        amount: Uint128::from(1_000_000_000u128),
+    };
+
+    // the simulation runs against the reserves before the offer arrives
+    let mut sim_deps = pool_deps(
+        [1_000_000_000_000, 1_000_000_000_000, usde],
+        3_000_000_000_000_000_000_000_000,
+    );
+    sim_deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin::new(1_000_000_000_000u128, "uusdt"),
+            Coin::new(1_000_000_000_000u128, "uusdc"),
+        ],
+    )]);
+    let simulation = query_simulation(sim_deps.as_ref(), offer_asset.clone(), native("uusde"));
+    assert_eq!(simulation, Err(ContractError::AssetMismatch {}));
+    let simulation =
+        query_simulation(sim_deps.as_ref(), offer_asset.clone(), native("uusdc")).unwrap();
+    let reverse = query_reverse_simulation(
+        sim_deps.as_ref(),
+        native("uusdt"),
+        Asset {
+            info: native("uusdc"),
+            amount: simulation.return_amount,
+        },
+    )
+    .unwrap();
+    assert!(reverse.offer_amount >= offer_asset.amount);
+    assert!(reverse.offer_amount <= offer_asset.amount + Uint128::from(2u128));
+
+    // asking for the offered asset is rejected
+    let msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: native("uusdt"),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &coins(1_000_000_000u128, "uusdt"),
+    );
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res, Err(ContractError::AssetMismatch {}));
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset,
+        ask_asset_info: native("uusdc"),
+        belief_price: Some(Decimal::one()),
+        max_spread: Some(Decimal::percent(1)),
+        to: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // 2_999_970 commission, 1/6 burnt and 1/6 to the fee wallet, rounded down
+    let return_amount = simulation.return_amount;
+    assert_eq!(simulation.commission_amount, Uint128::from(2_999_970u128));
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::<InjectiveMsgWrapper>::Bank(
+                cosmwasm_std::BankMsg::Send {
+                    to_address: deps.api.addr_make("addr0000").to_string(),
+                    amount: coins(return_amount.u128(), "uusdc"),
+                }
+            )),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("burnaddr0000").to_string(),
+                msg: to_json_binary(&choice::send_to_auction::ExecuteMsg::SendNative {
+                    asset: Asset {
+                        info: native("uusdc"),
+                        amount: Uint128::from(499_994u128),
+                    },
+                })
+                .unwrap(),
+                funds: coins(499_994u128, "uusdc"),
+            })),
+            SubMsg::new(CosmosMsg::<InjectiveMsgWrapper>::Bank(
+                cosmwasm_std::BankMsg::Send {
+                    to_address: deps.api.addr_make("feeaddr0000").to_string(),
+                    amount: coins(499_994u128, "uusdc"),
+                }
+            )),
+        ]
+    );
+}
+
+#[test]
+fn swap_token() {
+    let usde = 1_000_001_000_000_000_000_000_000u128;
+    let mut deps = pool_deps(
+        [1_000_000_000_000, 1_000_000_000_000, usde],
+        3_000_000_000_000_000_000_000_000,
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1_000_000_000_000_000_000u128),
+        msg: to_json_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: native("uusdt"),
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+
+    // only the pool's tokens may call the hook
+    let info = message_info(&deps.api.addr_make("other"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let info = message_info(&deps.api.addr_make("usde"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let return_amount = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "return_amount")
+        .map(|attr| attr.value.parse::<u128>().unwrap())
+        .unwrap();
+
+    // one usde returns just under one uusdt after the commission
+    assert!(return_amount < 997_000);
+    assert!(return_amount > 996_900);
+}
+
+#[test]
+fn withdraw_liquidity() {
+    let usde = 1_000_000_000_000_000_000_000_000u128;
+    let mut deps = pool_deps(
+        [1_000_000_000_000, 2_000_000_000_000, usde],
+        4_000_000_000_000_000_000_000_000,
+    );
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    let amount = Uint128::from(400_000_000_000_000_000_000_000u128);
+
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount,
+        min_assets: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res, Err(ContractError::InvalidLiquidityFunds {}));
+
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: lp_denom.clone(),
+            amount,
+        }],
+    );
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount,
+        min_assets: Some(vec![Asset {
+            info: native("uusdc"),
+            amount: Uint128::from(200_000_000_001u128),
+        }]),
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::MinAmountAssertion {
+            min_asset: "200000000001uusdc".to_string(),
+            asset: "200000000000uusdc".to_string(),
+        })
+    );
+
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount,
+        min_assets: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let sender = deps.api.addr_make("addr0000");
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::<InjectiveMsgWrapper>::Bank(
+                cosmwasm_std::BankMsg::Send {
+                    to_address: sender.to_string(),
+                    amount: coins(100_000_000_000u128, "uusdt"),
+                }
+            )),
+            SubMsg::new(CosmosMsg::<InjectiveMsgWrapper>::Bank(
+                cosmwasm_std::BankMsg::Send {
+                    to_address: sender.to_string(),
+                    amount: coins(200_000_000_000u128, "uusdc"),
+                }
+            )),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("usde").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: sender.to_string(),
+                    amount: Uint128::from(100_000_000_000_000_000_000_000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(create_burn_tokens_msg(
+                mock_env().contract.address,
+                Coin {
+                    denom: lp_denom,
+                    amount,
+                },
+            )),
+        ]
+    );
+
+    let pool: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets.len(), 3);
+    assert_eq!(
+        pool.total_share,
+        Uint128::from(4_000_000_000_000_000_000_000_000u128)
+    );
+}
@@ -2,23 +2,25 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdError, StdResult, Uint128, WasmMsg,
+    coins, from_json, to_json_binary, Addr, Api, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 
-use crate::operations::execute_swap_operation;
+use crate::operations::{execute_swap_operation, query_registered_pool};
 use crate::state::{Config, CONFIG};
 
 use choice::asset::{Asset, AssetInfo, PairInfo};
 use choice::pair::SimulationResponse;
-use choice::querier::{query_pair_info, reverse_simulate, simulate};
+use choice::querier::{
+    query_pair_info, reverse_simulate, reverse_simulate_pool, simulate, simulate_pool,
+};
 use choice::router::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
     SimulateSwapOperationsResponse, SwapOperation,
 };
 use choice::util::migrate_version;
-use cw20::Cw20ReceiveMsg;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use injective_cosmwasm::query::InjectiveQueryWrapper;
 use std::collections::HashMap;
 
@@ -71,11 +73,51 @@ pub fn execute(
                 deadline,
             )
         }
+        ExecuteMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            to,
+            deadline,
+        } => {
+            let offer_asset_info = operations
+                .first()
+                .map(|operation| operation.get_offer_asset_info())
+                .ok_or_else(|| StdError::generic_err("must provide operations"))?;
+            let offer_asset = match offer_asset_info {
+                AssetInfo::NativeToken { denom } => Asset {
+                    amount: info
+                        .funds
+                        .iter()
+                        .find(|coin| coin.denom == denom)
+                        .map(|coin| coin.amount)
+                        .unwrap_or_default(),
+                    info: AssetInfo::NativeToken { denom },
+                },
+                AssetInfo::Token { .. } => {
+                    return Err(StdError::generic_err(
+                        "token offers must be sent through the cw20 receive hook",
+                    ))
+                }
+            };
+
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
         ExecuteMsg::ExecuteSwapOperation {
             operation,
             to,
             deadline,
-        } => execute_swap_operation(deps, env, info, operation, to, deadline),
+            trader,
+        } => execute_swap_operation(deps, env, info, operation, to, deadline, trader),
         ExecuteMsg::AssertMinimumReceive {
             asset_info,
             prev_balance,
@@ -104,7 +146,7 @@ fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Opti
 pub fn receive_cw20(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> StdResult<Response> {
     let sender = deps.api.addr_validate(&cw20_msg.sender)?;
@@ -126,6 +168,31 @@ pub fn receive_cw20(
                 deadline,
             )
         }
+        Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            to,
+            deadline,
+        } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
     }
 }
 
@@ -146,6 +213,8 @@ pub fn execute_swap_operations(
     // Assert the operations are properly set
     assert_operations(&operations)?;
 
+    // the pairs apply the fee discount of the original sender
+    let trader = sender.to_string();
     let to = if let Some(to) = to { to } else { sender };
     let target_asset_info = operations.last().unwrap().get_target_asset_info();
 
@@ -165,6 +234,7 @@ pub fn execute_swap_operations(
                         None
                     },
                     deadline,
+                    trader: Some(trader.clone()),
                 })?,
             }))
         })
@@ -189,6 +259,87 @@ pub fn execute_swap_operations(
     Ok(Response::new().add_messages(messages))
 }
 
+/// Back-propagates `ask_amount` through the operations like
+/// `reverse_simulate_swap_operations`, refunds the part of `offer_asset` the
+/// first operation does not need and swaps the rest forward. The final
+/// receive is asserted to be at least `ask_amount`.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap_operations_exact_out(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    ask_amount: Uint128,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response> {
+    let offer_asset_info = operations
+        .first()
+        .map(|operation| operation.get_offer_asset_info())
+        .ok_or_else(|| StdError::generic_err("must provide operations"))?;
+    if offer_asset.info != offer_asset_info {
+        return Err(StdError::generic_err(
+            "invalid operations; sent asset is not the first offer asset",
+        ));
+    }
+
+    let offer_amount = reverse_simulate_swap_operations(
+        deps.as_ref(),
+        ask_amount,
+        operations.clone(),
+        Some(sender.to_string()),
+    )?
+    .amount;
+    if offer_amount > offer_asset.amount {
+        return Err(StdError::generic_err(format!(
+            "assertion failed; maximum offer amount: {}, required offer amount: {}",
+            offer_asset.amount, offer_amount
+        )));
+    }
+
+    let mut response = Response::new();
+    let refund_amount = offer_asset.amount - offer_amount;
+    if !refund_amount.is_zero() {
+        response = response.add_message(refund_msg(&offer_asset.info, refund_amount, &sender)?);
+    }
+
+    let swap_response = execute_swap_operations(
+        deps,
+        env,
+        sender,
+        operations,
+        Some(ask_amount),
+        to,
+        deadline,
+    )?;
+
+    Ok(response
+        .add_submessages(swap_response.messages)
+        .add_attributes(vec![
+            ("action", "execute_swap_operations_exact_out"),
+            ("offer_amount", &offer_amount.to_string()),
+            ("refund_amount", &refund_amount.to_string()),
+        ]))
+}
+
+fn refund_msg(asset_info: &AssetInfo, amount: Uint128, recipient: &Addr) -> StdResult<CosmosMsg> {
+    Ok(match asset_info {
+        AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(amount.u128(), denom),
+        }),
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+    })
+}
+
 fn assert_minimum_receive(
     deps: Deps<InjectiveQueryWrapper>,
     asset_info: AssetInfo,
@@ -216,12 +367,19 @@ pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> Std
         QueryMsg::SimulateSwapOperations {
             offer_amount,
             operations,
-        } => to_json_binary(&simulate_swap_operations(deps, offer_amount, operations)?),
+            trader,
+        } => to_json_binary(&simulate_swap_operations(
+            deps,
+            offer_amount,
+            operations,
+            trader,
+        )?),
         QueryMsg::ReverseSimulateSwapOperations {
             ask_amount,
             operations,
+            trader,
         } => to_json_binary(&reverse_simulate_swap_operations(
-            deps, ask_amount, operations,
+            deps, ask_amount, operations, trader,
         )?),
     }
 }
@@ -239,6 +397,7 @@ fn simulate_swap_operations(
     deps: Deps<InjectiveQueryWrapper>,
     offer_amount: Uint128,
     operations: Vec<SwapOperation>,
+    trader: Option<String>,
 ) -> StdResult<SimulateSwapOperationsResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
     let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
@@ -254,11 +413,13 @@ fn simulate_swap_operations(
             SwapOperation::Choice {
                 offer_asset_info,
                 ask_asset_info,
+                commission_bps,
             } => {
                 let pair_info: PairInfo = query_pair_info(
                     &deps.querier,
                     choice_factory.clone(),
                     &[offer_asset_info.clone(), ask_asset_info.clone()],
+                    commission_bps,
                 )?;
 
                 let res: SimulationResponse = simulate(
@@ -268,6 +429,26 @@ fn simulate_swap_operations(
                         info: offer_asset_info,
                         amount: offer_amount,
                     },
+                    trader.clone(),
+                )?;
+
+                offer_amount = res.return_amount;
+            }
+            SwapOperation::Pool {
+                pool_addr,
+                offer_asset_info,
+                ask_asset_info,
+            } => {
+                let pool_info = query_registered_pool(deps, &pool_addr)?;
+
+                let res: SimulationResponse = simulate_pool(
+                    &deps.querier,
+                    Addr::unchecked(pool_info.contract_addr),
+                    &Asset {
+                        info: offer_asset_info,
+                        amount: offer_amount,
+                    },
+                    &ask_asset_info,
                 )?;
 
                 offer_amount = res.return_amount;
@@ -284,6 +465,7 @@ fn reverse_simulate_swap_operations(
     deps: Deps<InjectiveQueryWrapper>,
     ask_amount: Uint128,
     operations: Vec<SwapOperation>,
+    trader: Option<String>,
 ) -> StdResult<SimulateSwapOperationsResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
 
@@ -298,6 +480,7 @@ fn reverse_simulate_swap_operations(
             SwapOperation::Choice {
                 offer_asset_info,
                 ask_asset_info,
+                commission_bps,
             } => {
                 let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
 
@@ -307,9 +490,29 @@ fn reverse_simulate_swap_operations(
                     ask_amount,
                     offer_asset_info,
                     ask_asset_info,
+                    commission_bps,
+                    trader.clone(),
                 )
                 .unwrap()
             }
+            SwapOperation::Pool {
+                pool_addr,
+                offer_asset_info,
+                ask_asset_info,
+            } => {
+                let pool_info = query_registered_pool(deps, &pool_addr)?;
+
+                reverse_simulate_pool(
+                    &deps.querier,
+                    Addr::unchecked(pool_info.contract_addr),
+                    &offer_asset_info,
+                    &Asset {
+                        amount: ask_amount,
+                        info: ask_asset_info,
+                    },
+                )?
+                .offer_amount
+            }
         }
     }
 
@@ -322,11 +525,14 @@ fn reverse_simulate_return_amount(
     ask_amount: Uint128,
     offer_asset_info: AssetInfo,
     ask_asset_info: AssetInfo,
+    commission_bps: Option<u16>,
+    trader: Option<String>,
 ) -> StdResult<Uint128> {
     let pair_info: PairInfo = query_pair_info(
         &deps.querier,
         factory,
         &[offer_asset_info, ask_asset_info.clone()],
+        commission_bps,
     )?;
 
     let res = reverse_simulate(
@@ -336,6 +542,7 @@ fn reverse_simulate_return_amount(
             amount: ask_amount,
             info: ask_asset_info,
         },
+        trader,
     )?;
 
     Ok(res.offer_amount)
@@ -348,6 +555,12 @@ fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
             SwapOperation::Choice {
                 offer_asset_info,
                 ask_asset_info,
+                ..
+            }
+            | SwapOperation::Pool {
+                offer_asset_info,
+                ask_asset_info,
+                ..
             } => (offer_asset_info.clone(), ask_asset_info.clone()),
         };
 
@@ -378,6 +591,7 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0001".to_string(),
             },
+            commission_bps: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::Token {
@@ -386,6 +600,7 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "inj".to_string(),
             },
+            commission_bps: None,
         }
     ])
     .is_ok());
@@ -399,6 +614,7 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0001".to_string(),
             },
+            commission_bps: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::Token {
@@ -407,6 +623,7 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "inj".to_string(),
             },
+            commission_bps: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::NativeToken {
@@ -415,9 +632,56 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0002".to_string(),
             },
+            commission_bps: None,
         },
     ])
     .is_ok());
+
+    // pool legs chain like pair legs
+    assert!(assert_operations(&[
+        SwapOperation::Pool {
+            pool_addr: "pool0000".to_string(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        },
+        SwapOperation::Choice {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            commission_bps: None,
+        },
+    ])
+    .is_ok());
+
+    // uusdc and inj output
+    assert!(assert_operations(&[
+        SwapOperation::Pool {
+            pool_addr: "pool0000".to_string(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        },
+        SwapOperation::Choice {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            commission_bps: None,
+        },
+    ])
+    .is_err());
 }
 
 const TARGET_CONTRACT_VERSION: &str = "1.1.2";
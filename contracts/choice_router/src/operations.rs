@@ -5,9 +5,12 @@ use cosmwasm_std::{
 
 use crate::state::{Config, CONFIG};
 
-use choice::asset::{Asset, AssetInfo, PairInfo};
+use choice::asset::{Asset, AssetInfo, PairInfo, PoolInfo};
 use choice::pair::ExecuteMsg as PairExecuteMsg;
-use choice::querier::{query_balance, query_pair_info, query_token_balance};
+use choice::pool::{Cw20HookMsg as PoolCw20HookMsg, ExecuteMsg as PoolExecuteMsg};
+use choice::querier::{
+    query_balance, query_pair_info, query_pool_info, query_pool_info_from_pool, query_token_balance,
+};
 use choice::router::SwapOperation;
 use cw20::Cw20ExecuteMsg;
 use injective_cosmwasm::query::InjectiveQueryWrapper;
@@ -21,6 +24,7 @@ pub fn execute_swap_operation(
     operation: SwapOperation,
     to: Option<String>,
     deadline: Option<u64>,
+    trader: Option<String>,
 ) -> StdResult<Response> {
     if env.contract.address != info.sender {
         return Err(StdError::generic_err("unauthorized"));
@@ -30,6 +34,7 @@ pub fn execute_swap_operation(
         SwapOperation::Choice {
             offer_asset_info,
             ask_asset_info,
+            commission_bps,
         } => {
             let config: Config = CONFIG.load(deps.as_ref().storage)?;
             let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
@@ -37,22 +42,10 @@ pub fn execute_swap_operation(
                 &deps.querier,
                 choice_factory,
                 &[offer_asset_info.clone(), ask_asset_info],
+                commission_bps,
             )?;
 
-            let amount = match offer_asset_info.clone() {
-                AssetInfo::NativeToken { denom } => {
-                    query_balance(&deps.querier, env.contract.address, denom)?
-                }
-                AssetInfo::Token { contract_addr } => query_token_balance(
-                    &deps.querier,
-                    deps.api.addr_validate(contract_addr.as_str())?,
-                    env.contract.address,
-                )?,
-            };
-            let offer_asset: Asset = Asset {
-                info: offer_asset_info,
-                amount,
-            };
+            let offer_asset = query_offer_asset(deps.as_ref(), &env, offer_asset_info)?;
 
             asset_into_swap_msg(
                 deps.as_ref(),
@@ -61,6 +54,23 @@ pub fn execute_swap_operation(
                 None,
                 to,
                 deadline,
+                trader,
+            )?
+        }
+        SwapOperation::Pool {
+            pool_addr,
+            offer_asset_info,
+            ask_asset_info,
+        } => {
+            let pool_info = query_registered_pool(deps.as_ref(), &pool_addr)?;
+            let offer_asset = query_offer_asset(deps.as_ref(), &env, offer_asset_info)?;
+
+            asset_into_pool_swap_msg(
+                Addr::unchecked(pool_info.contract_addr),
+                offer_asset,
+                ask_asset_info,
+                to,
+                deadline,
             )?
         }
     };
@@ -68,6 +78,55 @@ pub fn execute_swap_operation(
     Ok(Response::new().add_message(messages))
 }
 
+/// The router's whole balance of the offer asset, left by the previous operation
+fn query_offer_asset(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    offer_asset_info: AssetInfo,
+) -> StdResult<Asset> {
+    let amount = match offer_asset_info.clone() {
+        AssetInfo::NativeToken { denom } => {
+            query_balance(&deps.querier, env.contract.address.clone(), denom)?
+        }
+        AssetInfo::Token { contract_addr } => query_token_balance(
+            &deps.querier,
+            deps.api.addr_validate(contract_addr.as_str())?,
+            env.contract.address.clone(),
+        )?,
+    };
+
+    Ok(Asset {
+        info: offer_asset_info,
+        amount,
+    })
+}
+
+/// Pool info of `pool_addr`, only if the factory has registered that contract
+/// for the pool's assets and fee tier
+pub fn query_registered_pool(
+    deps: Deps<InjectiveQueryWrapper>,
+    pool_addr: &str,
+) -> StdResult<PoolInfo> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
+
+    let pool_info = query_pool_info_from_pool(&deps.querier, deps.api.addr_validate(pool_addr)?)?;
+    let registered = query_pool_info(
+        &deps.querier,
+        choice_factory,
+        &pool_info.asset_infos,
+        pool_info.commission_bps,
+    )?;
+    if registered.contract_addr != pool_addr {
+        return Err(StdError::generic_err(format!(
+            "pool {} is not registered in the factory",
+            pool_addr
+        )));
+    }
+
+    Ok(registered)
+}
+
 pub fn asset_into_swap_msg(
     _deps: Deps<InjectiveQueryWrapper>,
     pair_contract: Addr,
@@ -75,6 +134,7 @@ pub fn asset_into_swap_msg(
     max_spread: Option<Decimal>,
     to: Option<String>,
     deadline: Option<u64>,
+    trader: Option<String>,
 ) -> StdResult<CosmosMsg> {
     match offer_asset.info.clone() {
         AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -89,6 +149,7 @@ pub fn asset_into_swap_msg(
                 max_spread,
                 to,
                 deadline,
+                trader,
             })?,
         })),
         AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -103,6 +164,48 @@ pub fn asset_into_swap_msg(
                     max_spread,
                     to,
                     deadline,
+                    trader,
+                })?,
+            })?,
+        })),
+    }
+}
+
+pub fn asset_into_pool_swap_msg(
+    pool_contract: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    to: Option<String>,
+    deadline: Option<u64>,
+) -> StdResult<CosmosMsg> {
+    match offer_asset.info.clone() {
+        AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pool_contract.to_string(),
+            funds: vec![Coin {
+                denom,
+                amount: offer_asset.amount,
+            }],
+            msg: to_json_binary(&PoolExecuteMsg::Swap {
+                offer_asset,
+                ask_asset_info,
+                belief_price: None,
+                max_spread: None,
+                to,
+                deadline,
+            })?,
+        })),
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: pool_contract.to_string(),
+                amount: offer_asset.amount,
+                msg: to_json_binary(&PoolCw20HookMsg::Swap {
+                    ask_asset_info,
+                    belief_price: None,
+                    max_spread: None,
+                    to,
+                    deadline,
                 })?,
             })?,
         })),
@@ -1,14 +1,17 @@
-use cosmwasm_std::testing::{message_info, mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{message_info, mock_env, MockApi, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, from_json, to_json_binary, Addr, Api, Coin, CosmosMsg, StdError, SubMsg, Uint128, WasmMsg,
+    coin, from_json, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, StdError, SubMsg,
+    Uint128, WasmMsg,
 };
 
 use crate::contract::{execute, instantiate, query};
 use crate::operations::asset_into_swap_msg;
 use choice::mock_querier::mock_dependencies;
 
-use choice::asset::{Asset, AssetInfo, PairInfo};
-use choice::pair::ExecuteMsg as PairExecuteMsg;
+use choice::asset::{Asset, AssetInfo, PairInfo, PoolInfo};
+use choice::factory::FeeSplit;
+use choice::pair::{ExecuteMsg as PairExecuteMsg, PairStatus, PairType};
+use choice::pool::{Cw20HookMsg as PoolCw20HookMsg, ExecuteMsg as PoolExecuteMsg};
 use choice::router::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
     SimulateSwapOperationsResponse, SwapOperation,
@@ -77,6 +80,7 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: deps.api.addr_make("asset0001").to_string(),
                 },
+                commission_bps: None,
             },
             SwapOperation::Choice {
                 offer_asset_info: AssetInfo::Token {
@@ -85,6 +89,7 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "inj".to_string(),
                 },
+                commission_bps: None,
             },
             SwapOperation::Choice {
                 offer_asset_info: AssetInfo::NativeToken {
@@ -93,6 +98,7 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: deps.api.addr_make("asset0002").to_string(),
                 },
+                commission_bps: None,
             },
         ],
         minimum_receive: Some(Uint128::from(1000000u128)),
@@ -116,9 +122,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0001").to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: None,
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -133,9 +141,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::NativeToken {
                             denom: "inj".to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: None,
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -150,9 +160,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0002").to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: Some(deps.api.addr_make("addr0000").to_string()),
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -184,6 +196,7 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: deps.api.addr_make("asset0001").to_string(),
                     },
+                    commission_bps: None,
                 },
                 SwapOperation::Choice {
                     offer_asset_info: AssetInfo::Token {
@@ -192,6 +205,7 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "inj".to_string(),
                     },
+                    commission_bps: None,
                 },
                 SwapOperation::Choice {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -200,6 +214,7 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: deps.api.addr_make("asset0002").to_string(),
                     },
+                    commission_bps: None,
                 },
             ],
             minimum_receive: None,
@@ -225,9 +240,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0001").to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: None,
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -242,9 +259,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::NativeToken {
                             denom: "inj".to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: None,
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -259,9 +278,11 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0002").to_string(),
                         },
+                        commission_bps: None,
                     },
                     to: Some(deps.api.addr_make("addr0002").to_string()),
                     deadline: None,
+                    trader: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             }))
@@ -298,6 +319,10 @@ fn execute_swap_operation() {
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_bps: 30,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -319,9 +344,11 @@ fn execute_swap_operation() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
+            commission_bps: None,
         },
         to: None,
         deadline: None,
+        trader: None,
     };
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
@@ -350,6 +377,7 @@ fn execute_swap_operation() {
                 None,
                 None,
                 None,
+                None,
             )
             .unwrap()
         )],
@@ -365,9 +393,11 @@ fn execute_swap_operation() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
+            commission_bps: None,
         },
         to: Some(deps.api.addr_make("addr0000").to_string()),
         deadline: None,
+        trader: None,
     };
 
     let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
@@ -387,6 +417,7 @@ fn execute_swap_operation() {
                 None,
                 Some(deps.api.addr_make("addr0000").to_string()),
                 None,
+                None,
             )
             .unwrap()
         )],
@@ -408,6 +439,10 @@ fn execute_swap_operation() {
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_bps: 30,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -425,9 +460,11 @@ fn execute_swap_operation() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
+            commission_bps: None,
         },
         to: Some(deps.api.addr_make("addr0000").to_string()),
         deadline: None,
+        trader: None,
     };
 
     let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
@@ -451,6 +488,7 @@ fn execute_swap_operation() {
                     max_spread: None,
                     to: Some(deps.api.addr_make("addr0000").to_string()),
                     deadline: None,
+                    trader: None,
                 })
                 .unwrap()
             })
@@ -459,6 +497,91 @@ fn execute_swap_operation() {
     );
 }
 
+#[test]
+fn execute_swap_operation_with_fee_tier() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps.querier.with_choice_factory(
+        &[(
+            &("uusd".to_string() + deps.api.addr_make("asset0000").as_str()),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: deps.api.addr_make("asset0000").to_string(),
+                    },
+                ],
+                contract_addr: deps.api.addr_make("pair0005").to_string(),
+                liquidity_token: deps.api.addr_make("liquidity0005").to_string(),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_bps: 5,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
+            },
+        )],
+        &[("uusd".to_string(), 6u8)],
+    );
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        [Coin {
+            amount: Uint128::from(1000000u128),
+            denom: "uusd".to_string(),
+        }]
+        .to_vec(),
+    )]);
+
+    let asset0000 = deps.api.addr_make("asset0000").to_string();
+    let operation = |commission_bps: Option<u16>| ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::Choice {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: asset0000.clone(),
+            },
+            commission_bps,
+        },
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
+
+    // the default tier has no pair for these assets
+    execute(deps.as_mut(), mock_env(), info.clone(), operation(None)).unwrap_err();
+
+    let res = execute(deps.as_mut(), mock_env(), info, operation(Some(5u16))).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(
+            asset_into_swap_msg(
+                deps.as_ref(),
+                Addr::unchecked(deps.api.addr_make("pair0005")),
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::from(1000000u128)
+                },
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        )],
+    );
+}
 #[test]
 fn query_buy_with_routes() {
     let mut deps = mock_dependencies(&[]);
@@ -482,6 +605,7 @@ fn query_buy_with_routes() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: deps.api.addr_make("asset0000").to_string(),
                 },
+                commission_bps: None,
             },
             SwapOperation::Choice {
                 offer_asset_info: AssetInfo::Token {
@@ -490,8 +614,10 @@ fn query_buy_with_routes() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "inj".to_string(),
                 },
+                commission_bps: None,
             },
         ],
+        trader: None,
     };
 
     deps.querier.with_choice_factory(
@@ -512,6 +638,10 @@ fn query_buy_with_routes() {
                     asset_decimals: [6u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
             (
@@ -530,6 +660,10 @@ fn query_buy_with_routes() {
                     asset_decimals: [6u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
         ],
@@ -584,7 +718,9 @@ fn query_reverse_routes_with_from_native() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
+            commission_bps: None,
         }],
+        trader: None,
     };
 
     deps.querier.with_choice_factory(
@@ -605,6 +741,10 @@ fn query_reverse_routes_with_from_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
             (
@@ -623,6 +763,10 @@ fn query_reverse_routes_with_from_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
         ],
@@ -649,9 +793,11 @@ fn query_reverse_routes_with_from_native() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
+            commission_bps: None,
         },
         to: None,
         deadline: None,
+        trader: None,
     };
     let info = message_info(
         &deps.api.addr_make("addr0"),
@@ -682,6 +828,7 @@ fn query_reverse_routes_with_from_native() {
                 max_spread: None,
                 to: None,
                 deadline: None,
+                trader: None,
             })
             .unwrap(),
         })),],
@@ -726,7 +873,9 @@ fn query_reverse_routes_with_to_native() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "ukrw".to_string(),
             },
+            commission_bps: None,
         }],
+        trader: None,
     };
 
     deps.querier.with_choice_factory(
@@ -747,6 +896,10 @@ fn query_reverse_routes_with_to_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
             (
@@ -765,6 +918,10 @@ fn query_reverse_routes_with_to_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_bps: 30,
+                    fee_split: FeeSplit::default(),
+                    pair_type: PairType::ConstantProduct,
+                    status: PairStatus::Active,
                 },
             ),
         ],
@@ -794,6 +951,7 @@ fn query_reverse_routes_with_to_native() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "ukrw".to_string(),
                 },
+                commission_bps: None,
             }],
             minimum_receive: None,
             to: None,
@@ -817,9 +975,11 @@ fn query_reverse_routes_with_to_native() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "ukrw".to_string(),
                     },
+                    commission_bps: None,
                 },
                 to: Some(deps.api.addr_make("addr0").to_string()),
                 deadline: None,
+                trader: Some(deps.api.addr_make("addr0").to_string()),
             })
             .unwrap(),
         })),],
@@ -833,9 +993,11 @@ fn query_reverse_routes_with_to_native() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "ukrw".to_string(),
             },
+            commission_bps: None,
         },
         to: None,
         deadline: None,
+        trader: None,
     };
 
     let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
@@ -860,6 +1022,7 @@ fn query_reverse_routes_with_to_native() {
                     max_spread: None,
                     to: None,
                     deadline: None,
+                    trader: None,
                 })
                 .unwrap(),
             })
@@ -952,3 +1115,412 @@ fn assert_minimum_receive_token() {
         _ => panic!("DO NOT ENTER HERE"),
     }
 }
+
+fn stable_pool_info(api: &MockApi, pool: &str) -> PoolInfo {
+    PoolInfo {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: api.addr_make("usde").to_string(),
+            },
+        ],
+        contract_addr: pool.to_string(),
+        liquidity_token: format!("factory/{}/lp", pool),
+        asset_decimals: vec![6u8, 6u8, 18u8],
+        burn_address: api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: api.addr_make("feeaddr0000").to_string(),
+        commission_bps: 30,
+        fee_split: FeeSplit::default(),
+        amp: 100u64,
+    }
+}
+
+#[test]
+fn execute_pool_swap_operation() {
+    let mut deps = mock_dependencies(&[coin(1_000_000u128, "uusdt")]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let pool = deps.api.addr_make("pool0000").to_string();
+    let usde = deps.api.addr_make("usde").to_string();
+    let pool_info = stable_pool_info(&deps.api, &pool);
+    deps.querier.with_choice_pools(&[&pool_info]);
+    deps.querier.with_token_balances(&[(
+        &usde,
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(2_000_000u128),
+        )],
+    )]);
+
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::Pool {
+            pool_addr: pool.clone(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        },
+        to: Some(deps.api.addr_make("addr0000").to_string()),
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pool.clone(),
+            funds: vec![coin(1_000_000u128, "uusdt")],
+            msg: to_json_binary(&PoolExecuteMsg::Swap {
+                offer_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusdt".to_string(),
+                    },
+                    amount: Uint128::from(1_000_000u128),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uusdc".to_string(),
+                },
+                belief_price: None,
+                max_spread: None,
+                to: Some(deps.api.addr_make("addr0000").to_string()),
+                deadline: None,
+            })
+            .unwrap(),
+        })]
+    );
+
+    // token offers are sent to the pool with its swap hook
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::Pool {
+            pool_addr: pool.clone(),
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: usde.clone(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+        },
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: usde,
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: pool.clone(),
+                amount: Uint128::from(2_000_000u128),
+                msg: to_json_binary(&PoolCw20HookMsg::Swap {
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "uusdt".to_string(),
+                    },
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // a contract answering for the same assets isn't the registered pool
+    let fake_pool = deps.api.addr_make("pool0001").to_string();
+    deps.querier
+        .with_choice_pools(&[&pool_info, &stable_pool_info(&deps.api, &fake_pool)]);
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::Pool {
+            pool_addr: fake_pool.clone(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        },
+        to: None,
+        deadline: None,
+        trader: None,
+    };
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            format!("pool {} is not registered in the factory", fake_pool)
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_routes_through_pool() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let pool = deps.api.addr_make("pool0000").to_string();
+    deps.querier.with_choice_factory(
+        &[(
+            &format!("uusdc{}", deps.api.addr_make("asset0000")),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusdc".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: deps.api.addr_make("asset0000").to_string(),
+                    },
+                ],
+                contract_addr: deps.api.addr_make("pair0000").to_string(),
+                liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_bps: 30,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
+            },
+        )],
+        &[("uusdt".to_string(), 6u8), ("uusdc".to_string(), 6u8)],
+    );
+    deps.querier
+        .with_choice_pools(&[&stable_pool_info(&deps.api, &pool)]);
+
+    let operations = vec![
+        SwapOperation::Pool {
+            pool_addr: pool.clone(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        },
+        SwapOperation::Choice {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+            commission_bps: None,
+        },
+    ];
+
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1000000u128),
+        operations: operations.clone(),
+        trader: None,
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(1000000u128));
+
+    let msg = QueryMsg::ReverseSimulateSwapOperations {
+        ask_amount: Uint128::from(1000000u128),
+        operations,
+        trader: None,
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(1000000u128));
+
+    // pools the factory doesn't know can't be routed through
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1000000u128),
+        operations: vec![SwapOperation::Pool {
+            pool_addr: deps.api.addr_make("pool0001").to_string(),
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        }],
+        trader: None,
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
+
+#[test]
+fn execute_swap_operations_exact_out() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = deps.api.addr_make("addr0000");
+    let asset0000 = deps.api.addr_make("asset0000").to_string();
+    deps.querier.with_choice_factory(
+        &[(
+            &format!("ukrw{}", asset0000),
+            &PairInfo {
+                contract_addr: deps.api.addr_make("pair0000").to_string(),
+                liquidity_token: "liquidity0000".to_string(),
+                asset_infos: [
+                    AssetInfo::Token {
+                        contract_addr: asset0000.clone(),
+                    },
+                    AssetInfo::NativeToken {
+                        denom: "ukrw".to_string(),
+                    },
+                ],
+                asset_decimals: [8u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_bps: 30,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
+            },
+        )],
+        &[("ukrw".to_string(), 6u8)],
+    );
+    deps.querier.with_token_balances(&[(
+        &asset0000,
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+    )]);
+
+    let ukrw_to_asset = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: asset0000.clone(),
+        },
+        commission_bps: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: vec![ukrw_to_asset.clone()],
+        ask_amount: Uint128::from(1000000u128),
+        to: None,
+        deadline: None,
+    };
+
+    // the mocked pairs quote 1:1, so the surplus is refunded before swapping
+    let info = message_info(&user, &[coin(1500000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(500000, "ukrw")],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: ukrw_to_asset.clone(),
+                    to: Some(user.to_string()),
+                    deadline: None,
+                    trader: Some(user.to_string()),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: AssetInfo::Token {
+                        contract_addr: asset0000.clone(),
+                    },
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(1000000u128),
+                    receiver: user.to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let info = message_info(&user, &[coin(999999, "ukrw")]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "assertion failed; maximum offer amount: 999999, required offer amount: 1000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // token offers come through the receive hook and the surplus is transferred back
+    let asset_to_ukrw = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::Token {
+            contract_addr: asset0000.clone(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        commission_bps: None,
+    };
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: user.to_string(),
+        amount: Uint128::from(1200000u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations: vec![asset_to_ukrw.clone()],
+            ask_amount: Uint128::from(1000000u128),
+            to: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&Addr::unchecked(asset0000.clone()), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: asset0000.clone(),
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: user.to_string(),
+                amount: Uint128::from(200000u128),
+            })
+            .unwrap(),
+        }))
+    );
+
+    // the sent asset has to be the first offer asset
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: vec![asset_to_ukrw],
+        ask_amount: Uint128::from(1000000u128),
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&user, &[coin(1000000, "ukrw")]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "token offers must be sent through the cw20 receive hook"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
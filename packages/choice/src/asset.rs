@@ -2,6 +2,8 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::factory::{FeeSplit, DEFAULT_COMMISSION_BPS};
+use crate::pair::{PairStatus, PairType};
 use crate::querier::{query_balance, query_native_decimals, query_token_balance, query_token_info};
 use cosmwasm_std::{
     to_json_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, CustomQuery, MessageInfo,
@@ -260,6 +262,13 @@ pub struct PairInfo {
     pub asset_decimals: [u8; 2],
     pub burn_address: String,       // New field
     pub fee_wallet_address: String, // New field
+    /// Swap commission in basis points (fee tier)
+    pub commission_bps: u16,
+    /// Commission split in effect, resolved from the factory
+    pub fee_split: FeeSplit,
+    pub pair_type: PairType,
+    /// Trading status set by the factory owner
+    pub status: PairStatus,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -270,10 +279,25 @@ pub struct PairInfoRaw {
     pub asset_decimals: [u8; 2],
     pub burn_address: CanonicalAddr,       // New field
     pub fee_wallet_address: CanonicalAddr, // New field
+    /// Pairs stored before fee tiers charge the default tier
+    #[serde(default = "default_commission_bps")]
+    pub commission_bps: u16,
+    /// Pairs stored before pair types are constant product pairs
+    #[serde(default)]
+    pub pair_type: PairType,
+}
+
+fn default_commission_bps() -> u16 {
+    DEFAULT_COMMISSION_BPS
 }
 
 impl PairInfoRaw {
-    pub fn to_normal(&self, api: &dyn Api) -> StdResult<PairInfo> {
+    pub fn to_normal(
+        &self,
+        api: &dyn Api,
+        fee_split: FeeSplit,
+        status: PairStatus,
+    ) -> StdResult<PairInfo> {
         Ok(PairInfo {
             liquidity_token: self.liquidity_token.to_string(),
             contract_addr: api.addr_humanize(&self.contract_addr)?.to_string(),
@@ -284,6 +308,10 @@ impl PairInfoRaw {
             asset_decimals: self.asset_decimals,
             burn_address: api.addr_humanize(&self.burn_address)?.to_string(),
             fee_wallet_address: api.addr_humanize(&self.fee_wallet_address)?.to_string(),
+            commission_bps: self.commission_bps,
+            fee_split,
+            pair_type: self.pair_type.clone(),
+            status,
         })
     }
 
@@ -307,3 +335,70 @@ impl PairInfoRaw {
         ])
     }
 }
+
+/// Multi-asset pool, the N asset counterpart of `PairInfo`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PoolInfo {
+    pub asset_infos: Vec<AssetInfo>,
+    pub contract_addr: String,
+    pub liquidity_token: String,
+    pub asset_decimals: Vec<u8>,
+    pub burn_address: String,
+    pub fee_wallet_address: String,
+    /// Swap commission in basis points (fee tier)
+    pub commission_bps: u16,
+    /// Commission split in effect, resolved from the factory
+    pub fee_split: FeeSplit,
+    /// StableSwap amplification coefficient
+    pub amp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PoolInfoRaw {
+    pub asset_infos: Vec<AssetInfoRaw>,
+    pub contract_addr: CanonicalAddr,
+    pub liquidity_token: String,
+    pub asset_decimals: Vec<u8>,
+    pub burn_address: CanonicalAddr,
+    pub fee_wallet_address: CanonicalAddr,
+    pub commission_bps: u16,
+    pub amp: u64,
+}
+
+impl PoolInfoRaw {
+    pub fn to_normal(&self, api: &dyn Api, fee_split: FeeSplit) -> StdResult<PoolInfo> {
+        Ok(PoolInfo {
+            liquidity_token: self.liquidity_token.to_string(),
+            contract_addr: api.addr_humanize(&self.contract_addr)?.to_string(),
+            asset_infos: self
+                .asset_infos
+                .iter()
+                .map(|info| info.to_normal(api))
+                .collect::<StdResult<Vec<AssetInfo>>>()?,
+            asset_decimals: self.asset_decimals.clone(),
+            burn_address: api.addr_humanize(&self.burn_address)?.to_string(),
+            fee_wallet_address: api.addr_humanize(&self.fee_wallet_address)?.to_string(),
+            commission_bps: self.commission_bps,
+            fee_split,
+            amp: self.amp,
+        })
+    }
+
+    pub fn query_pools<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        api: &dyn Api,
+        contract_addr: Addr,
+    ) -> StdResult<Vec<Asset>> {
+        self.asset_infos
+            .iter()
+            .map(|info| {
+                let info = info.to_normal(api)?;
+                Ok(Asset {
+                    amount: info.query_pool(querier, api, contract_addr.clone())?,
+                    info,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,12 +1,54 @@
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, StdError, StdResult, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{Asset, AssetInfo, PairInfo};
+use crate::asset::{Asset, AssetInfo, PairInfo, PoolInfo};
+use crate::pair::{
+    Allowlist, BatchAuction, CircuitBreaker, DynamicFee, HoldingPeriod, LaunchProtection,
+    PairStatus, PairType, TradingStats,
+};
+
+/// Fee tier used when `CreatePair` or `Pair` omit `commission_bps` (0.3%)
+pub const DEFAULT_COMMISSION_BPS: u16 = 30;
+
+/// Highest fee tier the owner may allow (10%)
+pub const MAX_COMMISSION_BPS: u16 = 1_000;
+
+/// Shares of the swap commission taken by the protocol, the remainder stays in the pool
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeSplit {
+    /// Share sent to the burn handler
+    pub burn: Decimal,
+    /// Share sent to the fee wallet
+    pub fee_wallet: Decimal,
+}
+
+impl Default for FeeSplit {
+    /// 1/6 burn and 1/6 fee wallet, leaving 4/6 to liquidity providers
+    fn default() -> Self {
+        FeeSplit {
+            burn: Decimal::from_ratio(1u128, 6u128),
+            fee_wallet: Decimal::from_ratio(1u128, 6u128),
+        }
+    }
+}
+
+impl FeeSplit {
+    pub fn validate(&self) -> StdResult<()> {
+        match self.burn.checked_add(self.fee_wallet) {
+            Ok(total) if total <= Decimal::one() => Ok(()),
+            _ => Err(StdError::generic_err(
+                "fee split shares must not exceed 100%",
+            )),
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub pair_code_id: u64,
+    /// Code id of the multi-asset pool contract, pools can't be created without it
+    pub pool_code_id: Option<u64>,
     pub burn_address: String,
     pub fee_wallet_address: String,
 }
@@ -14,8 +56,15 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct UpdateConfigParams {
     pub pair_code_id: Option<u64>,
+    pub pool_code_id: Option<u64>,
     pub burn_address: Option<String>,
     pub fee_wallet_address: Option<String>,
+    /// Replaces the list of fee tiers (in bps) allowed for new pairs
+    pub fee_tiers: Option<Vec<u16>>,
+    /// Default commission split for pairs without an override
+    pub fee_split: Option<FeeSplit>,
+    /// Router whose swaps get the fee discount of the trader they are made for
+    pub router: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -28,6 +77,27 @@ pub enum ExecuteMsg {
     /// CreatePair instantiates pair contract
     CreatePair {
         assets: [Asset; 2],
+        /// Fee tier in bps, must be one of the allowed tiers. Defaults to 30 (0.3%)
+        commission_bps: Option<u16>,
+        /// Defaults to constant product
+        pair_type: Option<PairType>,
+        /// Initial amplification coefficient, required for stable swap pairs
+        amp: Option<u64>,
+        /// Asset weights in `assets` order, required for weighted pairs
+        weights: Option<[Decimal; 2]>,
+        /// Trading start, swap size limit and cooldown of the first minutes
+        launch_protection: Option<LaunchProtection>,
+        /// Makes the pair permissioned, the allowlist manager defaults to the
+        /// sender
+        allowlist: Option<Allowlist>,
+    },
+    /// CreatePool instantiates a multi-asset StableSwap pool contract
+    CreatePool {
+        asset_infos: Vec<AssetInfo>,
+        /// Fee tier in bps, must be one of the allowed tiers. Defaults to 30 (0.3%)
+        commission_bps: Option<u16>,
+        /// Amplification coefficient, fixed for the lifetime of the pool
+        amp: u64,
     },
     AddNativeTokenDecimals {
         denom: String,
@@ -46,6 +116,75 @@ pub enum ExecuteMsg {
     },
     AcceptOwnership,
     CancelOwnershipProposal,
+    /// Overrides the commission split of a single pair, `None` restores the default
+    UpdatePairFeeSplit {
+        pair: String,
+        fee_split: Option<FeeSplit>,
+    },
+    /// Pauses or deprecates a pair, withdrawals keep working in every status
+    UpdatePairStatus {
+        pair: String,
+        status: PairStatus,
+    },
+    /// Sets the volatility driven commission of a pair, `None` restores its fee tier
+    UpdatePairDynamicFee {
+        pair: String,
+        dynamic_fee: Option<DynamicFee>,
+    },
+    /// Ramps the amplification coefficient of a stable swap pair
+    RampAmp {
+        pair: String,
+        future_amp: u64,
+        future_time: u64,
+    },
+    /// Stops an ongoing amplification ramp of a stable swap pair
+    StopRampAmp {
+        pair: String,
+    },
+    /// Sets the reserves of a pair to its balances
+    SyncPair {
+        pair: String,
+    },
+    /// Replaces the contracts a pair notifies after each swap
+    UpdatePairSwapHooks {
+        pair: String,
+        hooks: Vec<String>,
+    },
+    /// Turns the batch auction mode of a constant product pair on or off
+    UpdatePairBatchAuction {
+        pair: String,
+        batch_auction: Option<BatchAuction>,
+    },
+    /// Sets the price and outflow thresholds past which a pair rejects swaps,
+    /// `None` removes them
+    UpdatePairCircuitBreaker {
+        pair: String,
+        circuit_breaker: Option<CircuitBreaker>,
+    },
+    /// Closes the window of a pair's circuit breaker before it ends
+    ResetPairCircuitBreaker {
+        pair: String,
+    },
+    /// Sets the minimum time LP of a pair has to be held before it is
+    /// withdrawn, `None` removes it
+    UpdatePairHoldingPeriod {
+        pair: String,
+        holding_period: Option<HoldingPeriod>,
+    },
+    /// Adds and removes addresses on the allowlist of a permissioned pair and
+    /// sets whether its swaps are restricted
+    UpdatePairAllowlist {
+        pair: String,
+        add: Vec<String>,
+        remove: Vec<String>,
+        restrict_swaps: Option<bool>,
+    },
+    /// Sets the share of the swap commission waived for `address`, `None`
+    /// removes its discount
+    UpdateFeeDiscount {
+        address: String,
+        discount: Option<Decimal>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -54,14 +193,51 @@ pub enum QueryMsg {
     Config {},
     Pair {
         asset_infos: [AssetInfo; 2],
+        /// Fee tier of the pair, defaults to 30 (0.3%)
+        commission_bps: Option<u16>,
     },
     Pairs {
         start_after: Option<[AssetInfo; 2]>,
+        /// Fee tier of the `start_after` pair, defaults to 30 (0.3%)
+        start_after_commission_bps: Option<u16>,
+        limit: Option<u32>,
+    },
+    Pool {
+        asset_infos: Vec<AssetInfo>,
+        /// Fee tier of the pool, defaults to 30 (0.3%)
+        commission_bps: Option<u16>,
+    },
+    Pools {
+        start_after: Option<Vec<AssetInfo>>,
+        /// Fee tier of the `start_after` pool, defaults to 30 (0.3%)
+        start_after_commission_bps: Option<u16>,
         limit: Option<u32>,
     },
     NativeTokenDecimals {
         denom: String,
     },
+    /// Commission split in effect for the given pair contract
+    FeeSplit {
+        pair: String,
+    },
+    /// Fee discount of a swap sent by `sender` for `trader`, the trader's
+    /// discount only counts when the sender is the router
+    FeeDiscount {
+        sender: String,
+        trader: Option<String>,
+    },
+    /// Registered fee discounts by address
+    FeeDiscounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Trading totals of the pairs, paginated like `Pairs`
+    PairsStats {
+        start_after: Option<[AssetInfo; 2]>,
+        /// Fee tier of the `start_after` pair, defaults to 30 (0.3%)
+        start_after_commission_bps: Option<u16>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -69,9 +245,30 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub owner: String,
     pub pair_code_id: u64,
+    pub pool_code_id: Option<u64>,
 
     pub burn_address: String,       // New field
     pub fee_wallet_address: String, // New field
+    pub fee_tiers: Vec<u16>,
+    pub fee_split: FeeSplit,
+    pub router: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeDiscountResponse {
+    /// Share of the swap commission waived, between 0 and 1
+    pub discount: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeDiscountInfo {
+    pub address: String,
+    pub discount: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeDiscountsResponse {
+    pub fee_discounts: Vec<FeeDiscountInfo>,
 }
 
 /// We currently take no arguments for migrations
@@ -84,6 +281,24 @@ pub struct PairsResponse {
     pub pairs: Vec<PairInfo>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairStats {
+    pub contract_addr: String,
+    pub asset_infos: [AssetInfo; 2],
+    pub commission_bps: u16,
+    pub stats: TradingStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairsStatsResponse {
+    pub pairs: Vec<PairStats>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PoolsResponse {
+    pub pools: Vec<PoolInfo>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct NativeTokenDecimalsResponse {
     pub decimals: u8,
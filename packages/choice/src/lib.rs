@@ -1,6 +1,7 @@
 pub mod asset;
 pub mod factory;
 pub mod pair;
+pub mod pool;
 pub mod querier;
 pub mod router;
 pub mod send_to_auction;
@@ -1,6 +1,6 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_json, to_json_binary, Binary, Coin, ContractResult, Empty, OwnedDeps, Querier,
+    from_json, to_json_binary, Binary, Coin, ContractResult, Decimal, Empty, OwnedDeps, Querier,
     QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use injective_cosmwasm::tokenfactory::response::TokenFactoryCreateDenomFeeResponse;
@@ -12,10 +12,14 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::panic;
 
-use crate::asset::{AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
-use crate::pair::QueryMsg as PairQueryMsg;
-use crate::pair::{ReverseSimulationResponse, SimulationResponse};
+use crate::asset::{AssetInfo, PairInfo, PoolInfo};
+use crate::factory::{
+    ConfigResponse, FeeDiscountResponse, FeeSplit, NativeTokenDecimalsResponse,
+    QueryMsg as FactoryQueryMsg, DEFAULT_COMMISSION_BPS,
+};
+use crate::pair::{PairStatus, PairType, QueryMsg as PairQueryMsg};
+use crate::pair::{ReverseSimulationResponse, SimulationResponse, StatsResponse};
+use crate::pool::QueryMsg as PoolQueryMsg;
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use injective_cosmwasm::query::InjectiveQueryWrapper;
 use injective_cosmwasm::WasmMockQuerier as InjWasmMockQuerier;
@@ -80,6 +84,11 @@ pub(crate) fn balances_to_map(
 pub struct ChoiceFactoryQuerier {
     pairs: HashMap<String, PairInfo>,
     native_token_decimals: HashMap<String, u8>,
+    fee_splits: HashMap<String, FeeSplit>,
+    fee_discounts: HashMap<String, Decimal>,
+    pools: Vec<PoolInfo>,
+    pair_stats: HashMap<String, StatsResponse>,
+    router: Option<String>,
 }
 
 impl ChoiceFactoryQuerier {
@@ -87,6 +96,11 @@ impl ChoiceFactoryQuerier {
         ChoiceFactoryQuerier {
             pairs: pairs_to_map(pairs),
             native_token_decimals: native_token_decimals_to_map(native_token_decimals),
+            fee_splits: HashMap::new(),
+            fee_discounts: HashMap::new(),
+            pools: vec![],
+            pair_stats: HashMap::new(),
+            router: None,
         }
     }
 }
@@ -132,15 +146,36 @@ impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<InjectiveQueryWrapper>) -> QuerierResult {
         let deps = mock_dependencies(&[]);
         match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if matches!(from_json(msg), Ok(PoolQueryMsg::PoolInfo {})) =>
+            {
+                match self
+                    .choice_factory_querier
+                    .pools
+                    .iter()
+                    .find(|pool| &pool.contract_addr == contract_addr)
+                {
+                    Some(v) => SystemResult::Ok(ContractResult::Ok(to_json_binary(v).unwrap())),
+                    None => SystemResult::Err(SystemError::InvalidRequest {
+                        error: "No pool info exists".to_string(),
+                        request: msg.as_slice().into(),
+                    }),
+                }
+            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => match from_json(msg) {
-                Ok(FactoryQueryMsg::Pair { asset_infos }) => {
+                Ok(FactoryQueryMsg::Pair {
+                    asset_infos,
+                    commission_bps,
+                }) => {
                     let key = [asset_infos[0].to_string(), asset_infos[1].to_string()].join("");
                     let mut sort_key: Vec<char> = key.chars().collect();
                     sort_key.sort_by(|a, b| b.cmp(a));
+                    let commission_bps = commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS);
                     match self
                         .choice_factory_querier
                         .pairs
                         .get(&String::from_iter(sort_key.iter()))
+                        .filter(|pair| pair.commission_bps == commission_bps)
                     {
                         Some(v) => SystemResult::Ok(ContractResult::Ok(to_json_binary(v).unwrap())),
                         None => SystemResult::Err(SystemError::InvalidRequest {
@@ -149,6 +184,25 @@ impl WasmMockQuerier {
                         }),
                     }
                 }
+                Ok(FactoryQueryMsg::Pool {
+                    asset_infos,
+                    commission_bps,
+                }) => {
+                    let commission_bps = commission_bps.unwrap_or(DEFAULT_COMMISSION_BPS);
+                    match self.choice_factory_querier.pools.iter().find(|pool| {
+                        pool.commission_bps == commission_bps
+                            && pool.asset_infos.len() == asset_infos.len()
+                            && asset_infos
+                                .iter()
+                                .all(|info| pool.asset_infos.contains(info))
+                    }) {
+                        Some(v) => SystemResult::Ok(ContractResult::Ok(to_json_binary(v).unwrap())),
+                        None => SystemResult::Err(SystemError::InvalidRequest {
+                            error: "No pool info exists".to_string(),
+                            request: msg.as_slice().into(),
+                        }),
+                    }
+                }
                 Ok(FactoryQueryMsg::NativeTokenDecimals { denom }) => {
                     match self
                         .choice_factory_querier
@@ -167,6 +221,46 @@ impl WasmMockQuerier {
                         }),
                     }
                 }
+                Ok(FactoryQueryMsg::FeeSplit { pair }) => {
+                    let fee_split = self
+                        .choice_factory_querier
+                        .fee_splits
+                        .get(&pair)
+                        .cloned()
+                        .unwrap_or_default();
+                    SystemResult::Ok(ContractResult::Ok(to_json_binary(&fee_split).unwrap()))
+                }
+                Ok(FactoryQueryMsg::Config {}) => SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ConfigResponse {
+                        owner: deps.api.addr_make("owner0000").to_string(),
+                        pair_code_id: 321u64,
+                        pool_code_id: None,
+                        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                        fee_tiers: vec![DEFAULT_COMMISSION_BPS],
+                        fee_split: FeeSplit::default(),
+                        router: self.choice_factory_querier.router.clone(),
+                    })
+                    .unwrap(),
+                )),
+                // the trader's discount counts whoever the sender is, the
+                // factory only takes it from the router
+                Ok(FactoryQueryMsg::FeeDiscount { sender, trader }) => {
+                    let discount = |address: &String| {
+                        self.choice_factory_querier
+                            .fee_discounts
+                            .get(address)
+                            .cloned()
+                            .unwrap_or_default()
+                    };
+                    let discount = match trader {
+                        Some(trader) => discount(&sender).max(discount(&trader)),
+                        None => discount(&sender),
+                    };
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&FeeDiscountResponse { discount }).unwrap(),
+                    ))
+                }
                 _ => match from_json(msg) {
                     Ok(PairQueryMsg::Pair {}) => {
                         let pair_addr = deps.api.addr_make("pair0000").to_string();
@@ -188,22 +282,39 @@ impl WasmMockQuerier {
                             liquidity_token,
                             burn_address,
                             fee_wallet_address,
+                            commission_bps: DEFAULT_COMMISSION_BPS,
+                            fee_split: FeeSplit::default(),
+                            pair_type: PairType::ConstantProduct,
+                            status: PairStatus::Active,
                         })))
                     }
-                    Ok(PairQueryMsg::Simulation { offer_asset }) => SystemResult::Ok(
+                    Ok(PairQueryMsg::Simulation { offer_asset, .. }) => SystemResult::Ok(
                         ContractResult::from(to_json_binary(&SimulationResponse {
                             return_amount: offer_asset.amount,
                             commission_amount: Uint128::zero(),
                             spread_amount: Uint128::zero(),
+                            commission_bps: 0,
                         })),
                     ),
-                    Ok(PairQueryMsg::ReverseSimulation { ask_asset }) => SystemResult::Ok(
+                    Ok(PairQueryMsg::ReverseSimulation { ask_asset, .. }) => SystemResult::Ok(
                         ContractResult::from(to_json_binary(&ReverseSimulationResponse {
                             offer_amount: ask_asset.amount,
                             commission_amount: Uint128::zero(),
                             spread_amount: Uint128::zero(),
+                            commission_bps: 0,
                         })),
                     ),
+                    Ok(PairQueryMsg::Stats { .. }) => {
+                        match self.choice_factory_querier.pair_stats.get(contract_addr) {
+                            Some(v) => {
+                                SystemResult::Ok(ContractResult::Ok(to_json_binary(v).unwrap()))
+                            }
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No pair stats exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
                     _ => match from_json(msg).unwrap() {
                         Cw20QueryMsg::TokenInfo {} => {
                             let balances: &HashMap<String, Uint128> =
@@ -436,6 +547,37 @@ impl WasmMockQuerier {
         self.choice_factory_querier = ChoiceFactoryQuerier::new(pairs, native_token_decimals);
     }
 
+    // configure a per-pair fee split override, other pairs use the default split
+    pub fn with_pair_fee_split(&mut self, pair: &str, fee_split: FeeSplit) {
+        self.choice_factory_querier
+            .fee_splits
+            .insert(pair.to_string(), fee_split);
+    }
+
+    // configure the fee discount of an address
+    pub fn with_fee_discount(&mut self, address: &str, discount: Decimal) {
+        self.choice_factory_querier
+            .fee_discounts
+            .insert(address.to_string(), discount);
+    }
+
+    // configure the router of the factory config
+    pub fn with_router(&mut self, router: &str) {
+        self.choice_factory_querier.router = Some(router.to_string());
+    }
+
+    // configure the trading stats returned by a pair contract
+    pub fn with_pair_stats(&mut self, pair: &str, stats: StatsResponse) {
+        self.choice_factory_querier
+            .pair_stats
+            .insert(pair.to_string(), stats);
+    }
+
+    // configure the multi-asset pools registered in the factory
+    pub fn with_choice_pools(&mut self, pools: &[&PoolInfo]) {
+        self.choice_factory_querier.pools = pools.iter().map(|pool| (*pool).clone()).collect();
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, Vec<Coin>)]) {
         for (addr, balance) in balances {
             self.base
@@ -495,6 +637,7 @@ mod mock_exception {
                     denom: "ulunc".to_string(),
                 },
             ],
+            commission_bps: None,
         })
         .unwrap();
         assert_eq!(
@@ -1,11 +1,149 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use crate::asset::{Asset, AssetInfo};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, Decimal, Decimal256, Uint128};
 use cw20::Cw20ReceiveMsg;
 
+/// Curve used by a pair to price swaps
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PairType {
+    /// x * y = k
+    #[default]
+    ConstantProduct,
+    /// Curve StableSwap invariant with an amplification coefficient
+    StableSwap,
+    /// Balancer style weighted invariant, b0^w0 * b1^w1 = k
+    Weighted,
+}
+
+impl fmt::Display for PairType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PairType::ConstantProduct => write!(f, "constant_product"),
+            PairType::StableSwap => write!(f, "stable_swap"),
+            PairType::Weighted => write!(f, "weighted"),
+        }
+    }
+}
+
+/// Trading status of a pair, set by the factory owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PairStatus {
+    #[default]
+    Active,
+    /// Swaps are rejected, liquidity can still be provided and withdrawn
+    SwapsPaused,
+    /// Only withdrawals are accepted, for deprecated pairs
+    WithdrawOnly,
+}
+
+impl fmt::Display for PairStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PairStatus::Active => write!(f, "active"),
+            PairStatus::SwapsPaused => write!(f, "swaps_paused"),
+            PairStatus::WithdrawOnly => write!(f, "withdraw_only"),
+        }
+    }
+}
+
+/// Volatility driven commission, replaces the fee tier of a pair while set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DynamicFee {
+    /// Commission of a calm market in bps, greater than zero
+    pub min_fee_bps: u16,
+    /// Highest commission in bps, at most the highest fee tier
+    pub max_fee_bps: u16,
+    /// Commission bps added per bps of recent price movement
+    pub volatility_multiplier: Decimal,
+    /// Seconds without swaps after which recorded price movement is forgotten
+    pub decay_period: u64,
+}
+
+/// Frequent batch auction mode, swaps are queued and cleared together at one
+/// price by `settle_batch`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchAuction {
+    /// Blocks a batch accepts swaps for after its first one, at least 1
+    pub interval_blocks: u64,
+    /// Smallest offer an order can queue in the pair's asset order, above zero
+    pub min_offer_amounts: [Uint128; 2],
+}
+
+/// Anti-sniping limits of a new pair, set at creation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LaunchProtection {
+    /// Unix time in seconds the pair accepts swaps from
+    pub trading_start: u64,
+    /// Seconds after `trading_start` the swap size limit and cooldown apply
+    pub duration: u64,
+    /// Largest amount a swap can offer, as a fraction of the offer reserve
+    pub max_swap_ratio: Decimal,
+    /// Seconds an address has to wait between two swaps
+    pub cooldown: u64,
+}
+
+/// Thresholds past which a pair rejects swaps, measured over a window opened
+/// by the first swap after the previous one ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CircuitBreaker {
+    /// Seconds a window lasts, at least 1
+    pub window: u64,
+    /// Largest relative fall of an asset's price within a window
+    pub max_price_drop: Decimal,
+    /// Largest net amount swapped out of a reserve within a window, as a
+    /// fraction of the reserve at the window start
+    pub max_outflow: Decimal,
+}
+
+/// Minimum time LP has to be held before it is withdrawn without penalty
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HoldingPeriod {
+    /// Seconds from the deposit, at least 1
+    pub seconds: u64,
+    /// Share of the refund an early withdrawal leaves in the pool, `None`
+    /// rejects early withdrawals
+    pub early_withdrawal_penalty: Option<Decimal>,
+}
+
+/// Allowlist of a permissioned pair, set at creation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Allowlist {
+    /// Address managing the allowlist along with the factory owner, the pair
+    /// creator when created through the factory
+    pub manager: Option<String>,
+    /// Addresses allowed from the start
+    pub addresses: Vec<String>,
+    /// Whether swaps are restricted to the allowlist as well
+    pub restrict_swaps: bool,
+}
+
+/// Length in seconds of the epochs a pair buckets its trading stats by
+pub const STATS_EPOCH_SECONDS: u64 = 86_400;
+
+/// Running trading totals of a pair, amounts are in the pair's asset order
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct TradingStats {
+    pub swap_count: u64,
+    /// Amounts swapped into the pool and paid out of it
+    pub volume: [Uint128; 2],
+    pub commission_amount: [Uint128; 2],
+    pub burn_amount: [Uint128; 2],
+    pub fee_wallet_amount: [Uint128; 2],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EpochStats {
+    /// Block time divided by `STATS_EPOCH_SECONDS`
+    pub epoch: u64,
+    pub stats: TradingStats,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     /// Asset infos
@@ -14,6 +152,16 @@ pub struct InstantiateMsg {
 
     pub burn_address: String,       // New field
     pub fee_wallet_address: String, // New field
+    /// Swap commission in basis points
+    pub commission_bps: u16,
+    pub pair_type: PairType,
+    /// Initial amplification coefficient, required for stable swap pairs
+    pub amp: Option<u64>,
+    /// Asset weights in `asset_infos` order, required for weighted pairs
+    pub weights: Option<[Decimal; 2]>,
+    pub launch_protection: Option<LaunchProtection>,
+    /// Makes the pair permissioned
+    pub allowlist: Option<Allowlist>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -34,6 +182,27 @@ pub enum ExecuteMsg {
         max_spread: Option<Decimal>,
         to: Option<String>,
         deadline: Option<u64>,
+        /// Address the swap is made for, its fee discount applies when the
+        /// swap is sent by the factory's router
+        trader: Option<String>,
+    },
+    /// Swap for exactly `ask_asset`, paying at most `max_offer`. Native offers
+    /// send `max_offer` and get the surplus refunded, token offers need an
+    /// allowance and only the required amount is transferred.
+    SwapExactOut {
+        ask_asset: Asset,
+        max_offer: Asset,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+
+    /// Provide liquidity with a single native asset, swapping the part of it
+    /// needed to deposit both assets in the pool ratio
+    ZapIn {
+        offer_asset: Asset,
+        min_lp: Option<Uint128>,
+        receiver: Option<String>,
+        deadline: Option<u64>,
     },
 
     // Withdraw liquidity
@@ -42,6 +211,122 @@ pub enum ExecuteMsg {
         min_assets: Option<[Asset; 2]>,
         deadline: Option<u64>,
     },
+    /// Withdraw liquidity as a single asset, the other side is swapped to
+    /// `target_asset` through the pool
+    ZapOut {
+        amount: Uint128,
+        target_asset: AssetInfo,
+        min_return: Option<Uint128>,
+        deadline: Option<u64>,
+    },
+
+    /// Factory only, linearly moves the amplification coefficient to `future_amp`
+    RampAmp {
+        future_amp: u64,
+        future_time: u64,
+    },
+    /// Factory only, freezes the amplification coefficient at its current value
+    StopRampAmp {},
+    /// Factory only, pauses or resumes trading
+    UpdateStatus {
+        status: PairStatus,
+    },
+    /// Factory only, sets the dynamic fee, `None` restores the flat fee tier
+    UpdateDynamicFee {
+        dynamic_fee: Option<DynamicFee>,
+    },
+    /// Factory only, replaces the contracts notified with [`SwapHookMsg`] after
+    /// each swap
+    UpdateSwapHooks {
+        hooks: Vec<String>,
+    },
+    /// Factory only, turns the batch auction mode on, `None` turns it off. A
+    /// batch already queued still settles.
+    UpdateBatchAuction {
+        batch_auction: Option<BatchAuction>,
+    },
+    /// Factory only, sets the circuit breaker, `None` removes it. Either way
+    /// the current window is closed.
+    UpdateCircuitBreaker {
+        circuit_breaker: Option<CircuitBreaker>,
+    },
+    /// Factory only, closes the current window
+    ResetCircuitBreaker {},
+    /// Factory only, sets the minimum LP holding period, `None` removes it
+    UpdateHoldingPeriod {
+        holding_period: Option<HoldingPeriod>,
+    },
+    /// Record the sender's LP balance beyond its recorded deposit as
+    /// deposited now, so LP received by transfer starts maturing
+    RecordLpDeposit {},
+    /// Allowlist manager or factory only, adds and removes addresses of a
+    /// permissioned pair and sets whether swaps are restricted
+    UpdateAllowlist {
+        add: Vec<String>,
+        remove: Vec<String>,
+        restrict_swaps: Option<bool>,
+    },
+
+    /// Lend `asset` out of the reserves to `to` (the sender by default), which
+    /// is called back with [`FlashSwapCallbackMsg`] and has to pay the pair back
+    /// before the transaction ends
+    FlashSwap {
+        asset: Asset,
+        to: Option<String>,
+        msg: Binary,
+    },
+
+    /// Send the balances held above the reserves to `to`, the sender by default
+    Skim {
+        to: Option<String>,
+    },
+    /// Factory only, sets the reserves to the balances of the pair
+    Sync {},
+    /// Add a native asset to the reserves without minting LP tokens
+    Donate {
+        asset: Asset,
+    },
+    /// Stage the sent native funds as a pending deposit, provided along with
+    /// the sender's next CW20 `provide_liquidity` hook
+    Deposit {},
+    /// Send the sender's pending deposit back
+    RefundDeposit {},
+    /// Clear the queued swaps of a closed batch at a single price, anyone can
+    /// settle it
+    SettleBatch {},
+}
+
+/// Callback executed on the receiver of a flash swap, after the borrowed asset
+/// has been sent. Repaying `repay_amount` of the borrowed asset always settles
+/// the loan, repayments must be transfers as the pair rejects messages until then.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashSwapCallbackMsg {
+    FlashSwapCallback {
+        initiator: String,
+        asset: Asset,
+        repay_amount: Uint128,
+        msg: Binary,
+    },
+}
+
+/// Message sent to each swap hook of a pair after a swap. Hooks run with a gas
+/// limit and a failing hook is ignored, so they cannot block swaps.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapHookMsg {
+    SwapHook {
+        sender: String,
+        receiver: String,
+        offer_asset: Asset,
+        return_asset: Asset,
+        spread_amount: Uint128,
+        /// Commission taken from the returned asset, including the burn and fee
+        /// wallet shares. Flash swaps charge it on the offer asset.
+        commission_amount: Uint128,
+        burn_amount: Uint128,
+        fee_wallet_amount: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -53,7 +338,27 @@ pub enum Cw20HookMsg {
         max_spread: Option<Decimal>,
         to: Option<String>,
         deadline: Option<u64>,
+        /// Address the swap is made for, its fee discount applies when the
+        /// swap is sent by the factory's router
+        trader: Option<String>,
+    },
+    /// Provide liquidity with the sent token only
+    ZapIn {
+        min_lp: Option<Uint128>,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Add the sent token to the reserves without minting LP tokens
+    Donate {},
+    /// Provide liquidity with the sent token and the sender's pending deposit,
+    /// the unused part of both is sent back
+    ProvideLiquidity {
+        receiver: Option<String>,
+        deadline: Option<u64>,
+        slippage_tolerance: Option<Decimal>,
     },
+    /// Stage the sent token as a pending deposit, for pairs of two CW20 tokens
+    Deposit {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -61,8 +366,78 @@ pub enum Cw20HookMsg {
 pub enum QueryMsg {
     Pair {},
     Pool {},
-    Simulation { offer_asset: Asset },
-    ReverseSimulation { ask_asset: Asset },
+    /// Swap of `offer_asset`, with the fee discount of `trader` if given
+    Simulation {
+        offer_asset: Asset,
+        trader: Option<String>,
+    },
+    /// Offer needed for `ask_asset`, with the fee discount of `trader` if given
+    ReverseSimulation {
+        ask_asset: Asset,
+        trader: Option<String>,
+    },
+    /// Amplification coefficient of a stable swap pair
+    Amp {},
+    /// Time-weighted average price of `asset` over the last `window_seconds`
+    Twap {
+        asset: AssetInfo,
+        window_seconds: u64,
+    },
+    /// Asset weights of a weighted pair
+    Weights {},
+    /// Dynamic fee settings and the commission a swap would pay now
+    DynamicFee {},
+    /// Trading totals since instantiation and the epochs after `start_after`
+    Stats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Marginal price of `asset` quoted in the other asset at the current reserves
+    SpotPrice {
+        asset: AssetInfo,
+    },
+    /// Amounts of `asset` a swap has to offer to lower its spot price by each
+    /// of the `price_impacts`
+    Depth {
+        asset: AssetInfo,
+        price_impacts: Vec<Decimal>,
+    },
+    /// Outcome of `provide_liquidity` with `assets` at the current reserves
+    SimulateProvide {
+        assets: [Asset; 2],
+    },
+    /// Assets `withdraw_liquidity` refunds for `amount` of LP
+    SimulateWithdraw {
+        amount: Uint128,
+    },
+    /// Deposit staged by `address` for a CW20 `provide_liquidity` hook
+    PendingDeposit {
+        address: String,
+    },
+    /// Contracts notified after each swap
+    SwapHooks {},
+    /// Batch auction settings and the batch being queued
+    BatchAuction {},
+    /// Launch limits of the pair and whether they still apply
+    LaunchProtection {},
+    /// Circuit breaker thresholds and the state of the current window
+    CircuitBreaker {},
+    /// Minimum LP holding period and when the LP supply as a whole matures
+    HoldingPeriod {},
+    /// LP deposit recorded for `address` under the holding period
+    LpDeposit {
+        address: String,
+    },
+    /// Allowlist of a permissioned pair, addresses after `start_after`
+    Allowlist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Swaps queued in the current batch, by order id
+    BatchOrders {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -78,6 +453,8 @@ pub struct SimulationResponse {
     pub return_amount: Uint128,
     pub spread_amount: Uint128,
     pub commission_amount: Uint128,
+    /// Commission rate of the swap in bps
+    pub commission_bps: u16,
 }
 
 /// ReverseSimulationResponse returns reverse swap simulation response
@@ -86,8 +463,187 @@ pub struct ReverseSimulationResponse {
     pub offer_amount: Uint128,
     pub spread_amount: Uint128,
     pub commission_amount: Uint128,
+    /// Commission rate of the swap in bps
+    pub commission_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AmpResponse {
+    /// Coefficient at the current block time
+    pub amp: u64,
+    /// Coefficient the ramp ends at, equal to `amp` when not ramping
+    pub future_amp: u64,
+    pub future_time: u64,
+}
+
+/// WeightsResponse returns the asset weights in the pair's asset order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WeightsResponse {
+    pub weights: [Decimal; 2],
+}
+
+/// DynamicFeeResponse returns the dynamic fee of a pair, `None` when it charges
+/// its fee tier, and the commission in bps a swap at the current reserves pays
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DynamicFeeResponse {
+    pub dynamic_fee: Option<DynamicFee>,
+    pub commission_bps: u16,
+}
+
+/// StatsResponse returns the trading totals of a pair and the epochs with swaps,
+/// oldest first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StatsResponse {
+    pub asset_infos: [AssetInfo; 2],
+    pub total: TradingStats,
+    pub epochs: Vec<EpochStats>,
+}
+
+/// TwapResponse returns the average price of an asset quoted in the other
+/// asset of the pair, adjusted for both assets' decimals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TwapResponse {
+    pub asset: AssetInfo,
+    pub price: Decimal256,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// SpotPriceResponse returns the marginal price of an asset quoted in the
+/// other asset of the pair, adjusted for both assets' decimals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SpotPriceResponse {
+    pub asset: AssetInfo,
+    pub price: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DepthLevel {
+    /// Relative drop of the offer asset's spot price, 0.01 for 1%
+    pub price_impact: Decimal,
+    /// Offer amount that causes it, before commission
+    pub offer_amount: Uint128,
 }
 
-/// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct DepthResponse {
+    pub asset: AssetInfo,
+    /// One level per requested price impact, in the requested order
+    pub levels: Vec<DepthLevel>,
+}
+
+/// SimulateProvideResponse returns the LP share a provision mints and what it
+/// leaves with the sender, in the pair's asset order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateProvideResponse {
+    /// LP minted to the receiver, net of the minimum liquidity locked by the
+    /// first provision
+    pub share: Uint128,
+    /// Native amounts sent back, CW20 tokens are only pulled up to the amount
+    /// the pool takes and are never refunded
+    pub refund_assets: [Asset; 2],
+    /// Largest refunded fraction of a deposit, the lowest `slippage_tolerance`
+    /// the provision passes
+    pub slippage: Decimal,
+}
+
+/// SimulateWithdrawResponse returns the assets a withdrawal refunds, in the
+/// pair's asset order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateWithdrawResponse {
+    pub refund_assets: [Asset; 2],
+}
+
+/// PendingDepositResponse returns a staged deposit in the pair's asset order,
+/// zero amounts when there is none
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingDepositResponse {
+    pub assets: [Asset; 2],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SwapHooksResponse {
+    pub hooks: Vec<String>,
+}
+
+/// BatchAuctionResponse returns the batch auction mode of a pair, `None` when
+/// swaps execute immediately, and the batch being queued
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchAuctionResponse {
+    pub batch_auction: Option<BatchAuction>,
+    /// First block the queued batch can be settled at, `None` without orders
+    pub settle_height: Option<u64>,
+    pub order_count: u64,
+    /// Queued offers in the pair's asset order
+    pub offer_assets: [Asset; 2],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchOrder {
+    pub id: u64,
+    pub sender: String,
+    pub receiver: String,
+    pub offer_asset: Asset,
+    pub belief_price: Option<Decimal>,
+    pub max_spread: Option<Decimal>,
+    pub deadline: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchOrdersResponse {
+    pub orders: Vec<BatchOrder>,
+}
+
+/// LaunchProtectionResponse returns the launch limits a pair was created with,
+/// `None` without any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LaunchProtectionResponse {
+    pub launch_protection: Option<LaunchProtection>,
+    /// False once the protection period is over
+    pub active: bool,
+}
+
+/// CircuitBreakerResponse returns the circuit breaker of a pair, `None`
+/// without one, and the window swaps are measured over
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CircuitBreakerResponse {
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// End of the current window, `None` when no window is open
+    pub window_end: Option<u64>,
+    /// Relative fall of the price of each asset since the window start, in
+    /// the pair's asset order
+    pub price_drops: [Decimal; 2],
+    /// Net amounts swapped out of the reserves in the pair's asset order
+    pub outflows: [Uint128; 2],
+}
+
+/// HoldingPeriodResponse returns the minimum LP holding period of a pair,
+/// `None` without one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HoldingPeriodResponse {
+    pub holding_period: Option<HoldingPeriod>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LpDepositResponse {
+    /// LP minted to the address under the holding period and not withdrawn
+    pub amount: Uint128,
+    /// When the deposit matures, `None` without a deposit or holding period
+    pub matures_at: Option<u64>,
+}
+
+/// AllowlistResponse returns the allowlist settings of a permissioned pair and
+/// a page of its addresses
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AllowlistResponse {
+    pub manager: Option<String>,
+    pub restrict_swaps: bool,
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Factory of the pair, stored by pairs instantiated before the factory
+    /// address was kept, which can't migrate without it
+    pub factory: Option<String>,
+}
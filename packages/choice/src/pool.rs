@@ -0,0 +1,95 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{Asset, AssetInfo};
+
+use cosmwasm_std::{Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+/// Fewest assets a multi-asset pool may hold, two asset markets are pairs
+pub const MIN_POOL_ASSETS: usize = 3;
+
+/// Most assets a multi-asset pool may hold
+pub const MAX_POOL_ASSETS: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Asset infos
+    pub asset_infos: Vec<AssetInfo>,
+    pub asset_decimals: Vec<u8>,
+
+    pub burn_address: String,
+    pub fee_wallet_address: String,
+    /// Swap commission in basis points
+    pub commission_bps: u16,
+    /// StableSwap amplification coefficient
+    pub amp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+    /// Provide any subset of the pool assets, deposits away from the pool
+    /// balance pay an imbalance fee
+    ProvideLiquidity {
+        assets: Vec<Asset>,
+        min_lp: Option<Uint128>,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Withdraw every pool asset in proportion to the burnt share
+    WithdrawLiquidity {
+        amount: Uint128,
+        min_assets: Option<Vec<Asset>>,
+        deadline: Option<u64>,
+    },
+    /// Swap an offer asset to `ask_asset_info`
+    Swap {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Sell the sent token for `ask_asset_info`
+    Swap {
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    PoolInfo {},
+    Pool {},
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    },
+    ReverseSimulation {
+        offer_asset_info: AssetInfo,
+        ask_asset: Asset,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PoolResponse {
+    pub assets: Vec<Asset>,
+    pub total_share: Uint128,
+}
+
+/// We currently take no arguments for migrations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {}
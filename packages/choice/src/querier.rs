@@ -1,6 +1,12 @@
-use crate::asset::{Asset, AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
-use crate::pair::{QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
+use crate::asset::{Asset, AssetInfo, PairInfo, PoolInfo};
+use crate::factory::{
+    ConfigResponse, FeeDiscountResponse, FeeSplit, NativeTokenDecimalsResponse,
+    QueryMsg as FactoryQueryMsg,
+};
+use crate::pair::{
+    QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse, StatsResponse,
+};
+use crate::pool::QueryMsg as PoolQueryMsg;
 
 use injective_cosmwasm::querier::InjectiveQuerier;
 use injective_cosmwasm::query::InjectiveQueryWrapper;
@@ -9,7 +15,7 @@ use injective_cosmwasm::tokenfactory::response::{
 };
 
 use cosmwasm_std::{
-    to_json_binary, Addr, BalanceResponse, BankQuery, Coin, CustomQuery, QuerierWrapper,
+    to_json_binary, Addr, BalanceResponse, BankQuery, Coin, CustomQuery, Decimal, QuerierWrapper,
     QueryRequest, StdResult, Uint128, WasmQuery,
 };
 
@@ -95,24 +101,73 @@ pub fn query_pair_info<Q: CustomQuery>(
     querier: &QuerierWrapper<Q>,
     factory_contract: Addr,
     asset_infos: &[AssetInfo; 2],
+    commission_bps: Option<u16>,
 ) -> StdResult<PairInfo> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: factory_contract.to_string(),
         msg: to_json_binary(&FactoryQueryMsg::Pair {
             asset_infos: asset_infos.clone(),
+            commission_bps,
+        })?,
+    }))
+}
+
+pub fn query_fee_split<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+    pair_contract: Addr,
+) -> StdResult<FeeSplit> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::FeeSplit {
+            pair: pair_contract.to_string(),
         })?,
     }))
 }
 
+/// Router set in the factory config, whose swaps are made for the `trader` it
+/// forwards
+pub fn query_factory_router<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+) -> StdResult<Option<String>> {
+    let config: ConfigResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::Config {})?,
+    }))?;
+
+    Ok(config.router)
+}
+
+/// Fee discount of a swap sent by `sender` for `trader`
+pub fn query_fee_discount<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+    sender: &Addr,
+    trader: Option<String>,
+) -> StdResult<Decimal> {
+    let res: FeeDiscountResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::FeeDiscount {
+            sender: sender.to_string(),
+            trader,
+        })?,
+    }))?;
+
+    Ok(res.discount)
+}
+
 pub fn simulate<Q: CustomQuery>(
     querier: &QuerierWrapper<Q>,
     pair_contract: Addr,
     offer_asset: &Asset,
+    trader: Option<String>,
 ) -> StdResult<SimulationResponse> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: pair_contract.to_string(),
         msg: to_json_binary(&PairQueryMsg::Simulation {
             offer_asset: offer_asset.clone(),
+            trader,
         })?,
     }))
 }
@@ -121,11 +176,13 @@ pub fn reverse_simulate<Q: CustomQuery>(
     querier: &QuerierWrapper<Q>,
     pair_contract: Addr,
     ask_asset: &Asset,
+    trader: Option<String>,
 ) -> StdResult<ReverseSimulationResponse> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: pair_contract.to_string(),
         msg: to_json_binary(&PairQueryMsg::ReverseSimulation {
             ask_asset: ask_asset.clone(),
+            trader,
         })?,
     }))
 }
@@ -141,3 +198,70 @@ pub fn query_pair_info_from_pair<Q: CustomQuery>(
 
     Ok(pair_info)
 }
+
+pub fn query_pair_stats<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    pair_contract: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<StatsResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pair_contract.to_string(),
+        msg: to_json_binary(&PairQueryMsg::Stats { start_after, limit })?,
+    }))
+}
+
+pub fn query_pool_info<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+    asset_infos: &[AssetInfo],
+    commission_bps: u16,
+) -> StdResult<PoolInfo> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::Pool {
+            asset_infos: asset_infos.to_vec(),
+            commission_bps: Some(commission_bps),
+        })?,
+    }))
+}
+
+pub fn query_pool_info_from_pool<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    pool_contract: Addr,
+) -> StdResult<PoolInfo> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pool_contract.to_string(),
+        msg: to_json_binary(&PoolQueryMsg::PoolInfo {})?,
+    }))
+}
+
+pub fn simulate_pool<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    pool_contract: Addr,
+    offer_asset: &Asset,
+    ask_asset_info: &AssetInfo,
+) -> StdResult<SimulationResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pool_contract.to_string(),
+        msg: to_json_binary(&PoolQueryMsg::Simulation {
+            offer_asset: offer_asset.clone(),
+            ask_asset_info: ask_asset_info.clone(),
+        })?,
+    }))
+}
+
+pub fn reverse_simulate_pool<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    pool_contract: Addr,
+    offer_asset_info: &AssetInfo,
+    ask_asset: &Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pool_contract.to_string(),
+        msg: to_json_binary(&PoolQueryMsg::ReverseSimulation {
+            offer_asset_info: offer_asset_info.clone(),
+            ask_asset: ask_asset.clone(),
+        })?,
+    }))
+}
@@ -17,13 +17,33 @@ pub enum SwapOperation {
     Choice {
         offer_asset_info: AssetInfo,
         ask_asset_info: AssetInfo,
+        /// Fee tier of the pair, the default tier when omitted
+        commission_bps: Option<u16>,
+    },
+    /// Swap between two assets of a multi-asset pool registered in the factory
+    Pool {
+        pool_addr: String,
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
     },
 }
 
 impl SwapOperation {
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::Choice {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+            SwapOperation::Pool {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::Choice { ask_asset_info, .. } => ask_asset_info.clone(),
+            SwapOperation::Pool { ask_asset_info, .. } => ask_asset_info.clone(),
         }
     }
 }
@@ -39,6 +59,14 @@ pub enum ExecuteMsg {
         to: Option<String>,
         deadline: Option<u64>,
     },
+    /// Swap the sent offer asset for exactly `ask_amount` of the last
+    /// operation's ask asset, refunding what the operations do not need
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
 
     /// Internal use
     /// Swap all offer tokens to ask token
@@ -46,6 +74,8 @@ pub enum ExecuteMsg {
         operation: SwapOperation,
         to: Option<String>,
         deadline: Option<u64>,
+        /// Sender of the operations, forwarded to pairs for its fee discount
+        trader: Option<String>,
     },
     /// Internal use
     /// Check the swap amount is exceed minimum_receive
@@ -66,6 +96,12 @@ pub enum Cw20HookMsg {
         to: Option<String>,
         deadline: Option<u64>,
     },
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -75,10 +111,14 @@ pub enum QueryMsg {
     SimulateSwapOperations {
         offer_amount: Uint128,
         operations: Vec<SwapOperation>,
+        /// Applies the fee discount of `trader` to the pair swaps
+        trader: Option<String>,
     },
     ReverseSimulateSwapOperations {
         ask_amount: Uint128,
         operations: Vec<SwapOperation>,
+        /// Applies the fee discount of `trader` to the pair swaps
+        trader: Option<String>,
     },
 }
 
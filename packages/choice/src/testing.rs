@@ -1,11 +1,13 @@
 use crate::asset::{Asset, AssetInfo, AssetInfoRaw, AssetRaw, PairInfo};
+use crate::factory::FeeSplit;
 use crate::mock_querier::mock_dependencies;
+use crate::pair::{PairStatus, PairType};
 use crate::querier::{query_balance, query_pair_info, query_token_balance, query_token_info};
 
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
 use cosmwasm_std::{
-    coin, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, MessageInfo, StdError, SubMsg,
-    Uint128, WasmMsg,
+    coin, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, MessageInfo, StdError,
+    SubMsg, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
 
@@ -391,6 +393,10 @@ fn query_choice_pair_contract() {
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burn0000").to_string(),
                 fee_wallet_address: deps.api.addr_make("fee_wallet_address0000").to_string(),
+                commission_bps: 30,
+                fee_split: FeeSplit::default(),
+                pair_type: PairType::ConstantProduct,
+                status: PairStatus::Active,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -407,6 +413,7 @@ fn query_choice_pair_contract() {
                 denom: "uusd".to_string(),
             },
         ],
+        None,
     )
     .unwrap();
 
@@ -419,3 +426,35 @@ fn query_choice_pair_contract() {
         deps.api.addr_make("liquidity0000"),
     );
 }
+
+#[test]
+fn fee_split_validate() {
+    FeeSplit::default().validate().unwrap();
+    FeeSplit {
+        burn: Decimal::percent(40),
+        fee_wallet: Decimal::percent(60),
+    }
+    .validate()
+    .unwrap();
+
+    assert_eq!(
+        FeeSplit {
+            burn: Decimal::percent(50),
+            fee_wallet: Decimal::percent(51),
+        }
+        .validate(),
+        Err(StdError::generic_err(
+            "fee split shares must not exceed 100%"
+        ))
+    );
+    assert_eq!(
+        FeeSplit {
+            burn: Decimal::MAX,
+            fee_wallet: Decimal::one(),
+        }
+        .validate(),
+        Err(StdError::generic_err(
+            "fee split shares must not exceed 100%"
+        ))
+    );
+}